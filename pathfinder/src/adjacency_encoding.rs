@@ -0,0 +1,309 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::kv_store::{Db, Tree};
+
+// Compact adjacency lists start with these bytes followed by the format version.
+// Legacy adjacency lists are bincode-serialized vectors starting with their length as u64 in little endian,
+// so a legacy list starting with these bytes would need at least 0x414650FF, i.e., about 1.1 billion elements.
+const MAGIC: [u8; 4] = [0xFF, b'P', b'F', b'A'];
+
+/// The version of the compact adjacency list format written by this version of the pathfinder.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// An adjacency list as pairs of numeric property and adjacent entity IDs.
+pub type AdjacencyIds = Vec<(u32, u32)>;
+
+// The interned names held in memory; the names are shared by both directions.
+#[derive(Default)]
+struct InternedNames {
+    names: Vec<Rc<str>>,
+    ids: HashMap<Rc<str>, u32>,
+}
+
+/// Maps entity and property IDs to dense numeric IDs and back.
+/// Both directions are persisted as trees of the adjacency list store and held in memory,
+/// so reading adjacency lists does not require any lookups in the store.
+pub struct IdInterner {
+    ids: Tree,
+    names: Tree,
+    interned: RefCell<InternedNames>,
+}
+
+impl IdInterner {
+    /// Opens the interner trees of a store and loads the interned names into memory.
+    /// Names whose numeric ID was not persisted, e.g., due to a crash while interning, are restored.
+    pub fn open(db: &Db) -> PathfinderResult<Self> {
+        let ids = db.open_tree("interned_ids")?;
+        let names = db.open_tree("interned_names")?;
+        let mut interned = InternedNames::default();
+
+        for entry in names.iter() {
+            let (key, name) = entry?;
+            let id = decode_id(&key)?;
+
+            // IDs are assigned consecutively starting at 0
+            if id as usize != interned.names.len() {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "Interned ID {id} follows {} interned names.",
+                    interned.names.len()
+                )));
+            }
+
+            let name: Rc<str> = Rc::from(std::str::from_utf8(&name)?);
+            interned.ids.insert(name.clone(), id);
+            interned.names.push(name);
+        }
+
+        if ids.len()? != interned.names.len() {
+            for (id, name) in interned.names.iter().enumerate() {
+                ids.insert(name.as_bytes(), &(id as u32).to_be_bytes())?;
+            }
+        }
+
+        Ok(Self {
+            ids,
+            names,
+            interned: RefCell::new(interned),
+        })
+    }
+
+    /// Returns the numeric ID of an entity or property, assigning the next free ID if it is new.
+    pub fn intern(&self, name: &str) -> PathfinderResult<u32> {
+        let mut interned = self.interned.borrow_mut();
+        if let Some(&id) = interned.ids.get(name) {
+            return Ok(id);
+        }
+
+        // the next free ID is taken from memory, so it cannot be assigned twice
+        // the names are written first as the IDs are restored from them when the interner is opened
+        let id = interned.names.len() as u32;
+        self.names.insert(id.to_be_bytes(), name)?;
+        self.ids.insert(name, &id.to_be_bytes())?;
+
+        let name: Rc<str> = Rc::from(name);
+        interned.ids.insert(name.clone(), id);
+        interned.names.push(name);
+
+        Ok(id)
+    }
+
+    /// Returns all interned entity and property IDs ordered by their numeric IDs.
+    pub fn names(&self) -> Vec<String> {
+        self.interned
+            .borrow()
+            .names
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Returns the numeric ID of an entity or property without assigning one if it is new.
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.interned.borrow().ids.get(name).copied()
+    }
+
+    /// Returns the entity or property ID of a numeric ID.
+    pub fn name(&self, id: u32) -> PathfinderResult<String> {
+        match self.interned.borrow().names.get(id as usize) {
+            Some(name) => Ok(name.to_string()),
+            None => Err(PathfinderError::DataConsistency(format!(
+                "Numeric ID {id} is not interned."
+            ))),
+        }
+    }
+}
+
+// Decodes a numeric ID stored as big endian bytes, so the IDs are ordered numerically in the tree.
+fn decode_id(bytes: &[u8]) -> PathfinderResult<u32> {
+    let bytes = bytes.try_into().map_err(|_| {
+        PathfinderError::DataConsistency(format!("Interned ID has {} bytes.", bytes.len()))
+    })?;
+
+    Ok(u32::from_be_bytes(bytes))
+}
+
+/// Checks whether a stored adjacency list uses the compact format.
+pub fn is_compact(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Encodes pairs of numeric property and entity IDs.
+/// The pairs are sorted and deduplicated such that properties and the entities of each property are delta encoded.
+pub fn encode(pairs: &mut AdjacencyIds) -> Vec<u8> {
+    pairs.sort_unstable();
+    pairs.dedup();
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(FORMAT_VERSION);
+    write_varint(&mut bytes, pairs.len() as u32);
+
+    let (mut previous_prop, mut previous_entity) = (0, 0);
+
+    for (index, &(prop, entity)) in pairs.iter().enumerate() {
+        let prop_delta = prop - previous_prop;
+        write_varint(&mut bytes, prop_delta);
+
+        // entities are only delta encoded relative to the previous entity of the same property
+        if index > 0 && prop_delta == 0 {
+            write_varint(&mut bytes, entity - previous_entity);
+        } else {
+            write_varint(&mut bytes, entity);
+        }
+
+        (previous_prop, previous_entity) = (prop, entity);
+    }
+
+    bytes
+}
+
+/// Decodes an adjacency list in the compact format into pairs of numeric property and entity IDs.
+/// Returns None if the list uses the legacy format and an error if the list is truncated or of an unsupported version.
+pub fn decode(bytes: &[u8]) -> PathfinderResult<Option<AdjacencyIds>> {
+    let Some(bytes) = bytes.strip_prefix(&MAGIC) else {
+        return Ok(None);
+    };
+    let (&version, mut bytes) = bytes.split_first().ok_or_else(truncated)?;
+
+    if version != FORMAT_VERSION {
+        return Err(PathfinderError::DataConsistency(format!(
+            "Adjacency list format version {version} is not supported."
+        )));
+    }
+
+    let count = read_varint(&mut bytes)?;
+    let mut pairs = Vec::with_capacity(count as usize);
+    let (mut previous_prop, mut previous_entity) = (0, 0);
+
+    for index in 0..count {
+        let prop_delta = read_varint(&mut bytes)?;
+        let prop = previous_prop + prop_delta;
+
+        let entity = if index > 0 && prop_delta == 0 {
+            previous_entity + read_varint(&mut bytes)?
+        } else {
+            read_varint(&mut bytes)?
+        };
+
+        pairs.push((prop, entity));
+        (previous_prop, previous_entity) = (prop, entity);
+    }
+
+    Ok(Some(pairs))
+}
+
+// Writes an unsigned LEB128 varint.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// Reads an unsigned LEB128 varint and advances the slice.
+fn read_varint(bytes: &mut &[u8]) -> PathfinderResult<u32> {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let (&byte, rest) = bytes.split_first().ok_or_else(truncated)?;
+        *bytes = rest;
+
+        value |= ((byte & 0x7F) as u32) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+        shift += 7;
+
+        // a u32 takes at most five bytes
+        if shift >= 32 {
+            return Err(PathfinderError::DataConsistency(
+                "Adjacency list contains an overlong varint.".to_owned(),
+            ));
+        }
+    }
+}
+
+fn truncated() -> PathfinderError {
+    PathfinderError::DataConsistency("Adjacency list is truncated.".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv_store;
+
+    #[test]
+    fn lists_are_sorted_deduplicated_and_round_tripped() {
+        let mut pairs = vec![(7, 300), (2, 5), (7, 12), (2, 5), (0, u32::MAX)];
+
+        let bytes = encode(&mut pairs);
+
+        assert!(is_compact(&bytes));
+        assert_eq!(pairs, [(0, u32::MAX), (2, 5), (7, 12), (7, 300)]);
+        assert_eq!(decode(&bytes).unwrap(), Some(pairs));
+        assert_eq!(decode(&encode(&mut vec![])).unwrap(), Some(vec![]));
+    }
+
+    #[test]
+    fn legacy_lists_are_not_decoded() {
+        let legacy = bincode::serialize(&vec!["P31-Q5".to_owned()]).unwrap();
+
+        assert!(!is_compact(&legacy));
+        assert_eq!(decode(&legacy).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_lists_and_unknown_versions_are_rejected() {
+        let bytes = encode(&mut vec![(1, 2), (3, 4)]);
+
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode(&MAGIC).is_err());
+
+        let mut future_version = bytes.clone();
+        future_version[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert!(decode(&future_version).is_err());
+    }
+
+    #[test]
+    fn varints_are_round_tripped() {
+        for value in [0, 1, 0x7F, 0x80, 0x3FFF, 0x4000, u32::MAX] {
+            let mut bytes = vec![];
+            write_varint(&mut bytes, value);
+
+            let mut slice = bytes.as_slice();
+            assert_eq!(read_varint(&mut slice).unwrap(), value);
+            assert!(slice.is_empty());
+        }
+
+        let mut overlong: &[u8] = &[0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        assert!(read_varint(&mut overlong).is_err());
+    }
+
+    #[test]
+    fn interned_ids_are_consecutive_and_persisted() {
+        let db = kv_store::open_temporary();
+        let interner = IdInterner::open(&db).unwrap();
+
+        assert_eq!(interner.intern("Q42").unwrap(), 0);
+        assert_eq!(interner.intern("P31").unwrap(), 1);
+        assert_eq!(interner.intern("Q42").unwrap(), 0);
+        assert_eq!(interner.name(1).unwrap(), "P31");
+        assert!(interner.name(2).is_err());
+
+        // a name whose ID was not persisted is restored when the interner is opened again
+        interner.names.insert(2u32.to_be_bytes(), "Q5").unwrap();
+        let reopened = IdInterner::open(&db).unwrap();
+
+        assert_eq!(reopened.names(), ["Q42", "P31", "Q5"]);
+        assert_eq!(reopened.intern("Q5").unwrap(), 2);
+        assert_eq!(
+            reopened.ids.get("Q5").unwrap().as_deref(),
+            Some(&[0, 0, 0, 2][..])
+        );
+        assert_eq!(reopened.intern("Q1").unwrap(), 3);
+    }
+}
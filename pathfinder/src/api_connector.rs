@@ -1,8 +1,46 @@
 use log::debug;
-use serde_json::{Map, Value};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::str::FromStr;
-
-use cached::proc_macro::cached;
+use std::time::Duration;
+
+use crate::error::{PathfinderError, PathfinderResult};
+
+/// The maximum number of hops of adjacent entities fetched per request; smaller depths are tried if a request fails.
+pub const MAX_ADJACENCY_DEPTH: usize = 2;
+
+/// The label, description, and adjacency mappings returned by the Wikidata API.
+pub type AdjacentEntityData = (
+    HashMap<String, String>,
+    HashMap<String, String>,
+    HashMap<String, String>,
+    HashMap<String, String>,
+    HashMap<String, Vec<String>>,
+);
+
+/// The literal values and property label and description mappings returned by the Wikidata API.
+pub type AdjacentLiteralData = (
+    Vec<(String, String, String)>,
+    HashMap<String, String>,
+    HashMap<String, String>,
+);
+
+/// Labels and descriptions per entity and language.
+pub type LanguageValues = (
+    HashMap<String, HashMap<String, String>>,
+    HashMap<String, HashMap<String, String>>,
+);
+
+// A literal value as returned by the adjacent literals endpoint of the Wikidata API
+#[derive(Deserialize)]
+struct LiteralValue {
+    predicate: String,
+    #[serde(rename = "type")]
+    datatype: String,
+    value: String,
+}
 
 /// A connector for interacting with APIs
 pub struct ApiConnector {
@@ -10,6 +48,8 @@ pub struct ApiConnector {
     wembed_api: String,
     /// The base URL of the Wikidata API
     wikidata_api: String,
+    /// The HTTP client shared by all requests, which aborts requests exceeding the timeout
+    client: reqwest::blocking::Client,
 }
 
 impl ApiConnector {
@@ -17,13 +57,23 @@ impl ApiConnector {
     /// # Arguments
     /// * `wembed_api` - The base URL of the word embedding API
     /// * `wikidata_api` - The base URL of the Wikidata API
+    /// * `timeout` - The maximum duration of a request including the response, after which it fails as network error
     /// # Returns
-    /// * The instance
-    pub fn new(wembed_api: String, wikidata_api: String) -> Self {
-        Self {
+    /// * The instance or an error if the HTTP client cannot be created
+    pub fn new(
+        wembed_api: String,
+        wikidata_api: String,
+        timeout: Duration,
+    ) -> PathfinderResult<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()?;
+
+        Ok(Self {
             wembed_api,
             wikidata_api,
-        }
+            client,
+        })
     }
 
     /// Fetches the ids, labels, and descriptions of entities adjacent to an entity from our Wikidata API.
@@ -36,143 +86,170 @@ impl ApiConnector {
     /// * A mapping between property IDs and labels
     /// * A mapping between property IDs and descriptions
     /// * A mapping between entity IDs and lists with IDs of adjacent entities
-    pub fn fetch_adjacent_entity_data(
-        &self,
-        entity: &str,
-    ) -> (
-        Map<String, Value>,
-        Map<String, Value>,
-        Map<String, Value>,
-        Map<String, Value>,
-        Map<String, Value>,
-    ) {
-        let mut json = Default::default();
-
-        // closure for making the request using a depth parameter
-        let mut make_request = |d: i8| -> Result<(), reqwest::Error> {
-            let url = format!(
-                "{}/adjacent_entities?entity={}&depth={}",
-                self.wikidata_api, entity, d
-            );
-            let response = reqwest::blocking::get(url)?.text()?;
-            json = Value::from_str(&response).unwrap();
-            Ok(())
-        };
+    /// * The error of the request with the smallest depth if the data could not be retrieved with any depth
+    pub fn fetch_adjacent_entity_data(&self, entity: &str) -> PathfinderResult<AdjacentEntityData> {
+        let mut last_error = None;
 
         // try with higher depth first and decrement if it fails
-        for depth in (1..=2).rev() {
+        // the Wikidata API reports failed SPARQL queries, e.g., due to timeouts, via an error field
+        for depth in (1..=MAX_ADJACENCY_DEPTH).rev() {
             debug!(
                 "Attempting to fetch adjacent entities of {} with depth {}",
                 entity, depth
             );
-            let result = make_request(depth);
 
-            if result.is_ok() {
-                result.unwrap();
-                break
+            let url = format!(
+                "{}/adjacent_entities?entity={}&depth={}",
+                self.wikidata_api, entity, depth
+            );
+
+            // a malformed response, e.g., truncated at the higher depth, is retried with a smaller depth as well
+            let data = self.get_json(&url).and_then(|json| {
+                Ok((
+                    decode_field(&json, "q_labels")?,
+                    decode_field(&json, "q_descriptions")?,
+                    decode_field(&json, "p_labels")?,
+                    decode_field(&json, "p_descriptions")?,
+                    decode_field(&json, "adjacent_entities")?,
+                ))
+            });
+
+            match data {
+                Ok(data) => return Ok(data),
+                Err(error) => last_error = Some(error),
             }
         }
 
-        // extract the data for all adjacent entities
-        let adjacent_entities_data = json.get("adjacent_entities").unwrap().as_object().unwrap();
-        let q_label_data = json.get("q_labels").unwrap().as_object().unwrap();
-        let q_desc_data = json.get("q_descriptions").unwrap().as_object().unwrap();
-        let p_label_data = json.get("p_labels").unwrap().as_object().unwrap();
-        let p_desc_data = json.get("p_descriptions").unwrap().as_object().unwrap();
-
-        return (
-            q_label_data.clone(),
-            q_desc_data.clone(),
-            p_label_data.clone(),
-            p_desc_data.clone(),
-            adjacent_entities_data.clone(),
-        );
+        Err(last_error.unwrap())
     }
 
-    /// Fetches the label and the description of an entity.
+    /// Fetches the literal values (dates, coordinates, and external IDs) of an entity from our Wikidata API.
     /// # Arguments
     /// * `entity` - The entity
     /// # Returns
-    /// * The label of the entity
-    /// * The description of the entity
-    pub fn fetch_label_description(&self, entity: &str) -> (String, String) {
-        let url = format!("{}/label_description?entity={}", self.wikidata_api, entity);
-        let response = reqwest::blocking::get(url).unwrap().text().unwrap();
-
-        let mut fetched_label = "";
-        let mut fetched_description = "";
-
-        let parse_result = Value::from_str(&response);
-        if parse_result.is_ok() {
-            let json = parse_result.unwrap();
-            fetched_label = json.get("label").unwrap().as_str().unwrap();
-            fetched_description = json.get("description").unwrap().as_str().unwrap();
-
-            return (fetched_label.to_owned(), fetched_description.to_owned())
+    /// * A list of property IDs, Wikibase datatypes, and raw values
+    /// * A mapping between property IDs and labels
+    /// * A mapping between property IDs and descriptions
+    /// * An error if the request failed or the Wikidata API reported an error
+    pub fn fetch_adjacent_literal_data(
+        &self,
+        entity: &str,
+    ) -> PathfinderResult<AdjacentLiteralData> {
+        let url = format!("{}/adjacent_literals?entity={}", self.wikidata_api, entity);
+        let json = self.get_json(&url)?;
+
+        let literals = decode_field::<Vec<LiteralValue>>(&json, "literals")?
+            .into_iter()
+            .map(|literal| (literal.predicate, literal.datatype, literal.value))
+            .collect();
+
+        Ok((
+            literals,
+            decode_field(&json, "p_labels")?,
+            decode_field(&json, "p_descriptions")?,
+        ))
+    }
+
+    /// Fetches the labels and descriptions of entities in several languages.
+    /// The entities are requested in chunks to keep the URLs short.
+    /// # Arguments
+    /// * `entities` - The entities
+    /// * `languages` - The language codes, e.g., de, en, mul
+    /// # Returns
+    /// * A mapping between entity IDs and their labels per language
+    /// * A mapping between entity IDs and their descriptions per language
+    /// * An error if a request failed or the Wikidata API reported an error
+    pub fn fetch_labels_descriptions(
+        &self,
+        entities: &[String],
+        languages: &[String],
+    ) -> PathfinderResult<LanguageValues> {
+        let mut labels = HashMap::new();
+        let mut descriptions = HashMap::new();
+
+        for chunk in entities.chunks(50) {
+            let url = format!(
+                "{}/labels_descriptions?entities={}&languages={}",
+                self.wikidata_api,
+                chunk.join("|"),
+                languages.join("|")
+            );
+            let json = self.get_json(&url)?;
+
+            for (key, target) in [("labels", &mut labels), ("descriptions", &mut descriptions)] {
+                let parsed: HashMap<String, HashMap<String, String>> = decode_field(&json, key)?;
+                target.extend(parsed);
+            }
         }
 
-        (fetched_label.to_owned(), fetched_description.to_owned())
+        Ok((labels, descriptions))
     }
 
-    /// Fetches the semantic distance between two strings.
-    /// To optimize the runtime, a cached function is called.
+    /// Fetches the entity an entity redirects to, e.g., because both entities were merged.
     /// # Arguments
-    /// * `string_a` - The first string
-    /// * `string_b` - The second string
+    /// * `entity` - The entity
     /// # Returns
-    /// * The semantic distance
-    pub fn fetch_semantic_distance(&self, string_a: &str, string_b: &str) -> f64 {
-        fetch_distance_cached(
-            (&self.wembed_api).to_owned(),
-            string_a.to_owned(),
-            string_b.to_owned(),
-        )
+    /// * The target of the redirect, an empty string if the entity is not redirected
+    /// * An error if the request failed or the Wikidata API reported an error
+    pub fn fetch_redirect(&self, entity: &str) -> PathfinderResult<String> {
+        let url = format!("{}/redirect?entity={}", self.wikidata_api, entity);
+        decode_field(&self.get_json(&url)?, "target")
     }
 
-    /// Fetches the average frequency of one or more properties.
-    /// To optimize the runtime, a cached function is called.
+    /// Fetches the embedding vectors of several texts with a single request to the word embedding API.
     /// # Arguments
-    /// * `props` - The properties
+    /// * `texts` - The texts
     /// # Returns
-    /// * The average prop frequency
-    pub fn fetch_average_prop_frequency(&self, props: &Vec<String>) -> f64 {
-        fetch_frequency_cached((&self.wikidata_api).to_owned(), props.to_owned())
+    /// * The identifier of the embedding model and one vector per text
+    /// * An error if the request failed or the number of vectors differs from the number of texts
+    pub fn fetch_embeddings(&self, texts: &[String]) -> PathfinderResult<(String, Vec<Vec<f32>>)> {
+        let url = format!("{}/embed", self.wembed_api);
+        let body = serde_json::json!({ "texts": texts }).to_string();
+
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()?
+            .text()?;
+        let json = Value::from_str(&response)?;
+
+        let model: String = decode_field(&json, "model")?;
+        let embeddings: Vec<Vec<f32>> = decode_field(&json, "embeddings")?;
+
+        if embeddings.len() != texts.len() {
+            return Err(PathfinderError::DataConsistency(format!(
+                "The Wembed API returned {} vectors for {} texts.",
+                embeddings.len(),
+                texts.len()
+            )));
+        }
+
+        Ok((model, embeddings))
     }
-}
 
-/// A cached function retrieving the semantic distance between two strings via the word embedding API.
-/// The reason for introducing a separate cached function is that functions using the cached procedural macro do not allow &self as a parameter.
-/// # Arguments
-/// * `wembed_api` - The base URL of the word embedding API
-/// * `string_a` - The first string
-/// * `string_b` - The second string
-/// # Returns
-/// * The semantic distance
-#[cached]
-pub fn fetch_distance_cached(wembed_api: String, string_a: String, string_b: String) -> f64 {
-    let url = format!("{}?string_a={}&string_b={}", wembed_api, string_a, string_b);
-
-    let response = reqwest::blocking::get(url).unwrap().text().unwrap();
-    let json = Value::from_str(&response).unwrap();
-    let fetched_distance = json.get("distance").unwrap().as_f64().unwrap();
-
-    fetched_distance
+    // Requests a URL of the Wikidata API and parses the response as JSON.
+    // Errors the Wikidata API reports via an error field are returned as network errors.
+    fn get_json(&self, url: &str) -> PathfinderResult<Value> {
+        let response = self.client.get(url).send()?.text()?;
+        let json = Value::from_str(&response)?;
+
+        match json.get("error") {
+            Some(error) => Err(PathfinderError::Network(format!(
+                "{url} reported an error: {error}"
+            ))),
+            None => Ok(json),
+        }
+    }
 }
 
-/// A cached function retrieving the average frequency of one or more properties via the Wikidata API.
-/// The reason for introducing a separate cached function is that functions using the cached procedural macro do not allow &self as a parameter.
-/// # Arguments
-/// * `wembed_api` - The base URL of the word embedding API
-/// * `props` - The properties
-/// # Returns
-/// * The average prop frequency
-#[cached]
-pub fn fetch_frequency_cached(wikidata_api: String, props: Vec<String>) -> f64 {
-    let url = format!("{wikidata_api}/average_prop_frequency?props={}", props.join("-"));
-
-    let response = reqwest::blocking::get(url).unwrap().text().unwrap();
-    let json = Value::from_str(&response).unwrap();
-    let average_prop_frequency = json.get("average_prop_frequency").unwrap().as_f64().unwrap();
-
-    average_prop_frequency
+// Decodes a field of a JSON response.
+fn decode_field<T: DeserializeOwned>(json: &Value, name: &str) -> PathfinderResult<T> {
+    let value = json
+        .get(name)
+        .ok_or_else(|| PathfinderError::Decode(format!("The response lacks the field {name}.")))?;
+
+    T::deserialize(value)
+        .map_err(|error| PathfinderError::Decode(format!("The field {name} is malformed: {error}")))
 }
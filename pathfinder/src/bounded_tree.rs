@@ -0,0 +1,437 @@
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::kv_store::{Batch, IVec, Tree};
+use log::{info, warn};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+// The share of the maximum number of entries that is kept when a tree is full,
+// so the entries are not scanned for eviction on every insert
+const EVICTION_TARGET: f64 = 0.9;
+
+// The number of recorded accesses kept in memory before they are written to the access tree
+const ACCESS_BUFFER_SIZE: usize = 10_000;
+
+/// How entries are chosen for eviction when a store exceeds its maximum number of entries.
+#[derive(Clone, Copy)]
+pub enum EvictionPolicy {
+    /// Evicts the entries that were read or written the longest time ago
+    LeastRecentlyUsed,
+    /// Evicts the entries that were read or written the fewest times, the least recently used first among equals
+    LeastFrequentlyUsed,
+}
+
+impl EvictionPolicy {
+    /// Parses an eviction policy as specified in the config.toml.
+    pub fn from_config(policy: &str) -> Self {
+        match policy {
+            "lru" => EvictionPolicy::LeastRecentlyUsed,
+            "lfu" => EvictionPolicy::LeastFrequentlyUsed,
+            _ => panic!("Specified eviction policy is not supported."),
+        }
+    }
+}
+
+/// The maximum number of entries of a store and how entries are evicted once it is exceeded.
+#[derive(Clone, Copy)]
+pub struct EvictionConfig {
+    pub max_entries: usize,
+    pub policy: EvictionPolicy,
+}
+
+impl EvictionConfig {
+    /// Creates an EvictionConfig, None if the maximum number of entries is 0, i.e., the store is unbounded.
+    pub fn new(max_entries: usize, policy: EvictionPolicy) -> Option<Self> {
+        (max_entries > 0).then_some(Self {
+            max_entries,
+            policy,
+        })
+    }
+}
+
+// When an entry was last accessed and how often it was accessed
+#[derive(Clone, Copy, Default)]
+struct Access {
+    last: u64,
+    count: u64,
+}
+
+impl Access {
+    fn decode(bytes: &[u8]) -> PathfinderResult<Self> {
+        let invalid = || {
+            PathfinderError::Decode(format!(
+                "A recorded access has {} instead of 16 bytes.",
+                bytes.len()
+            ))
+        };
+        if bytes.len() != 16 {
+            return Err(invalid());
+        }
+        let (last, count) = bytes.split_at(8);
+
+        Ok(Self {
+            last: u64::from_le_bytes(last.try_into().map_err(|_| invalid())?),
+            count: u64::from_le_bytes(count.try_into().map_err(|_| invalid())?),
+        })
+    }
+
+    fn encode(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.last.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+}
+
+/// A tree whose number of entries is bounded by evicting entries according to an EvictionPolicy.
+/// The accesses of the entries are recorded in a second tree; without EvictionConfig, nothing is recorded.
+pub struct BoundedTree {
+    // the name of the tree in log messages
+    name: String,
+    tree: Tree,
+    accesses: Tree,
+    eviction: Option<EvictionConfig>,
+    // the number of entries, which is counted once as sled counts entries by iterating over them
+    len: Cell<usize>,
+    // accesses since the access tree was last written
+    pending: RefCell<HashMap<IVec, Access>>,
+    // a logical clock ordering the accesses, continued from the latest recorded access
+    clock: Cell<u64>,
+}
+
+impl BoundedTree {
+    /// Creates a BoundedTree and evicts entries if the tree exceeds the maximum number of entries already.
+    /// # Arguments
+    /// * `name` - The name of the tree in log messages
+    /// * `tree` - The tree holding the entries
+    /// * `accesses` - The tree recording the accesses of the entries
+    /// * `eviction` - The maximum number of entries and the eviction policy, None for an unbounded tree
+    /// # Returns
+    /// * The instance or an error if the access tree cannot be read or entries cannot be evicted
+    pub fn open(
+        name: &str,
+        tree: Tree,
+        accesses: Tree,
+        eviction: Option<EvictionConfig>,
+    ) -> PathfinderResult<Self> {
+        let (len, clock) = match eviction {
+            Some(_) => {
+                let mut clock = 0;
+                for value in accesses.iter().values() {
+                    clock = clock.max(Access::decode(&value?)?.last);
+                }
+                (tree.len()?, clock)
+            }
+            None => (0, 0),
+        };
+
+        let bounded_tree = Self {
+            name: name.to_owned(),
+            tree,
+            accesses,
+            eviction,
+            len: Cell::new(len),
+            pending: RefCell::new(HashMap::new()),
+            clock: Cell::new(clock),
+        };
+        bounded_tree.evict_if_full()?;
+
+        Ok(bounded_tree)
+    }
+
+    /// Returns the underlying tree, e.g., to iterate over all entries.
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Reads an entry and records the access.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> PathfinderResult<Option<IVec>> {
+        let Some(value) = self.tree.get(key.as_ref())? else {
+            return Ok(None);
+        };
+        self.record_access(key.as_ref())?;
+        Ok(Some(value))
+    }
+
+    /// Writes an entry, records the access, and evicts entries if the tree is full.
+    pub fn insert(&self, key: impl AsRef<[u8]>, value: impl Into<IVec>) -> PathfinderResult<()> {
+        let previous = self.tree.insert(key.as_ref(), value)?;
+
+        if self.eviction.is_some() {
+            if previous.is_none() {
+                self.len.set(self.len.get() + 1);
+            }
+            self.record_access(key.as_ref())?;
+            self.evict_if_full()?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes an entry along with its recorded accesses.
+    pub fn remove(&self, key: impl AsRef<[u8]>) -> PathfinderResult<()> {
+        let previous = self.tree.remove(key.as_ref())?;
+        self.accesses.remove(key.as_ref())?;
+        self.pending.borrow_mut().remove(key.as_ref());
+
+        if previous.is_some() && self.eviction.is_some() {
+            self.len.set(self.len.get() - 1);
+        }
+
+        Ok(())
+    }
+
+    /// Writes a batch of entries, e.g., when importing a store, and evicts entries if the tree is full.
+    /// The entries of a batch are not recorded as accessed, so they are the first to be evicted.
+    pub fn apply_batch(&self, batch: Batch) -> PathfinderResult<()> {
+        self.tree.apply_batch(batch)?;
+
+        if self.eviction.is_some() {
+            self.len.set(self.tree.len()?);
+            self.evict_if_full()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the recorded accesses kept in memory to the access tree.
+    pub fn flush_accesses(&self) -> PathfinderResult<()> {
+        let pending = self.pending.take();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut batch = Batch::default();
+        for (key, access) in pending {
+            let recorded = self
+                .accesses
+                .get(&key)?
+                .map(|value| Access::decode(&value))
+                .transpose()?
+                .unwrap_or_default();
+
+            let access = Access {
+                last: access.last,
+                count: recorded.count + access.count,
+            };
+            batch.insert(key, &access.encode());
+        }
+        self.accesses.apply_batch(batch)?;
+
+        Ok(())
+    }
+
+    fn record_access(&self, key: &[u8]) -> PathfinderResult<()> {
+        if self.eviction.is_none() {
+            return Ok(());
+        }
+
+        let last = self.clock.get() + 1;
+        self.clock.set(last);
+
+        let buffer_size = {
+            let mut pending = self.pending.borrow_mut();
+            let access = pending.entry(IVec::from(key)).or_default();
+            access.last = last;
+            access.count += 1;
+            pending.len()
+        };
+
+        if buffer_size >= ACCESS_BUFFER_SIZE {
+            self.flush_accesses()?;
+        }
+
+        Ok(())
+    }
+
+    // Evicts entries until the tree holds the target share of the maximum number of entries.
+    // Entries without recorded access, e.g., those stored before the tree was bounded, are evicted first.
+    fn evict_if_full(&self) -> PathfinderResult<()> {
+        let Some(eviction) = self.eviction else {
+            return Ok(());
+        };
+        if self.len.get() <= eviction.max_entries {
+            return Ok(());
+        }
+
+        self.flush_accesses()?;
+
+        let mut entries: Vec<((u64, u64), IVec)> = vec![];
+        for key in self.tree.iter().keys() {
+            let key = key?;
+            let access = self
+                .accesses
+                .get(&key)?
+                .map(|value| Access::decode(&value))
+                .transpose()?
+                .unwrap_or_default();
+
+            let rank = match eviction.policy {
+                EvictionPolicy::LeastRecentlyUsed => (access.last, 0),
+                EvictionPolicy::LeastFrequentlyUsed => (access.count, access.last),
+            };
+            entries.push((rank, key));
+        }
+
+        let target = (eviction.max_entries as f64 * EVICTION_TARGET) as usize;
+        let eviction_count = entries.len().saturating_sub(target);
+        if eviction_count == 0 {
+            self.len.set(entries.len());
+            return Ok(());
+        }
+
+        entries.select_nth_unstable_by_key(eviction_count - 1, |(rank, _)| *rank);
+
+        let (mut batch, mut access_batch) = (Batch::default(), Batch::default());
+        for (_, key) in &entries[..eviction_count] {
+            batch.remove(key);
+            access_batch.remove(key);
+        }
+        self.tree.apply_batch(batch)?;
+        self.accesses.apply_batch(access_batch)?;
+        self.len.set(entries.len() - eviction_count);
+
+        info!(
+            "Evicted {} entries of {} to keep at most {} entries.",
+            eviction_count, self.name, eviction.max_entries
+        );
+
+        Ok(())
+    }
+}
+
+impl Drop for BoundedTree {
+    // accesses that cannot be written only affect which entries are evicted first
+    fn drop(&mut self) {
+        if let Err(error) = self.flush_accesses() {
+            warn!("Accesses of {} could not be recorded: {}", self.name, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv_store::{self, Db};
+
+    fn open(db: &Db, max_entries: usize, policy: EvictionPolicy) -> BoundedTree {
+        BoundedTree::open(
+            "test",
+            db.open_tree("entries").unwrap(),
+            db.open_tree("accesses").unwrap(),
+            EvictionConfig::new(max_entries, policy),
+        )
+        .unwrap()
+    }
+
+    fn insert_keys(tree: &BoundedTree, keys: std::ops::Range<usize>) {
+        for key in keys {
+            tree.insert(format!("k{key}"), "value").unwrap();
+        }
+    }
+
+    fn contains(tree: &BoundedTree, key: usize) -> bool {
+        tree.tree().contains_key(format!("k{key}")).unwrap()
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let db = kv_store::open_temporary();
+        let tree = open(&db, 10, EvictionPolicy::LeastRecentlyUsed);
+        insert_keys(&tree, 0..10);
+        tree.get("k0").unwrap();
+
+        // the eleventh entry exceeds the maximum, so the tree is reduced to 90% of it
+        insert_keys(&tree, 10..11);
+        assert_eq!(tree.tree().len().unwrap(), 9);
+        assert!(contains(&tree, 0) && contains(&tree, 10));
+        assert!(!contains(&tree, 1) && !contains(&tree, 2));
+    }
+
+    #[test]
+    fn least_frequently_used_entries_are_evicted() {
+        let db = kv_store::open_temporary();
+        let tree = open(&db, 10, EvictionPolicy::LeastFrequentlyUsed);
+        insert_keys(&tree, 0..10);
+        for key in [0, 1, 2, 3, 4, 7, 8, 9] {
+            tree.get(format!("k{key}")).unwrap();
+        }
+
+        // among the entries accessed once, the least recently used are evicted first
+        insert_keys(&tree, 10..11);
+        assert_eq!(tree.tree().len().unwrap(), 9);
+        assert!(!contains(&tree, 5) && !contains(&tree, 6));
+        assert!((0..5).chain(7..11).all(|key| contains(&tree, key)));
+    }
+
+    #[test]
+    fn entries_without_recorded_access_are_evicted_first() {
+        let db = kv_store::open_temporary();
+        let entries = db.open_tree("entries").unwrap();
+        for key in 0..3 {
+            entries.insert(format!("old{key}"), "value").unwrap();
+        }
+
+        let tree = open(&db, 10, EvictionPolicy::LeastRecentlyUsed);
+        insert_keys(&tree, 0..8);
+        assert_eq!(tree.tree().len().unwrap(), 9);
+        assert_eq!(tree.tree().scan_prefix("old").count(), 1);
+        assert!((0..8).all(|key| contains(&tree, key)));
+    }
+
+    #[test]
+    fn accesses_are_kept_when_the_tree_is_reopened() {
+        let db = kv_store::open_temporary();
+        {
+            let tree = open(&db, 10, EvictionPolicy::LeastRecentlyUsed);
+            insert_keys(&tree, 0..5);
+            tree.get("k0").unwrap();
+        }
+
+        // a lower maximum evicts entries on opening, ordered by the accesses recorded before
+        let tree = open(&db, 4, EvictionPolicy::LeastRecentlyUsed);
+        assert_eq!(tree.tree().len().unwrap(), 3);
+        assert!(contains(&tree, 0) && contains(&tree, 3) && contains(&tree, 4));
+
+        // the clock continues, so a new entry is more recent than all recorded ones
+        insert_keys(&tree, 5..7);
+        tree.get("k0").unwrap();
+        insert_keys(&tree, 7..8);
+        assert!(contains(&tree, 0) && contains(&tree, 7));
+        assert!(!contains(&tree, 3));
+    }
+
+    #[test]
+    fn unbounded_trees_record_no_accesses() {
+        let db = kv_store::open_temporary();
+        let tree = BoundedTree::open(
+            "test",
+            db.open_tree("entries").unwrap(),
+            db.open_tree("accesses").unwrap(),
+            None,
+        )
+        .unwrap();
+        insert_keys(&tree, 0..20);
+        tree.get("k0").unwrap();
+        tree.flush_accesses().unwrap();
+
+        assert_eq!(tree.tree().len().unwrap(), 20);
+        assert!(db.open_tree("accesses").unwrap().is_empty().unwrap());
+    }
+
+    #[test]
+    fn invalid_accesses_are_errors() {
+        let db = kv_store::open_temporary();
+        db.open_tree("accesses")
+            .unwrap()
+            .insert("k0", "too short")
+            .unwrap();
+
+        let result = BoundedTree::open(
+            "test",
+            db.open_tree("entries").unwrap(),
+            db.open_tree("accesses").unwrap(),
+            EvictionConfig::new(10, EvictionPolicy::LeastRecentlyUsed),
+        );
+        assert!(matches!(result, Err(PathfinderError::Decode(_))));
+    }
+}
@@ -1,29 +1,92 @@
-use crate::store_connector::StoreConnector;
+use crate::error::PathfinderResult;
+use crate::graph_store::GraphStore;
+use crate::kg_embeddings::KgEmbeddings;
+
+/// The source of the distance between the last entity of a path and the target entity, i.e., the h term of the costs.
+pub enum Heuristic {
+    /// The semantic distance between the texts of both entities
+    Text,
+    /// The distance between the knowledge graph embeddings of both entities
+    KgEmbedding(KgEmbeddings),
+    /// The weighted average of both distances, where the weight applies to the knowledge graph embedding distance
+    Blend(KgEmbeddings, f64),
+}
+
+impl Heuristic {
+    /// Returns the name of the heuristic as used in the config.toml.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Heuristic::Text => "text",
+            Heuristic::KgEmbedding(_) => "kg",
+            Heuristic::Blend(..) => "blend",
+        }
+    }
+
+    /// Returns the entities whose heuristic distance to the target entity requires the semantic distance of their texts.
+    /// With knowledge graph embeddings, these are the entities without embedding, so they are the only ones to prefetch.
+    /// # Arguments
+    /// * `entities` - The entities, e.g., the adjacent entities of an expanded entity
+    /// * `target_entity` - The entity the distances are calculated to
+    pub fn text_distance_entities(&self, entities: &[String], target_entity: &str) -> Vec<String> {
+        match self {
+            Heuristic::Text => entities.to_vec(),
+            Heuristic::KgEmbedding(kg_embeddings) | Heuristic::Blend(kg_embeddings, 1.0) => {
+                entities
+                    .iter()
+                    .filter(|entity| kg_embeddings.distance(entity, target_entity).is_none())
+                    .cloned()
+                    .collect()
+            }
+            Heuristic::Blend(..) => entities.to_vec(),
+        }
+    }
+
+    // Returns the heuristic distance between two entities.
+    // Entities without knowledge graph embedding fall back to the semantic distance of their texts.
+    fn distance(
+        &self,
+        graph_store: &dyn GraphStore,
+        entity: &str,
+        target_entity: &str,
+    ) -> PathfinderResult<f64> {
+        let text_distance = || graph_store.get_semantic_distance(entity, target_entity);
+
+        match self {
+            Heuristic::Text => text_distance(),
+            Heuristic::KgEmbedding(kg_embeddings) => {
+                match kg_embeddings.distance(entity, target_entity) {
+                    Some(kg_distance) => Ok(kg_distance),
+                    None => text_distance(),
+                }
+            }
+            // with a weight of 1, the texts are only needed for entities without embedding
+            Heuristic::Blend(kg_embeddings, kg_weight) => {
+                match kg_embeddings.distance(entity, target_entity) {
+                    Some(kg_distance) if *kg_weight == 1.0 => Ok(kg_distance),
+                    Some(kg_distance) => {
+                        Ok(kg_weight * kg_distance + (1.0 - kg_weight) * text_distance()?)
+                    }
+                    None => text_distance(),
+                }
+            }
+        }
+    }
+}
 
 // Calculates the costs of a path.
 // Costs mapping fScore from https://en.wikipedia.org/wiki/A*_search_algorithm cannot be used for us as we use the average (!) semantic distance in the g costs
+// The directional target entity is the target entity for paths starting at the source entity and vice versa
 pub fn calculate_costs(
-    store_connector: &StoreConnector,
-    source_entity: &str,
-    target_entity: &str,
-    path: &Vec<String>,
-    average_prop_frequency: f64,
+    graph_store: &dyn GraphStore,
+    heuristic: &Heuristic,
+    directional_target_entity: &str,
+    path: &[String],
+    prop_frequency: f64,
     hyperparameter_config: &(f64, f64, f64),
-) -> i64 {
+) -> PathfinderResult<i64> {
     let (alpha, beta, gamma) = hyperparameter_config;
     let (g1, g2, h);
 
-    let directional_target_entity;
-
-    // sets a directional target entity to account for the direction of the path
-    if path[0] == source_entity {
-        directional_target_entity = target_entity;
-    } else if path[0] == target_entity {
-        directional_target_entity = source_entity;
-    } else {
-        panic!("Something is wrong with the candidate paths.");
-    }
-
     // get a slice of all entities except the last entity on the path
     let path_except_last = &path[0..path.len() - 1];
 
@@ -31,9 +94,10 @@ pub fn calculate_costs(
     if *alpha == 0.0 || path_except_last.is_empty() {
         g1 = 0.0;
     } else {
-        let total_distance: f64 = path_except_last.iter().fold(0.0, |acc, e| {
-            acc + store_connector.get_semantic_distance(e, directional_target_entity)
-        });
+        let mut total_distance = 0.0;
+        for e in path_except_last {
+            total_distance += graph_store.get_semantic_distance(e, directional_target_entity)?;
+        }
         let average_distance = total_distance / path_except_last.len() as f64;
 
         g1 = alpha * average_distance;
@@ -51,16 +115,15 @@ pub fn calculate_costs(
         h = 0.0;
     } else {
         h = gamma
-            * store_connector
-                .get_semantic_distance(&path.last().unwrap(), directional_target_entity);
+            * heuristic.distance(graph_store, path.last().unwrap(), directional_target_entity)?;
     }
 
     let mut costs = g1 + g2 + h;
 
     // TODO evaluate if this improves path quality
-    // increase costs based on the average frequency of props on the path
+    // increase costs based on the aggregated frequency of props on the path
     // props with higher frequency are less interesting -> higher costs
-    costs = costs * (1.0 + average_prop_frequency);
+    costs *= 1.0 + prop_frequency;
 
     // costs must not be negative
     assert!(costs >= 0.0);
@@ -80,5 +143,5 @@ pub fn calculate_costs(
 
     assert!(integer_const >= 100_000_000_000);
 
-    return integer_const;
+    Ok(integer_const)
 }
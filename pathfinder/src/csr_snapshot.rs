@@ -0,0 +1,635 @@
+use log::info;
+use memmap2::Mmap;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::graph_store::{CacheMisses, GraphStore};
+use crate::store_connector::StoreConnector;
+
+// A snapshot file starts with these bytes followed by the format version
+const MAGIC: [u8; 7] = *b"PFCSR\0\0";
+const FORMAT_VERSION: u8 = 2;
+
+// A snapshot file consists of these sections, all numbers are little endian:
+//
+// header: magic, version, node count n, outgoing edge count m, incoming edge count k, name length l,
+//         and name index slot count s, each as u64
+// outgoing edges: m pairs of u32, each consisting of a property and an adjacent entity node
+// incoming edges: k pairs of u32, each consisting of a property and a node pointing to the entity
+// outgoing ranges: n pairs of u64 start and u32 count, the outgoing edges of node i are at start..start + count
+// incoming ranges: n pairs of u64 start and u32 count, likewise for the incoming edges
+// list flags: n u8, 1 if the adjacency list of a node is known, 0 if it was never fetched
+// name offsets: n + 1 u64, the entity ID of node i is at positions name_offsets[i]..name_offsets[i + 1]
+// names: the l bytes of the UTF-8 encoded entity IDs of all nodes
+// name index: a hash table of s u32 slots with linear probing, each slot is empty (0) or holds a node plus one
+//
+// The edges are written first while the lists are read from the stores, so the lists are never held in memory.
+const HEADER_LENGTH: usize = 48;
+const EDGE_LENGTH: usize = 8;
+const RANGE_LENGTH: usize = 12;
+
+/// Writes the adjacency lists and incoming lists of the sled store as compressed sparse row snapshot.
+/// The lists are streamed from the stores to the file.
+/// # Arguments
+/// * `store_connector` - The StoreConnector reading the lists
+/// * `snapshot_path` - The path of the snapshot file, which is overwritten
+/// # Returns
+/// * An error if the stores cannot be read or the file cannot be written
+pub fn write_snapshot(
+    store_connector: &StoreConnector,
+    snapshot_path: &str,
+) -> PathfinderResult<()> {
+    let file = File::create(snapshot_path).map_err(|error| {
+        PathfinderError::Store(format!(
+            "Snapshot file {snapshot_path} could not be created: {error}"
+        ))
+    })?;
+    let mut writer = SnapshotWriter::new(BufWriter::new(file))?;
+
+    info!("Writing adjacency lists to {}.", snapshot_path);
+    store_connector
+        .for_each_adjacency_ids(|node, pairs| writer.add_adjacency_list(node, &pairs))?;
+
+    info!("Writing incoming lists to {}.", snapshot_path);
+    store_connector.for_each_incoming_ids(|node, pairs| writer.add_incoming_list(node, &pairs))?;
+
+    // the names are written last as entities of legacy lists are interned while reading them
+    let (node_count, edge_count) = writer.finish(&store_connector.interned_names())?;
+
+    info!(
+        "Snapshot with {} nodes and {} edges written to {}.",
+        node_count, edge_count, snapshot_path
+    );
+    Ok(())
+}
+
+// Writes a snapshot section by section: first all adjacency lists, then all incoming lists, and finally the names.
+struct SnapshotWriter<W: Write + Seek> {
+    writer: W,
+    outgoing_ranges: Vec<(u64, u32)>,
+    incoming_ranges: Vec<(u64, u32)>,
+    list_flags: Vec<u8>,
+    outgoing_edge_count: u64,
+    incoming_edge_count: u64,
+    writing_incoming_lists: bool,
+}
+
+impl<W: Write + Seek> SnapshotWriter<W> {
+    fn new(mut writer: W) -> PathfinderResult<Self> {
+        // the header is written by finish once all counts are known
+        writer.write_all(&[0; HEADER_LENGTH])?;
+
+        Ok(Self {
+            writer,
+            outgoing_ranges: vec![],
+            incoming_ranges: vec![],
+            list_flags: vec![],
+            outgoing_edge_count: 0,
+            incoming_edge_count: 0,
+            writing_incoming_lists: false,
+        })
+    }
+
+    fn add_adjacency_list(&mut self, node: u32, pairs: &[(u32, u32)]) -> PathfinderResult<()> {
+        if self.writing_incoming_lists {
+            return Err(PathfinderError::DataConsistency(
+                "Adjacency lists must be written before the incoming lists.".to_owned(),
+            ));
+        }
+
+        let start = self.outgoing_edge_count;
+        self.write_edges(pairs)?;
+        self.outgoing_edge_count += pairs.len() as u64;
+
+        set(&mut self.outgoing_ranges, node, (start, pairs.len() as u32));
+        set(&mut self.list_flags, node, 1);
+        Ok(())
+    }
+
+    fn add_incoming_list(&mut self, node: u32, pairs: &[(u32, u32)]) -> PathfinderResult<()> {
+        self.writing_incoming_lists = true;
+        let start = self.incoming_edge_count;
+        self.write_edges(pairs)?;
+        self.incoming_edge_count += pairs.len() as u64;
+
+        set(&mut self.incoming_ranges, node, (start, pairs.len() as u32));
+        Ok(())
+    }
+
+    fn write_edges(&mut self, pairs: &[(u32, u32)]) -> PathfinderResult<()> {
+        for (prop, some_entity) in pairs {
+            self.writer.write_all(&prop.to_le_bytes())?;
+            self.writer.write_all(&some_entity.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Writes the ranges, the names, and the header and returns the number of nodes and outgoing edges.
+    fn finish(mut self, names: &[String]) -> PathfinderResult<(usize, u64)> {
+        let node_count = names.len();
+
+        if self.list_flags.len() > node_count || self.incoming_ranges.len() > node_count {
+            return Err(PathfinderError::DataConsistency(
+                "A list belongs to a node without name.".to_owned(),
+            ));
+        }
+        self.outgoing_ranges.resize(node_count, (0, 0));
+        self.incoming_ranges.resize(node_count, (0, 0));
+        self.list_flags.resize(node_count, 0);
+
+        for (start, count) in self.outgoing_ranges.iter().chain(&self.incoming_ranges) {
+            self.writer.write_all(&start.to_le_bytes())?;
+            self.writer.write_all(&count.to_le_bytes())?;
+        }
+        self.writer.write_all(&self.list_flags)?;
+
+        let mut name_offset = 0u64;
+        self.writer.write_all(&name_offset.to_le_bytes())?;
+        for name in names {
+            name_offset += name.len() as u64;
+            self.writer.write_all(&name_offset.to_le_bytes())?;
+        }
+        for name in names {
+            self.writer.write_all(name.as_bytes())?;
+        }
+
+        // at most half of the slots are used, so probing sequences stay short
+        let slot_count = (node_count * 2).next_power_of_two();
+        let mut slots = vec![0u32; slot_count];
+        for (node, name) in names.iter().enumerate() {
+            let mut slot = name_hash(name) as usize & (slot_count - 1);
+            while slots[slot] != 0 {
+                slot = (slot + 1) & (slot_count - 1);
+            }
+            slots[slot] = node as u32 + 1;
+        }
+        for slot in slots {
+            self.writer.write_all(&slot.to_le_bytes())?;
+        }
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&MAGIC)?;
+        self.writer.write_all(&[FORMAT_VERSION])?;
+        for count in [
+            node_count as u64,
+            self.outgoing_edge_count,
+            self.incoming_edge_count,
+            name_offset,
+            slot_count as u64,
+        ] {
+            self.writer.write_all(&count.to_le_bytes())?;
+        }
+        self.writer.flush()?;
+
+        Ok((node_count, self.outgoing_edge_count))
+    }
+}
+
+// Sets the value of a node, growing the vector as nodes are not visited in order.
+fn set<T: Clone + Default>(values: &mut Vec<T>, node: u32, value: T) {
+    let index = node as usize;
+    if values.len() <= index {
+        values.resize(index + 1, T::default());
+    }
+    values[index] = value;
+}
+
+// The FNV-1a hash of an entity ID, which is stable across platforms and versions unlike the hasher of HashMap.
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// The validated sections of a snapshot, which is either memory-mapped or, in tests, read into memory.
+struct CsrSnapshot<B: AsRef<[u8]>> {
+    bytes: B,
+    node_count: usize,
+    outgoing_edge_count: usize,
+    incoming_edge_count: usize,
+    outgoing_edges_start: usize,
+    incoming_edges_start: usize,
+    outgoing_ranges_start: usize,
+    incoming_ranges_start: usize,
+    list_flags_start: usize,
+    name_offsets_start: usize,
+    names_start: usize,
+    name_index_start: usize,
+    slot_count: usize,
+}
+
+impl<B: AsRef<[u8]>> CsrSnapshot<B> {
+    // Checks the header and that all sections, ranges, and names lie within the snapshot.
+    // The nodes of single edges and name index slots are checked when they are read.
+    fn parse(bytes: B) -> PathfinderResult<Self> {
+        let data = bytes.as_ref();
+        let invalid = |message: &str| PathfinderError::DataConsistency(message.to_owned());
+
+        if data.len() < HEADER_LENGTH || !data.starts_with(&MAGIC) {
+            return Err(invalid("The file is not a snapshot."));
+        }
+        if data[MAGIC.len()] != FORMAT_VERSION {
+            return Err(PathfinderError::DataConsistency(format!(
+                "Snapshot format version {} is not supported, write the snapshot again.",
+                data[MAGIC.len()]
+            )));
+        }
+
+        let count = |index: usize| usize::try_from(read_u64(data, 8 + index * 8)).ok();
+        let (Some(node_count), Some(outgoing_edge_count), Some(incoming_edge_count)) =
+            (count(0), count(1), count(2))
+        else {
+            return Err(invalid("The snapshot header is invalid."));
+        };
+        let (Some(names_length), Some(slot_count)) = (count(3), count(4)) else {
+            return Err(invalid("The snapshot header is invalid."));
+        };
+
+        if !slot_count.is_power_of_two() || slot_count < node_count {
+            return Err(invalid("The name index of the snapshot is invalid."));
+        }
+
+        // the start of each section follows from the lengths of the previous sections
+        let mut position = HEADER_LENGTH;
+        let mut section = |length: Option<usize>| -> PathfinderResult<usize> {
+            let start = position;
+            position = length
+                .and_then(|length| position.checked_add(length))
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| invalid("The snapshot is truncated."))?;
+            Ok(start)
+        };
+
+        let outgoing_edges_start = section(outgoing_edge_count.checked_mul(EDGE_LENGTH))?;
+        let incoming_edges_start = section(incoming_edge_count.checked_mul(EDGE_LENGTH))?;
+        let outgoing_ranges_start = section(node_count.checked_mul(RANGE_LENGTH))?;
+        let incoming_ranges_start = section(node_count.checked_mul(RANGE_LENGTH))?;
+        let list_flags_start = section(Some(node_count))?;
+        let name_offsets_start = section(node_count.checked_add(1).and_then(|n| n.checked_mul(8)))?;
+        let names_start = section(Some(names_length))?;
+        let name_index_start = section(slot_count.checked_mul(4))?;
+
+        if position != data.len() {
+            return Err(invalid("The snapshot has trailing bytes."));
+        }
+
+        let snapshot = Self {
+            bytes,
+            node_count,
+            outgoing_edge_count,
+            incoming_edge_count,
+            outgoing_edges_start,
+            incoming_edges_start,
+            outgoing_ranges_start,
+            incoming_ranges_start,
+            list_flags_start,
+            name_offsets_start,
+            names_start,
+            name_index_start,
+            slot_count,
+        };
+        snapshot.validate_nodes(names_length)?;
+
+        Ok(snapshot)
+    }
+
+    // Checks the ranges, list flags, and names of all nodes.
+    fn validate_nodes(&self, names_length: usize) -> PathfinderResult<()> {
+        let data = self.bytes.as_ref();
+        let mut previous_name_end = 0;
+
+        for node in 0..self.node_count {
+            let (_, outgoing_end) = self.range(self.outgoing_ranges_start, node);
+            let (_, incoming_end) = self.range(self.incoming_ranges_start, node);
+
+            if outgoing_end > self.outgoing_edge_count as u64
+                || incoming_end > self.incoming_edge_count as u64
+                || data[self.list_flags_start + node] > 1
+            {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "The lists of node {node} exceed the snapshot."
+                )));
+            }
+
+            let name_start = read_u64(data, self.name_offsets_start + node * 8);
+            let name_end = read_u64(data, self.name_offsets_start + node * 8 + 8);
+
+            if name_start != previous_name_end || name_end < name_start {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "The name of node {node} exceeds the snapshot."
+                )));
+            }
+            previous_name_end = name_end;
+        }
+
+        if previous_name_end != names_length as u64 {
+            return Err(PathfinderError::DataConsistency(
+                "The names do not fill the snapshot section of names.".to_owned(),
+            ));
+        }
+
+        // the offsets are consecutive, so every name is valid if the whole section is valid and split at char boundaries
+        let names = std::str::from_utf8(&data[self.names_start..self.name_index_start])?;
+        for node in 0..self.node_count {
+            let offset = read_u64(data, self.name_offsets_start + node * 8) as usize;
+            if !names.is_char_boundary(offset) {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "The name of node {node} is not valid UTF-8."
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the start and the end of the edges of a node in one of the range sections.
+    fn range(&self, ranges_start: usize, node: usize) -> (u64, u64) {
+        let position = ranges_start + node * RANGE_LENGTH;
+        let start = read_u64(self.bytes.as_ref(), position);
+        let count = read_u32(self.bytes.as_ref(), position + 8) as u64;
+        (start, start.saturating_add(count))
+    }
+
+    // Returns the entity ID of a node.
+    fn name(&self, node: u32) -> PathfinderResult<&str> {
+        let node = node as usize;
+        if node >= self.node_count {
+            return Err(PathfinderError::DataConsistency(format!(
+                "Node {node} is not part of the snapshot."
+            )));
+        }
+
+        let data = self.bytes.as_ref();
+        let start = self.names_start + read_u64(data, self.name_offsets_start + node * 8) as usize;
+        let end =
+            self.names_start + read_u64(data, self.name_offsets_start + node * 8 + 8) as usize;
+
+        // the names were validated when the snapshot was parsed
+        Ok(std::str::from_utf8(&data[start..end])?)
+    }
+
+    // Finds the node of an entity via the name index.
+    fn find_node(&self, entity: &str) -> PathfinderResult<Option<u32>> {
+        let mask = self.slot_count - 1;
+        let mut slot = name_hash(entity) as usize & mask;
+
+        // every node occupies one slot, so at least half of the slots are empty and the probing terminates
+        for _ in 0..self.slot_count {
+            match read_u32(self.bytes.as_ref(), self.name_index_start + slot * 4) {
+                0 => return Ok(None),
+                node_plus_one if self.name(node_plus_one - 1)? == entity => {
+                    return Ok(Some(node_plus_one - 1))
+                }
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Returns the outgoing edges of a node or None if its adjacency list is not part of the snapshot.
+    fn adjacent_entities(&self, node: u32) -> PathfinderResult<Option<Vec<(String, String)>>> {
+        if self.bytes.as_ref()[self.list_flags_start + node as usize] != 1 {
+            return Ok(None);
+        }
+
+        self.edges(self.outgoing_ranges_start, self.outgoing_edges_start, node)
+            .map(Some)
+    }
+
+    // Returns the (property, entity) pairs pointing to a node.
+    fn incoming_entities(&self, node: u32) -> PathfinderResult<Vec<(String, String)>> {
+        self.edges(self.incoming_ranges_start, self.incoming_edges_start, node)
+    }
+
+    fn edges(
+        &self,
+        ranges_start: usize,
+        edges_start: usize,
+        node: u32,
+    ) -> PathfinderResult<Vec<(String, String)>> {
+        let (start, end) = self.range(ranges_start, node as usize);
+
+        (start as usize..end as usize)
+            .map(|edge| {
+                let position = edges_start + edge * EDGE_LENGTH;
+                let prop = read_u32(self.bytes.as_ref(), position);
+                let some_entity = read_u32(self.bytes.as_ref(), position + 4);
+                Ok((
+                    self.name(prop)?.to_owned(),
+                    self.name(some_entity)?.to_owned(),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// A graph store answering adjacency and incoming edge lookups from a memory-mapped snapshot instead of sled.
+/// Labels, descriptions, redirects, semantic distances, and property frequencies are delegated to a StoreConnector,
+/// as labels and descriptions depend on the configured languages.
+/// Adjacent literal hubs are not part of the snapshot.
+pub struct CsrStore<'a> {
+    snapshot: CsrSnapshot<Mmap>,
+    store_connector: StoreConnector<'a>,
+    cache_misses: Cell<CacheMisses>,
+}
+
+impl<'a> CsrStore<'a> {
+    /// Loads a CsrStore by memory-mapping a snapshot file.
+    /// # Arguments
+    /// * `snapshot_path` - The path of the snapshot file
+    /// * `store_connector` - The StoreConnector answering all lookups except adjacency lookups
+    /// # Returns
+    /// * The instance or an error if the file cannot be mapped or is not a valid snapshot
+    pub fn load(
+        snapshot_path: &str,
+        store_connector: StoreConnector<'a>,
+    ) -> PathfinderResult<Self> {
+        let file = File::open(snapshot_path).map_err(|error| {
+            PathfinderError::Store(format!(
+                "Snapshot file {snapshot_path} could not be opened: {error}"
+            ))
+        })?;
+        // the snapshot must not be modified while it is mapped, which holds as it is only written by the snapshot mode
+        let snapshot = CsrSnapshot::parse(unsafe { Mmap::map(&file) }?)?;
+
+        info!(
+            "Loaded snapshot with {} nodes and {} edges from {}.",
+            snapshot.node_count, snapshot.outgoing_edge_count, snapshot_path
+        );
+
+        Ok(Self {
+            snapshot,
+            store_connector,
+            cache_misses: Cell::new(CacheMisses::default()),
+        })
+    }
+}
+
+impl GraphStore for CsrStore<'_> {
+    // entities whose adjacency list is not part of the snapshot are reported as cache misses
+    fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        if let Some(node) = self.snapshot.find_node(entity)? {
+            if let Some(adjacent_entities) = self.snapshot.adjacent_entities(node)? {
+                return Ok(adjacent_entities);
+            }
+        }
+
+        let mut cache_misses = self.cache_misses.get();
+        cache_misses.adjacent_entities += 1;
+        self.cache_misses.set(cache_misses);
+        Ok(vec![])
+    }
+
+    fn get_incoming_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        match self.snapshot.find_node(entity)? {
+            Some(node) => self.snapshot.incoming_entities(node),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        self.store_connector.get_label_with_language(entity)
+    }
+
+    fn get_description_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        self.store_connector.get_description_with_language(entity)
+    }
+
+    fn preferred_language(&self) -> &str {
+        self.store_connector.preferred_language()
+    }
+
+    fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64> {
+        self.store_connector
+            .get_semantic_distance(entity_a, entity_b)
+    }
+
+    fn prefetch_embeddings(&self, entities: &[String], target: &str) -> PathfinderResult<()> {
+        self.store_connector.prefetch_embeddings(entities, target)
+    }
+
+    fn get_prop_frequency(&self, props: &[String]) -> f64 {
+        self.store_connector.get_prop_frequency(props)
+    }
+
+    fn normalize_entity(&self, entity: &str) -> String {
+        self.store_connector.normalize_entity(entity)
+    }
+
+    fn resolve_entity(&self, entity: &str) -> PathfinderResult<String> {
+        self.store_connector.resolve_entity(entity)
+    }
+
+    fn adjacency_failure_count(&self) -> usize {
+        self.store_connector.adjacency_failure_count()
+    }
+
+    fn cache_misses(&self) -> CacheMisses {
+        let mut cache_misses = self.store_connector.cache_misses();
+        cache_misses += self.cache_misses.get();
+        cache_misses
+    }
+}
+
+// The positions passed to these functions lie within the sections validated when the snapshot was parsed.
+fn read_u64(bytes: &[u8], position: usize) -> u64 {
+    u64::from_le_bytes(bytes[position..position + 8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // Writes a snapshot of Q1 -P31-> Q2 and Q1 -P279-> Q3, where the adjacency list of Q3 was never fetched.
+    fn snapshot_bytes() -> Vec<u8> {
+        let names = ["Q1", "P31", "Q2", "P279", "Q3"].map(str::to_owned);
+        let mut cursor = Cursor::new(vec![]);
+        let mut writer = SnapshotWriter::new(&mut cursor).unwrap();
+
+        writer.add_adjacency_list(2, &[]).unwrap();
+        writer.add_adjacency_list(0, &[(1, 2), (3, 4)]).unwrap();
+        writer.add_incoming_list(2, &[(1, 0)]).unwrap();
+        writer.add_incoming_list(4, &[(3, 0)]).unwrap();
+        assert!(writer.add_adjacency_list(4, &[]).is_err());
+        assert_eq!(writer.finish(&names).unwrap(), (5, 2));
+
+        cursor.into_inner()
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(prop, entity)| (prop.to_string(), entity.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn snapshots_are_round_tripped() {
+        let snapshot = CsrSnapshot::parse(snapshot_bytes()).unwrap();
+
+        let q1 = snapshot.find_node("Q1").unwrap().unwrap();
+        let q2 = snapshot.find_node("Q2").unwrap().unwrap();
+        let q3 = snapshot.find_node("Q3").unwrap().unwrap();
+        assert_eq!(snapshot.find_node("Q4").unwrap(), None);
+
+        assert_eq!(
+            snapshot.adjacent_entities(q1).unwrap(),
+            Some(pairs(&[("P31", "Q2"), ("P279", "Q3")]))
+        );
+        assert_eq!(snapshot.adjacent_entities(q2).unwrap(), Some(vec![]));
+        assert_eq!(snapshot.adjacent_entities(q3).unwrap(), None);
+
+        assert_eq!(
+            snapshot.incoming_entities(q3).unwrap(),
+            pairs(&[("P279", "Q1")])
+        );
+        assert_eq!(snapshot.incoming_entities(q1).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn invalid_snapshots_are_rejected() {
+        let bytes = snapshot_bytes();
+
+        assert!(CsrSnapshot::parse(&bytes[..bytes.len() - 1]).is_err());
+        assert!(CsrSnapshot::parse(&bytes[..HEADER_LENGTH - 1]).is_err());
+        assert!(CsrSnapshot::parse(b"not a snapshot".as_slice()).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(CsrSnapshot::parse(trailing).is_err());
+
+        let mut old_version = bytes.clone();
+        old_version[MAGIC.len()] = 1;
+        assert!(CsrSnapshot::parse(old_version).is_err());
+
+        // a node count exceeding the file must not overflow the section positions
+        let mut huge_count = bytes.clone();
+        huge_count[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(CsrSnapshot::parse(huge_count).is_err());
+
+        // the outgoing range of the first node points beyond the outgoing edges
+        let mut invalid_range = bytes.clone();
+        let ranges_start = HEADER_LENGTH + 4 * EDGE_LENGTH;
+        invalid_range[ranges_start..ranges_start + 8].copy_from_slice(&7u64.to_le_bytes());
+        assert!(CsrSnapshot::parse(invalid_range).is_err());
+    }
+
+    #[test]
+    fn edges_to_unknown_nodes_are_reported() {
+        let mut bytes = snapshot_bytes();
+        // the adjacent entity of the first outgoing edge
+        bytes[HEADER_LENGTH + 4..HEADER_LENGTH + 8].copy_from_slice(&99u32.to_le_bytes());
+
+        let snapshot = CsrSnapshot::parse(bytes).unwrap();
+        let q1 = snapshot.find_node("Q1").unwrap().unwrap();
+        assert!(snapshot.adjacent_entities(q1).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::api_connector::ApiConnector;
+use crate::error::{PathfinderError, PathfinderResult};
+
+/// A source of embedding vectors for entities.
+/// Semantic distances are calculated locally as cosine distance between the vectors of two entities.
+pub trait EmbeddingProvider {
+    /// Returns the identifier of the embedding model, which is part of the keys of stored vectors and distances.
+    fn model(&self) -> &str;
+
+    /// Embeds several entities at once.
+    /// # Arguments
+    /// * `texts` - Pairs of entity ID and the text describing the entity, i.e., its label and description
+    /// # Returns
+    /// * One vector per entity, None for entities that cannot be embedded
+    /// * An error if the provider failed altogether
+    fn embed(&self, texts: &[(String, String)]) -> PathfinderResult<Vec<Option<Vec<f32>>>>;
+
+    /// Returns whether the provider works without external services, so it is also used in offline mode.
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Embeds the texts describing entities with the sentence transformer of the Wembed API.
+pub struct WembedProvider<'a> {
+    api_connector: &'a ApiConnector,
+    model: String,
+}
+
+impl<'a> WembedProvider<'a> {
+    /// Creates a WembedProvider instance.
+    /// # Arguments
+    /// * `api_connector` - The ApiConnector used for requests to the Wembed API
+    /// * `model` - The identifier of the model the Wembed API is expected to serve
+    /// # Returns
+    /// * The instance
+    pub fn new(api_connector: &'a ApiConnector, model: String) -> Self {
+        Self {
+            api_connector,
+            model,
+        }
+    }
+}
+
+impl EmbeddingProvider for WembedProvider<'_> {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    // vectors of a different model must not be mixed with the stored ones
+    fn embed(&self, texts: &[(String, String)]) -> PathfinderResult<Vec<Option<Vec<f32>>>> {
+        let texts: Vec<String> = texts.iter().map(|(_, text)| text.to_owned()).collect();
+
+        let (model, embeddings) = self.api_connector.fetch_embeddings(&texts)?;
+
+        if model != self.model {
+            return Err(PathfinderError::DataConsistency(format!(
+                "The Wembed API serves {model}, but {} is configured.",
+                self.model
+            )));
+        }
+
+        Ok(embeddings.into_iter().map(Some).collect())
+    }
+}
@@ -0,0 +1,229 @@
+use crate::bounded_tree::{BoundedTree, EvictionConfig};
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::kv_store::Db;
+
+/// Embedding vectors of entities per embedding model.
+/// A vector is stored as little endian f32 values under the key `model\0entity`.
+/// An empty vector marks an entity the model cannot embed, e.g., as none of the words of its text are known.
+pub struct EmbeddingStore {
+    db: Db,
+    // the vectors in the default tree of the store, bounded if an EvictionConfig is given
+    vectors: BoundedTree,
+    model: String,
+}
+
+impl EmbeddingStore {
+    /// Creates an EmbeddingStore for the vectors of a single embedding model.
+    /// # Arguments
+    /// * `db` - The key value store holding the vectors
+    /// * `model` - The identifier of the embedding model
+    /// * `eviction` - The maximum number of vectors of all models and the eviction policy, None for an unbounded store
+    /// # Returns
+    /// * The instance or an error if the store cannot be read
+    pub fn new(db: Db, model: String, eviction: Option<EvictionConfig>) -> PathfinderResult<Self> {
+        let vectors = BoundedTree::open(
+            "embedding_store",
+            (*db).clone(),
+            db.open_tree("accesses")?,
+            eviction,
+        )?;
+
+        Ok(Self { db, vectors, model })
+    }
+
+    /// Returns the identifier of the embedding model.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the key value store holding the vectors.
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    /// Returns the stored embedding vector of an entity, which is empty if the entity cannot be embedded.
+    pub fn get(&self, entity: &str) -> PathfinderResult<Option<Vec<f32>>> {
+        let bytes = self.vectors.get(self.key(entity))?;
+        Ok(bytes.map(|bytes| decode_vector(&bytes)))
+    }
+
+    /// Returns the number of stored vectors of the embedding model, not counting entities that cannot be embedded.
+    pub fn len(&self) -> usize {
+        self.db
+            .scan_prefix(format!("{}\0", self.model))
+            .filter(|entry| entry.as_ref().is_ok_and(|(_, bytes)| !bytes.is_empty()))
+            .count()
+    }
+
+    /// Stores the embedding vector of an entity.
+    pub fn insert(&self, entity: &str, vector: &[f32]) -> PathfinderResult<()> {
+        self.insert_for_model(&self.model, entity, vector)
+    }
+
+    /// Returns the stored vectors of all embedding models as (model, entity, vector) triples, e.g., to export them.
+    pub fn all_vectors(
+        &self,
+    ) -> impl Iterator<Item = PathfinderResult<(String, String, Vec<f32>)>> + '_ {
+        self.db.iter().map(|entry| {
+            let (key, bytes) = entry?;
+            let key = std::str::from_utf8(&key)?;
+            let (model, entity) = key.split_once('\0').ok_or_else(|| {
+                PathfinderError::Decode(format!("The embedding key {key:?} has no model."))
+            })?;
+            Ok((model.to_owned(), entity.to_owned(), decode_vector(&bytes)))
+        })
+    }
+
+    /// Stores the embedding vector of an entity for any embedding model, e.g., when importing exported vectors.
+    /// Returns an error if the vector has another dimension than the stored vectors of the model.
+    pub fn insert_for_model(
+        &self,
+        model: &str,
+        entity: &str,
+        vector: &[f32],
+    ) -> PathfinderResult<()> {
+        // all vectors of a model share the dimension of the first stored one, markers of entities that cannot be
+        // embedded are skipped
+        if !vector.is_empty() {
+            let stored_bytes = self
+                .db
+                .scan_prefix(format!("{model}\0"))
+                .map(|entry| entry.map(|(_, bytes)| bytes))
+                .find(|entry| entry.as_ref().map_or(true, |bytes| !bytes.is_empty()))
+                .transpose()?;
+            if let Some(bytes) = stored_bytes.filter(|bytes| bytes.len() != vector.len() * 4) {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "The embedding vector of {} has {} dimensions, but the stored vectors of {} have {}.",
+                    entity,
+                    vector.len(),
+                    model,
+                    bytes.len() / 4
+                )));
+            }
+        }
+
+        let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.vectors.insert(format!("{model}\0{entity}"), bytes)
+    }
+
+    /// Removes the vectors of all models of the entities for which the predicate returns false.
+    /// Returns the number of removed vectors.
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) -> PathfinderResult<usize> {
+        let mut removed_keys = vec![];
+        for key in self.db.iter().keys() {
+            let key = key?;
+            let text = std::str::from_utf8(&key)?;
+            let (_, entity) = text.split_once('\0').ok_or_else(|| {
+                PathfinderError::Decode(format!("The embedding key {text:?} has no model."))
+            })?;
+            if !keep(entity) {
+                removed_keys.push(key);
+            }
+        }
+
+        for key in &removed_keys {
+            self.vectors.remove(key)?;
+        }
+
+        Ok(removed_keys.len())
+    }
+
+    fn key(&self, entity: &str) -> String {
+        format!("{}\0{}", self.model, entity)
+    }
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+/// Calculates the cosine distance between two vectors like scipy.spatial.distance.cosine.
+/// # Arguments
+/// * `vector_a` - The first vector
+/// * `vector_b` - The second vector of the same dimension
+/// # Returns
+/// * The cosine distance between 0.0 and 2.0 or None if the dimensions differ or a vector is zero, as the angle is undefined
+pub fn cosine_distance(vector_a: &[f32], vector_b: &[f32]) -> Option<f64> {
+    if vector_a.len() != vector_b.len() {
+        return None;
+    }
+
+    let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+
+    for (&a, &b) in vector_a.iter().zip(vector_b) {
+        let (a, b) = (a as f64, b as f64);
+        dot += a * b;
+        norm_a += a * a;
+        norm_b += b * b;
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+
+    Some(1.0 - dot / (norm_a.sqrt() * norm_b.sqrt()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv_store;
+
+    #[test]
+    fn cosine_distance_is_undefined_for_zero_vectors_and_differing_dimensions() {
+        assert_eq!(cosine_distance(&[1.0, 0.0], &[0.0, 2.0]), Some(1.0));
+        assert_eq!(
+            cosine_distance(&[1.0, 1.0], &[2.0, 2.0]).map(f64::round),
+            Some(0.0)
+        );
+        assert_eq!(cosine_distance(&[1.0, 0.0], &[-1.0, 0.0]), Some(2.0));
+        assert_eq!(cosine_distance(&[0.0, 0.0], &[1.0, 0.0]), None);
+        assert_eq!(cosine_distance(&[1.0, 0.0], &[1.0, 0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn vectors_of_another_dimension_are_rejected() {
+        let store =
+            EmbeddingStore::new(kv_store::open_temporary(), "model".to_owned(), None).unwrap();
+        // a marker of an entity that cannot be embedded does not determine the dimension
+        store.insert("Q0", &[]).unwrap();
+        store.insert("Q1", &[1.0, 0.0]).unwrap();
+        store.insert("Q2", &[0.0, 1.0]).unwrap();
+
+        assert!(matches!(
+            store.insert("Q3", &[1.0, 0.0, 0.0]),
+            Err(PathfinderError::DataConsistency(_))
+        ));
+        // other models may have other dimensions
+        store
+            .insert_for_model("other", "Q3", &[1.0, 0.0, 0.0])
+            .unwrap();
+        assert_eq!(store.get("Q3").unwrap(), None);
+        assert_eq!(store.get("Q2").unwrap(), Some(vec![0.0, 1.0]));
+        assert_eq!(store.get("Q0").unwrap(), Some(vec![]));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn vectors_of_all_models_are_retained() {
+        let store =
+            EmbeddingStore::new(kv_store::open_temporary(), "model".to_owned(), None).unwrap();
+        store.insert("Q1", &[1.0, 0.0]).unwrap();
+        store.insert("Q2", &[0.0, 1.0]).unwrap();
+        store.insert_for_model("other", "Q2", &[1.0]).unwrap();
+
+        assert_eq!(store.retain(|entity| entity == "Q1").unwrap(), 2);
+        assert_eq!(store.get("Q1").unwrap(), Some(vec![1.0, 0.0]));
+        assert_eq!(store.all_vectors().count(), 1);
+
+        // keys without model are no vectors of this store
+        store.db().insert("Q3", "vector").unwrap();
+        assert!(matches!(
+            store.retain(|_| true),
+            Err(PathfinderError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+// Wikibase entity IDs come in several types: items (Q42), properties (P31), lexemes (L7),
+// and the forms (L7-F1) and senses (L7-S1) of lexemes.
+
+const ENTITY_IRI_PREFIXES: [&str; 3] = [
+    "http://www.wikidata.org/entity/",
+    "https://www.wikidata.org/wiki/",
+    "wd:",
+];
+
+/// The types of Wikibase entities that can be part of a path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntityKind {
+    Item,
+    Property,
+    Lexeme,
+    Form,
+    Sense,
+}
+
+impl EntityKind {
+    /// Returns the Turtle predicate used for the label of an entity of this kind.
+    pub fn label_predicate(&self) -> &'static str {
+        match self {
+            EntityKind::Item | EntityKind::Property => "rdfs:label",
+            EntityKind::Lexeme => "wikibase:lemma",
+            EntityKind::Form => "ontolex:representation",
+            EntityKind::Sense => "skos:definition",
+        }
+    }
+}
+
+/// Determines the kind of a Wikibase entity ID.
+/// # Arguments
+/// * `entity` - The entity ID, e.g., Q42, P31, L7, L7-F1, or L7-S1
+/// # Returns
+/// * The kind of the entity or None if the ID is not a valid Wikibase entity ID
+pub fn entity_kind(entity: &str) -> Option<EntityKind> {
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    let (main_id, sub_id) = match entity.split_once('-') {
+        Some((main_id, sub_id)) => (main_id, Some(sub_id)),
+        None => (entity, None),
+    };
+
+    let (prefix, number) = split_prefix(main_id)?;
+    if !is_number(number) {
+        return None;
+    }
+
+    match (prefix, sub_id) {
+        ('Q', None) => Some(EntityKind::Item),
+        ('P', None) => Some(EntityKind::Property),
+        ('L', None) => Some(EntityKind::Lexeme),
+        ('L', Some(sub_id)) => match split_prefix(sub_id)? {
+            ('F', sub_number) if is_number(sub_number) => Some(EntityKind::Form),
+            ('S', sub_number) if is_number(sub_number) => Some(EntityKind::Sense),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Splits an ID into its first character and the rest, e.g., Q42 into Q and 42; None for an empty ID.
+// The first character may take more than one byte, e.g., in names mistaken for IDs.
+fn split_prefix(id: &str) -> Option<(char, &str)> {
+    let mut chars = id.chars();
+    let prefix = chars.next()?;
+    Some((prefix, chars.as_str()))
+}
+
+/// Normalises user-provided entity references like wd:P50, http://www.wikidata.org/entity/L7, or q42 to plain IDs.
+/// References that do not denote a Wikibase entity are returned unchanged.
+pub fn normalize_entity_id(entity: &str) -> String {
+    let trimmed = entity.trim();
+    let id = ENTITY_IRI_PREFIXES
+        .iter()
+        .find_map(|prefix| trimmed.strip_prefix(prefix))
+        .unwrap_or(trimmed)
+        .to_uppercase();
+
+    match entity_kind(&id) {
+        Some(_) => id,
+        None => trimmed.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entity_kinds_are_determined() {
+        assert_eq!(entity_kind("Q42"), Some(EntityKind::Item));
+        assert_eq!(entity_kind("P31"), Some(EntityKind::Property));
+        assert_eq!(entity_kind("L7"), Some(EntityKind::Lexeme));
+        assert_eq!(entity_kind("L7-F1"), Some(EntityKind::Form));
+        assert_eq!(entity_kind("L7-S1"), Some(EntityKind::Sense));
+
+        for invalid in ["", "Q", "Q4x", "Q7-F1", "L7-X1", "L7-F", "42"] {
+            assert_eq!(entity_kind(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn non_ascii_input_is_no_entity() {
+        for input in ["ÉMILE", "É1", "Q١٢", "L7-É1", "€"] {
+            assert_eq!(entity_kind(input), None, "{input}");
+        }
+        assert_eq!(normalize_entity_id(" Émile "), "Émile");
+    }
+
+    #[test]
+    fn entity_references_are_normalized() {
+        assert_eq!(normalize_entity_id("wd:P50"), "P50");
+        assert_eq!(
+            normalize_entity_id("http://www.wikidata.org/entity/L7"),
+            "L7"
+        );
+        assert_eq!(normalize_entity_id("q42"), "Q42");
+        assert_eq!(normalize_entity_id("l7-f1"), "L7-F1");
+        assert_eq!(normalize_entity_id("Douglas Adams"), "Douglas Adams");
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+
+use crate::kv_store::KvError;
+
+/// The errors that abort a single search, e.g., while the benchmark continues with the next query.
+/// Failed requests of the Wikidata API during a search are counted by the stores instead, as the search can
+/// continue without the adjacent entities, and are reported as FailureReason::ApiFailure.
+#[derive(Debug)]
+pub enum PathfinderError {
+    /// A request to the Wikidata API or the Wembed API failed or the API reported an error
+    Network(String),
+    /// A response of an API or a stored value could not be decoded, e.g., malformed JSON
+    Decode(String),
+    /// A store or an input file, e.g., a dump, could not be read or written
+    Store(String),
+    /// Stored or returned data contradicts itself, e.g., an adjacency list with a numeric ID that is not interned
+    DataConsistency(String),
+}
+
+impl PathfinderError {
+    /// All kinds of errors in the order in which they are reported.
+    pub const KINDS: [&'static str; 4] = ["network", "decode", "store", "data_consistency"];
+
+    /// Returns a snake case name of the kind of the error for result files.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PathfinderError::Network(_) => "network",
+            PathfinderError::Decode(_) => "decode",
+            PathfinderError::Store(_) => "store",
+            PathfinderError::DataConsistency(_) => "data_consistency",
+        }
+    }
+}
+
+impl fmt::Display for PathfinderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            PathfinderError::Network(message)
+            | PathfinderError::Decode(message)
+            | PathfinderError::Store(message)
+            | PathfinderError::DataConsistency(message) => message,
+        };
+        write!(f, "{} error: {}", self.kind(), message)
+    }
+}
+
+impl std::error::Error for PathfinderError {}
+
+impl From<reqwest::Error> for PathfinderError {
+    fn from(error: reqwest::Error) -> Self {
+        PathfinderError::Network(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for PathfinderError {
+    fn from(error: serde_json::Error) -> Self {
+        PathfinderError::Decode(error.to_string())
+    }
+}
+
+impl From<bincode::Error> for PathfinderError {
+    fn from(error: bincode::Error) -> Self {
+        PathfinderError::Decode(error.to_string())
+    }
+}
+
+impl From<std::str::Utf8Error> for PathfinderError {
+    fn from(error: std::str::Utf8Error) -> Self {
+        PathfinderError::Decode(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for PathfinderError {
+    fn from(error: std::io::Error) -> Self {
+        PathfinderError::Store(error.to_string())
+    }
+}
+
+impl From<KvError> for PathfinderError {
+    fn from(error: KvError) -> Self {
+        PathfinderError::Store(error.to_string())
+    }
+}
+
+pub type PathfinderResult<T> = Result<T, PathfinderError>;
@@ -0,0 +1,159 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::graph_store::GraphStore;
+use crate::memory_store::MemoryStore;
+
+// A fixture file is a TOML file of this form:
+//
+// default_distance = 1.0
+//
+// [entities.Q1]
+// label = "first entity"
+// description = "an entity"
+// edges = [["P31", "Q2"]]
+//
+// [[distances]]
+// entities = ["Q1", "Q2"]
+// distance = 0.3
+//
+// [prop_frequencies]
+// P31 = 0.9
+//
+// [redirects]
+// Q3 = "Q1"
+
+#[derive(Deserialize)]
+struct Fixture {
+    #[serde(default = "default_distance")]
+    default_distance: f64,
+    #[serde(default)]
+    entities: HashMap<String, FixtureEntity>,
+    #[serde(default)]
+    distances: Vec<FixtureDistance>,
+    #[serde(default)]
+    prop_frequencies: HashMap<String, f64>,
+    #[serde(default)]
+    redirects: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct FixtureEntity {
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    edges: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct FixtureDistance {
+    entities: (String, String),
+    distance: f64,
+}
+
+fn default_distance() -> f64 {
+    1.0
+}
+
+/// A graph store reading a hand-made graph from a fixture file.
+/// Properties are entities of the fixture as well, such that they can have labels and descriptions.
+pub struct FixtureStore {
+    memory_store: MemoryStore,
+}
+
+impl FixtureStore {
+    /// Loads a FixtureStore from a TOML fixture file.
+    /// # Arguments
+    /// * `fixture_path` - The path of the fixture file
+    /// # Returns
+    /// * The instance, or an error if the fixture file cannot be read or parsed
+    pub fn load(fixture_path: &str) -> PathfinderResult<Self> {
+        let fixture_data = fs::read_to_string(fixture_path).map_err(|error| {
+            PathfinderError::Store(format!("{fixture_path} could not be read: {error}"))
+        })?;
+        let fixture: Fixture = toml::from_str(&fixture_data).map_err(|error| {
+            PathfinderError::Decode(format!("{fixture_path} is invalid: {error}"))
+        })?;
+
+        let mut memory_store = MemoryStore::new(fixture.default_distance);
+
+        for (entity, data) in &fixture.entities {
+            memory_store.set_label(entity, &data.label);
+            memory_store.set_description(entity, &data.description);
+
+            for (prop, object) in &data.edges {
+                memory_store.add_edge(entity, prop, object);
+            }
+        }
+
+        for FixtureDistance { entities, distance } in &fixture.distances {
+            memory_store.set_distance(&entities.0, &entities.1, *distance);
+        }
+
+        for (prop, frequency) in &fixture.prop_frequencies {
+            memory_store.set_prop_frequency(prop, *frequency);
+        }
+
+        for (entity, target) in &fixture.redirects {
+            memory_store.set_redirect(entity, target);
+        }
+
+        Ok(Self { memory_store })
+    }
+}
+
+impl GraphStore for FixtureStore {
+    fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        self.memory_store.get_adjacent_entities(entity)
+    }
+
+    fn get_incoming_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        self.memory_store.get_incoming_entities(entity)
+    }
+
+    fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        self.memory_store.get_label_with_language(entity)
+    }
+
+    fn get_description_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        self.memory_store.get_description_with_language(entity)
+    }
+
+    fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64> {
+        self.memory_store.get_semantic_distance(entity_a, entity_b)
+    }
+
+    fn get_prop_frequency(&self, props: &[String]) -> f64 {
+        self.memory_store.get_prop_frequency(props)
+    }
+
+    fn resolve_entity(&self, entity: &str) -> PathfinderResult<String> {
+        self.memory_store.resolve_entity(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_fixtures_are_errors() {
+        let missing_path =
+            std::env::temp_dir().join(format!("{}_missing.toml", std::process::id()));
+        assert!(matches!(
+            FixtureStore::load(missing_path.to_str().unwrap()),
+            Err(PathfinderError::Store(_))
+        ));
+
+        let invalid_path =
+            std::env::temp_dir().join(format!("{}_invalid.toml", std::process::id()));
+        fs::write(&invalid_path, "[entities.Q1]\nedges = [\"P31\"]\n").unwrap();
+        let result = FixtureStore::load(invalid_path.to_str().unwrap());
+        fs::remove_file(&invalid_path).unwrap();
+        assert!(matches!(result, Err(PathfinderError::Decode(_))));
+    }
+}
@@ -0,0 +1,181 @@
+use std::ops::{AddAssign, Sub};
+
+use crate::entity_id::entity_kind;
+use crate::error::PathfinderResult;
+use crate::literal_hub;
+
+// The prefixes of the Turtle serialization of paths through Wikidata
+const WIKIDATA_PREFIXES: [(&str, &str); 10] = [
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("schema", "http://schema.org/"),
+    ("wd", "http://www.wikidata.org/entity/"),
+    ("wdt", "http://www.wikidata.org/prop/direct/"),
+    ("wikibase", "http://wikiba.se/ontology#"),
+    ("ontolex", "http://www.w3.org/ns/lemon/ontolex#"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("geo", "http://www.opengis.net/ont/geosparql#"),
+];
+
+/// The number of lookups a store could not answer from its local data per kind of data.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheMisses {
+    pub adjacent_entities: usize,
+    pub labels: usize,
+    pub descriptions: usize,
+    pub distances: usize,
+    pub prop_frequencies: usize,
+    pub redirects: usize,
+}
+
+impl CacheMisses {
+    /// Returns the number of misses of all kinds.
+    pub fn total(&self) -> usize {
+        self.adjacent_entities
+            + self.labels
+            + self.descriptions
+            + self.distances
+            + self.prop_frequencies
+            + self.redirects
+    }
+}
+
+impl AddAssign for CacheMisses {
+    fn add_assign(&mut self, other: CacheMisses) {
+        self.adjacent_entities += other.adjacent_entities;
+        self.labels += other.labels;
+        self.descriptions += other.descriptions;
+        self.distances += other.distances;
+        self.prop_frequencies += other.prop_frequencies;
+        self.redirects += other.redirects;
+    }
+}
+
+impl Sub for CacheMisses {
+    type Output = CacheMisses;
+
+    fn sub(self, other: CacheMisses) -> CacheMisses {
+        CacheMisses {
+            adjacent_entities: self.adjacent_entities - other.adjacent_entities,
+            labels: self.labels - other.labels,
+            descriptions: self.descriptions - other.descriptions,
+            distances: self.distances - other.distances,
+            prop_frequencies: self.prop_frequencies - other.prop_frequencies,
+            redirects: self.redirects - other.redirects,
+        }
+    }
+}
+
+/// The data about a knowledge graph required by the pathfinder and the costs calculation.
+/// Implementations may fetch missing data lazily, e.g., from the Wikidata API.
+/// Lookups return an error if the data cannot be read, e.g., because a store is corrupted,
+/// whereas data that is merely unavailable, e.g., due to a failed request, is returned as empty value.
+pub trait GraphStore {
+    /// Returns the properties and entities adjacent to an entity via its outgoing edges.
+    /// # Arguments
+    /// * `entity` - The entity
+    /// # Returns
+    /// * A list of tuples consisting of a property and an adjacent entity
+    fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>>;
+
+    /// Returns the properties and entities pointing to an entity via their outgoing edges.
+    /// Only edges known to the store are returned, so no external services are called.
+    /// # Arguments
+    /// * `entity` - The entity
+    /// # Returns
+    /// * A list of tuples consisting of a property and an entity having an edge to the entity
+    fn get_incoming_entities(&self, _entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        Ok(vec![])
+    }
+
+    /// Returns the label of an entity and the language of the label.
+    /// The language is empty if it is unknown.
+    fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)>;
+
+    /// Returns the description of an entity and the language of the description.
+    /// The language is empty if it is unknown.
+    fn get_description_with_language(&self, entity: &str) -> PathfinderResult<(String, String)>;
+
+    /// Returns the label of an entity.
+    fn get_label(&self, entity: &str) -> PathfinderResult<String> {
+        Ok(self.get_label_with_language(entity)?.0)
+    }
+
+    /// Returns the description of an entity.
+    fn get_description(&self, entity: &str) -> PathfinderResult<String> {
+        Ok(self.get_description_with_language(entity)?.0)
+    }
+
+    /// Returns the language labels and descriptions are preferably returned in.
+    fn preferred_language(&self) -> &str {
+        ""
+    }
+
+    /// Returns the semantic distance between two entities.
+    fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64>;
+
+    /// Prepares the semantic distances between several entities and a target entity, e.g., by embedding them with a single request.
+    fn prefetch_embeddings(&self, _entities: &[String], _target: &str) -> PathfinderResult<()> {
+        Ok(())
+    }
+
+    /// Returns the aggregated normalised frequency of one or more properties, e.g., their average frequency.
+    fn get_prop_frequency(&self, props: &[String]) -> f64;
+
+    /// Normalises the notation of an entity reference, e.g., wd:Q42 to Q42, without following redirects.
+    fn normalize_entity(&self, entity: &str) -> String {
+        entity.to_owned()
+    }
+
+    /// Resolves an entity reference to the entity that should be used for the search, e.g., by following redirects.
+    fn resolve_entity(&self, entity: &str) -> PathfinderResult<String> {
+        Ok(self.normalize_entity(entity))
+    }
+
+    /// Returns the number of failed requests for adjacent entities since the store was created.
+    /// Failed requests for other data, e.g., embeddings, are not counted, as the search continues with the graph as known.
+    fn adjacency_failure_count(&self) -> usize {
+        0
+    }
+
+    /// Returns the number of lookups that could not be answered from local data since the store was created.
+    fn cache_misses(&self) -> CacheMisses {
+        CacheMisses::default()
+    }
+
+    /// Returns the prefixes used by the Turtle terms of this store as pairs of prefix and namespace IRI.
+    fn turtle_prefixes(&self) -> Vec<(String, String)> {
+        WIKIDATA_PREFIXES
+            .iter()
+            .map(|(prefix, namespace)| (prefix.to_string(), namespace.to_string()))
+            .collect()
+    }
+
+    /// Serializes an entity or property as subject or object of a Turtle triple, e.g., wd:Q42.
+    /// Literal hubs become typed literals.
+    fn entity_term(&self, entity: &str) -> String {
+        if literal_hub::is_literal_hub(entity) {
+            literal_hub::turtle_literal(entity)
+        } else {
+            format!("wd:{entity}")
+        }
+    }
+
+    /// Serializes a property as predicate of a Turtle triple, e.g., wdt:P31.
+    fn prop_term(&self, prop: &str) -> String {
+        format!("wdt:{prop}")
+    }
+
+    /// Returns the Turtle predicate for the label of an entity, e.g., lemmas for lexemes.
+    fn label_term(&self, entity: &str) -> String {
+        entity_kind(entity)
+            .map_or("rdfs:label", |kind| kind.label_predicate())
+            .to_owned()
+    }
+
+    /// Returns the Turtle predicate for the description of an entity.
+    fn description_term(&self, _entity: &str) -> String {
+        "schema:description".to_owned()
+    }
+}
@@ -0,0 +1,499 @@
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use log::info;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+
+use crate::entity_id::{entity_kind, EntityKind};
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::store_connector::{StoreConnector, SOURCE_TRUTHY_DUMP};
+use crate::store_schema::StoreParameters;
+
+const ENTITY_IRI: &str = "http://www.wikidata.org/entity/";
+const DIRECT_PROP_IRI: &str = "http://www.wikidata.org/prop/direct/";
+const LABEL_IRIS: [&str; 2] = [
+    "http://www.w3.org/2000/01/rdf-schema#label",
+    "http://wikiba.se/ontology#lemma",
+];
+const DESCRIPTION_IRI: &str = "http://schema.org/description";
+
+/// The number of subjects whose data is written to the stores at once
+pub const BATCH_SIZE: usize = 10_000;
+
+/// Opens a possibly compressed dump file for reading line by line.
+/// The compression is determined by the file extension (.gz or .bz2).
+/// Returns an error if the file cannot be opened.
+pub fn open_dump(dump_path: &str) -> PathfinderResult<Box<dyn BufRead>> {
+    let file = File::open(dump_path).map_err(|error| {
+        PathfinderError::Store(format!(
+            "Dump file {dump_path} could not be opened: {error}"
+        ))
+    })?;
+
+    // dumps consist of several compressed streams, so multi stream decoders are required
+    Ok(if dump_path.ends_with(".gz") {
+        Box::new(BufReader::with_capacity(
+            1 << 20,
+            MultiGzDecoder::new(BufReader::new(file)),
+        ))
+    } else if dump_path.ends_with(".bz2") {
+        Box::new(BufReader::with_capacity(
+            1 << 20,
+            MultiBzDecoder::new(BufReader::new(file)),
+        ))
+    } else {
+        Box::new(BufReader::with_capacity(1 << 20, file))
+    })
+}
+
+/// Reads a file with one entity ID per line, e.g., to restrict an import to a subset of entities.
+/// Returns an error if the file cannot be read.
+pub fn read_entity_filter(filter_path: &str) -> PathfinderResult<HashSet<String>> {
+    let content = fs::read_to_string(filter_path).map_err(|error| {
+        PathfinderError::Store(format!(
+            "Entity filter file {filter_path} could not be read: {error}"
+        ))
+    })?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+// The object of an N-Triples statement; only IRIs and language-tagged literals are relevant for the import.
+enum Object<'l> {
+    Iri(&'l str),
+    Literal { value: String, language: &'l str },
+}
+
+/// The data collected for a single subject of a dump.
+#[derive(Default)]
+pub struct SubjectData {
+    pub adjacent_entities: Vec<String>,
+    pub labels: HashMap<String, String>,
+    pub descriptions: HashMap<String, String>,
+}
+
+impl SubjectData {
+    /// Adds the subject, the properties, and the adjacent entities of the subject to a set of entities.
+    /// These entities require labels and descriptions for the filters of the Wikidata API.
+    pub fn collect_entities(&self, subject: &str, entities: &mut HashSet<String>) {
+        entities.insert(subject.to_owned());
+        for edge in &self.adjacent_entities {
+            let (prop, adjacent_entity) = edge.split_once('-').unwrap();
+            entities.insert(prop.to_owned());
+            entities.insert(adjacent_entity.to_owned());
+        }
+    }
+}
+
+/// Imports adjacency lists, labels, and descriptions from a Wikidata truthy N-Triples dump (latest-truthy.nt).
+/// The same filters as in the Wikidata API are applied: only edges between entities having a label and a description
+/// in English via properties having a label and a description in English are kept.
+/// # Arguments
+/// * `store_connector` - The StoreConnector writing to the stores
+/// * `dump_path` - The path of the dump, optionally compressed with gzip or bzip2
+/// * `entity_filter` - If set, only the adjacency lists of these entities are imported
+/// # Returns
+/// * An error if the dump cannot be read or the stores cannot be written
+pub fn import_truthy_dump(
+    store_connector: &StoreConnector,
+    dump_path: &str,
+    entity_filter: Option<HashSet<String>>,
+) -> PathfinderResult<()> {
+    let languages = import_languages(store_connector);
+    let parameters = import_parameters(dump_path, entity_filter.as_ref(), &languages);
+
+    // without a filter, labels and descriptions of all entities are imported in a single pass
+    // with a filter, only the labels and descriptions of entities on the imported edges are imported in a second pass
+    info!("Importing adjacency lists from {}.", dump_path);
+    let mut required_entities: HashSet<String> = HashSet::new();
+    let imported_subjects = import_pass(
+        store_connector,
+        dump_path,
+        &languages,
+        |subject, data| match &entity_filter {
+            Some(filter) if filter.contains(subject) => {
+                data.collect_entities(subject, &mut required_entities);
+                (true, false)
+            }
+            Some(_) => (false, false),
+            None => (true, true),
+        },
+    )?;
+
+    if entity_filter.is_some() {
+        info!(
+            "Importing labels and descriptions of {} entities from {}.",
+            required_entities.len(),
+            dump_path
+        );
+        import_pass(store_connector, dump_path, &languages, |subject, _| {
+            (false, required_entities.contains(subject))
+        })?;
+    }
+
+    remove_unlabeled_edges(store_connector, &imported_subjects)?;
+    store_connector.record_import(SOURCE_TRUTHY_DUMP, parameters)
+}
+
+/// Returns the parameters of an import, which are recorded in the metadata of the stores.
+/// # Arguments
+/// * `dump_path` - The path of the dump
+/// * `entity_filter` - The entities whose data is imported, if filtered
+/// * `languages` - The languages in which labels and descriptions are imported
+pub fn import_parameters(
+    dump_path: &str,
+    entity_filter: Option<&HashSet<String>>,
+    languages: &[String],
+) -> StoreParameters {
+    vec![
+        ("dump", dump_path.to_owned()),
+        (
+            "entity_filter",
+            entity_filter.map_or_else(
+                || "none".to_owned(),
+                |filter| format!("{} entities", filter.len()),
+            ),
+        ),
+        ("languages", languages.join(",")),
+    ]
+}
+
+/// Returns the languages in which labels and descriptions are imported.
+/// Besides the fallback chain, English labels and descriptions are required for the filters of the Wikidata API.
+pub fn import_languages(store_connector: &StoreConnector) -> Vec<String> {
+    let mut languages = store_connector.languages().to_vec();
+    if !languages.iter().any(|language| language == "en") {
+        languages.push("en".to_owned());
+    }
+    languages
+}
+
+/// Applies the filters of the Wikidata API to the imported adjacency lists: only edges between entities having a label
+/// and a description in English via properties having a label and a description in English are kept.
+/// Lists that were not written by the import are left as they are.
+/// # Arguments
+/// * `store_connector` - The StoreConnector writing to the stores
+/// * `imported_subjects` - The entities whose adjacency lists were written by the import
+pub fn remove_unlabeled_edges(
+    store_connector: &StoreConnector,
+    imported_subjects: &HashSet<String>,
+) -> PathfinderResult<()> {
+    let removed_count =
+        store_connector.retain_adjacent_entities(imported_subjects, |subject, prop, object| {
+            for entity in [subject, prop, object] {
+                if !store_connector.has_label_and_description(entity)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })?;
+
+    info!(
+        "Import finished; {} edges were removed as labels or descriptions are missing.",
+        removed_count
+    );
+    Ok(())
+}
+
+/// Collects the data of subjects while reading a dump and writes it to the stores in batches.
+pub struct ImportWriter<'s, 'a> {
+    store_connector: &'s StoreConnector<'a>,
+    languages: &'s [String],
+    source: &'static str,
+    adjacency_lists: Vec<(String, Vec<String>)>,
+    language_data: Vec<(String, SubjectData)>,
+    imported_subjects: HashSet<String>,
+}
+
+impl<'s, 'a> ImportWriter<'s, 'a> {
+    pub fn new(
+        store_connector: &'s StoreConnector<'a>,
+        languages: &'s [String],
+        source: &'static str,
+    ) -> Self {
+        ImportWriter {
+            store_connector,
+            languages,
+            source,
+            adjacency_lists: vec![],
+            language_data: vec![],
+            imported_subjects: HashSet::new(),
+        }
+    }
+
+    /// Returns the subjects whose adjacency lists were written by this writer.
+    pub fn into_imported_subjects(self) -> HashSet<String> {
+        self.imported_subjects
+    }
+
+    /// Adds the adjacency list and/or the labels and descriptions of a subject to the current batch.
+    pub fn add(
+        &mut self,
+        subject: String,
+        mut data: SubjectData,
+        store_edges: bool,
+        store_language_data: bool,
+    ) {
+        if store_edges {
+            data.adjacent_entities.sort();
+            data.adjacent_entities.dedup();
+            self.imported_subjects.insert(subject.clone());
+            self.adjacency_lists
+                .push((subject.clone(), std::mem::take(&mut data.adjacent_entities)));
+        }
+
+        if store_language_data {
+            self.language_data.push((subject, data));
+        }
+    }
+
+    /// Writes the current batch to the stores.
+    pub fn flush(&mut self) -> PathfinderResult<()> {
+        let store_connector = self.store_connector;
+
+        // dumps are grouped by subject, but lists are merged in case a subject occurs more than once
+        store_connector.store_adjacency_lists(
+            std::mem::take(&mut self.adjacency_lists),
+            true,
+            self.source,
+        )?;
+
+        for language in self.languages {
+            store_connector.store_labels(
+                self.language_data.iter().filter_map(|(e, data)| {
+                    Some((e.as_str(), data.labels.get(language)?.as_str()))
+                }),
+                language,
+            )?;
+            store_connector.store_descriptions(
+                self.language_data.iter().filter_map(|(e, data)| {
+                    Some((e.as_str(), data.descriptions.get(language)?.as_str()))
+                }),
+                language,
+            )?;
+        }
+
+        // languages without label or description are marked such that they are not requested from the Wikidata API
+        let entities: Vec<String> = self
+            .language_data
+            .drain(..)
+            .map(|(entity, _)| entity)
+            .collect();
+        store_connector.mark_missing_languages(&entities)
+    }
+}
+
+// Reads the dump once and passes the data of each subject to the select closure.
+// The closure decides whether the adjacency list and whether the labels and descriptions of the subject are stored.
+// Returns the subjects whose adjacency lists were stored.
+fn import_pass(
+    store_connector: &StoreConnector,
+    dump_path: &str,
+    languages: &[String],
+    mut select: impl FnMut(&str, &SubjectData) -> (bool, bool),
+) -> PathfinderResult<HashSet<String>> {
+    let mut writer = ImportWriter::new(store_connector, languages, SOURCE_TRUTHY_DUMP);
+
+    let mut current_subject = String::new();
+    let mut current_data = SubjectData::default();
+    let mut subject_count = 0;
+
+    let mut finish_subject = |subject: String, data: SubjectData, writer: &mut ImportWriter| {
+        let (store_edges, store_language_data) = select(&subject, &data);
+        writer.add(subject, data, store_edges, store_language_data);
+    };
+
+    for (line_number, line) in open_dump(dump_path)?.lines().enumerate() {
+        let line = line?;
+
+        if line_number % 10_000_000 == 0 && line_number > 0 {
+            info!("{} lines processed.", line_number);
+        }
+
+        let Some((subject, predicate, object)) = parse_triple(&line) else {
+            continue;
+        };
+
+        // only statements about Wikibase entities are relevant
+        let Some(subject) = subject.strip_prefix(ENTITY_IRI) else {
+            continue;
+        };
+        if entity_kind(subject).is_none() {
+            continue;
+        }
+
+        if subject != current_subject {
+            if !current_subject.is_empty() {
+                finish_subject(
+                    std::mem::take(&mut current_subject),
+                    std::mem::take(&mut current_data),
+                    &mut writer,
+                );
+                subject_count += 1;
+
+                if subject_count % BATCH_SIZE == 0 {
+                    writer.flush()?;
+                }
+            }
+            current_subject = subject.to_owned();
+        }
+
+        match object {
+            Object::Iri(object) => {
+                let (Some(prop), Some(object)) = (
+                    predicate.strip_prefix(DIRECT_PROP_IRI),
+                    object.strip_prefix(ENTITY_IRI),
+                ) else {
+                    continue;
+                };
+
+                if entity_kind(prop) == Some(EntityKind::Property) && entity_kind(object).is_some()
+                {
+                    current_data
+                        .adjacent_entities
+                        .push(format!("{prop}-{object}"));
+                }
+            }
+            Object::Literal { value, language } => {
+                if !languages.iter().any(|l| l == language) {
+                    continue;
+                }
+
+                if LABEL_IRIS.contains(&predicate) {
+                    current_data.labels.insert(language.to_owned(), value);
+                } else if predicate == DESCRIPTION_IRI {
+                    current_data.descriptions.insert(language.to_owned(), value);
+                }
+            }
+        }
+    }
+
+    if !current_subject.is_empty() {
+        finish_subject(current_subject, current_data, &mut writer);
+    }
+
+    writer.flush()?;
+    Ok(writer.into_imported_subjects())
+}
+
+// Parses a single N-Triples line into subject IRI, predicate IRI, and object.
+// Returns None for lines that are empty, comments, or have a literal object without language tag.
+fn parse_triple(line: &str) -> Option<(&str, &str, Object<'_>)> {
+    let (subject, rest) = parse_iri(line.trim_start())?;
+    let (predicate, rest) = parse_iri(rest.trim_start())?;
+    let rest = rest.trim_start();
+
+    if rest.starts_with('<') {
+        let (object, _) = parse_iri(rest)?;
+        return Some((subject, predicate, Object::Iri(object)));
+    }
+
+    let (value, rest) = parse_literal(rest)?;
+    let language = rest.strip_prefix('@')?.split_whitespace().next()?;
+
+    Some((subject, predicate, Object::Literal { value, language }))
+}
+
+// Parses an IRI enclosed in angle brackets and returns it along with the remaining text.
+fn parse_iri(text: &str) -> Option<(&str, &str)> {
+    let text = text.strip_prefix('<')?;
+    let end = text.find('>')?;
+    Some((&text[..end], &text[end + 1..]))
+}
+
+// Parses a quoted literal, resolves escape sequences, and returns it along with the remaining text.
+fn parse_literal(text: &str) -> Option<(String, &str)> {
+    let text = text.strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &text[index + 1..])),
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                match escaped {
+                    't' => value.push('\t'),
+                    'b' => value.push('\u{8}'),
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    'f' => value.push('\u{c}'),
+                    'u' | 'U' => {
+                        let length = if escaped == 'u' { 4 } else { 8 };
+                        let hex: String = chars.by_ref().take(length).map(|(_, h)| h).collect();
+                        value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                    }
+                    other => value.push(other),
+                }
+            }
+            _ => value.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triples_with_iri_objects_are_parsed() {
+        let line =
+            "<http://www.wikidata.org/entity/Q42> <http://www.wikidata.org/prop/direct/P31> \
+                    <http://www.wikidata.org/entity/Q5> .";
+
+        let Some((subject, predicate, Object::Iri(object))) = parse_triple(line) else {
+            panic!("Triple was not parsed.");
+        };
+        assert_eq!(subject, "http://www.wikidata.org/entity/Q42");
+        assert_eq!(predicate, "http://www.wikidata.org/prop/direct/P31");
+        assert_eq!(object, "http://www.wikidata.org/entity/Q5");
+    }
+
+    #[test]
+    fn triples_with_language_tagged_literals_are_parsed() {
+        let line = "<http://www.wikidata.org/entity/Q42> <http://schema.org/description> \
+                    \"English writer\"@en-gb .";
+
+        let Some((_, predicate, Object::Literal { value, language })) = parse_triple(line) else {
+            panic!("Triple was not parsed.");
+        };
+        assert_eq!(predicate, DESCRIPTION_IRI);
+        assert_eq!(value, "English writer");
+        assert_eq!(language, "en-gb");
+    }
+
+    #[test]
+    fn irrelevant_lines_are_skipped() {
+        assert!(parse_triple("").is_none());
+        assert!(parse_triple("# a comment").is_none());
+        // literals without language tag, e.g., typed literals
+        assert!(parse_triple(
+            "<http://www.wikidata.org/entity/Q42> <http://www.wikidata.org/prop/direct/P569> \
+             \"1952-03-11T00:00:00Z\"^^<http://www.w3.org/2001/XMLSchema#dateTime> ."
+        )
+        .is_none());
+        assert!(parse_triple("<http://www.wikidata.org/entity/Q42> <broken").is_none());
+    }
+
+    #[test]
+    fn literal_escapes_are_resolved() {
+        assert_eq!(
+            parse_literal(r#""a \"quoted\" \\ value\ttab"@en ."#),
+            Some(("a \"quoted\" \\ value\ttab".to_owned(), "@en ."))
+        );
+        assert_eq!(
+            parse_literal(r#""café \U0001F600"@fr"#),
+            Some(("café 😀".to_owned(), "@fr"))
+        );
+        assert_eq!(parse_literal(r#""unterminated"#), None);
+        assert_eq!(parse_literal(r#""invalid \u00G9""#), None);
+    }
+}
@@ -0,0 +1,351 @@
+use log::{info, warn};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+
+use crate::error::PathfinderResult;
+use crate::importer::{self, ImportWriter, SubjectData, BATCH_SIZE};
+use crate::store_connector::{Statement, StoreConnector, SOURCE_JSON_DUMP};
+
+/// Imports adjacency lists, statements with rank and qualifiers, labels, descriptions, and sitelink counts
+/// from the line-delimited Wikidata JSON dump (latest-all.json).
+/// Like the Wikidata API, only the best ranked entity values of each property become edges.
+/// # Arguments
+/// * `store_connector` - The StoreConnector writing to the stores
+/// * `dump_path` - The path of the dump, optionally compressed with gzip or bzip2
+/// * `entity_filter` - If set, only the statements, sitelink counts, and adjacency lists of these entities are imported
+/// * `offset` - The number of dump lines to skip, e.g., to resume an interrupted import
+/// # Returns
+/// * An error if the dump cannot be read or the stores cannot be written
+pub fn import_json_dump(
+    store_connector: &StoreConnector,
+    dump_path: &str,
+    entity_filter: Option<HashSet<String>>,
+    offset: usize,
+) -> PathfinderResult<()> {
+    let languages = importer::import_languages(store_connector);
+    let mut parameters = importer::import_parameters(dump_path, entity_filter.as_ref(), &languages);
+    parameters.push(("offset", offset.to_string()));
+
+    info!(
+        "Importing entities from {} starting at line {}.",
+        dump_path, offset
+    );
+    let mut writer = ImportWriter::new(store_connector, &languages, SOURCE_JSON_DUMP);
+    let mut statements: Vec<(String, Vec<Statement>)> = vec![];
+    let mut sitelink_counts: Vec<(String, usize)> = vec![];
+    let mut line_number = offset;
+    let mut malformed_count = 0;
+
+    // skipped lines are only searched for their line breaks, they are neither decoded nor parsed
+    let mut reader = importer::open_dump(dump_path)?;
+    for _ in 0..offset {
+        if reader.skip_until(b'\n')? == 0 {
+            break;
+        }
+    }
+
+    for line in reader.lines() {
+        let line = line?;
+        line_number += 1;
+
+        // the dump is a JSON array with one entity per line
+        let Some(entity) = parse_entity_line(&line, line_number, &mut malformed_count) else {
+            continue;
+        };
+        let id = entity["id"].as_str().unwrap().to_owned();
+
+        if entity_filter
+            .as_ref()
+            .is_some_and(|filter| !filter.contains(&id))
+        {
+            continue;
+        }
+
+        let entity_statements = extract_statements(&entity);
+        let data = SubjectData {
+            adjacent_entities: best_ranked_edges(&entity_statements),
+            labels: extract_language_values(&entity["labels"], &languages),
+            descriptions: extract_language_values(&entity["descriptions"], &languages),
+        };
+
+        sitelink_counts.push((
+            id.clone(),
+            entity["sitelinks"].as_object().map_or(0, |s| s.len()),
+        ));
+        statements.push((id.clone(), entity_statements));
+
+        // with a filter, labels and descriptions of the adjacent entities are imported in a second pass
+        writer.add(id, data, true, entity_filter.is_none());
+
+        if statements.len() == BATCH_SIZE {
+            writer.flush()?;
+            store_connector.store_statements(std::mem::take(&mut statements))?;
+            store_connector.store_sitelink_counts(std::mem::take(&mut sitelink_counts))?;
+            info!(
+                "Entities up to line {} imported; resume with offset {}.",
+                line_number, line_number
+            );
+        }
+    }
+
+    writer.flush()?;
+    store_connector.store_statements(statements)?;
+    store_connector.store_sitelink_counts(sitelink_counts)?;
+    info!("Entities up to line {} imported.", line_number);
+    if malformed_count > 0 {
+        warn!("{} malformed lines were skipped.", malformed_count);
+    }
+    let imported_subjects = writer.into_imported_subjects();
+
+    if let Some(filter) = entity_filter {
+        // the adjacency lists are read from the stores, so entities imported before a resumed import are covered
+        let mut required_entities: HashSet<String> = HashSet::new();
+        for entity in &filter {
+            let data = SubjectData {
+                adjacent_entities: store_connector
+                    .read_adjacency_list(entity)?
+                    .unwrap_or_default(),
+                ..SubjectData::default()
+            };
+            data.collect_entities(entity, &mut required_entities);
+        }
+
+        info!(
+            "Importing labels and descriptions of {} entities from {}.",
+            required_entities.len(),
+            dump_path
+        );
+        import_required_language_data(store_connector, dump_path, &languages, &required_entities)?;
+    }
+
+    importer::remove_unlabeled_edges(store_connector, &imported_subjects)?;
+    store_connector.record_import(SOURCE_JSON_DUMP, parameters)
+}
+
+// Reads the labels and descriptions of the required entities from the dump.
+fn import_required_language_data(
+    store_connector: &StoreConnector,
+    dump_path: &str,
+    languages: &[String],
+    required_entities: &HashSet<String>,
+) -> PathfinderResult<()> {
+    let mut writer = ImportWriter::new(store_connector, languages, SOURCE_JSON_DUMP);
+    let mut entity_count = 0;
+    let mut malformed_count = 0;
+
+    for (index, line) in importer::open_dump(dump_path)?.lines().enumerate() {
+        let line = line?;
+
+        let Some(entity) = parse_entity_line(&line, index + 1, &mut malformed_count) else {
+            continue;
+        };
+        let id = entity["id"].as_str().unwrap();
+
+        if !required_entities.contains(id) {
+            continue;
+        }
+
+        let data = SubjectData {
+            labels: extract_language_values(&entity["labels"], languages),
+            descriptions: extract_language_values(&entity["descriptions"], languages),
+            ..SubjectData::default()
+        };
+        writer.add(id.to_owned(), data, false, true);
+
+        entity_count += 1;
+        if entity_count % BATCH_SIZE == 0 {
+            writer.flush()?;
+        }
+    }
+
+    if malformed_count > 0 {
+        warn!("{} malformed lines were skipped.", malformed_count);
+    }
+    writer.flush()
+}
+
+// Parses a line of the dump; the opening and closing brackets of the array are skipped.
+// Lines that are not a JSON object with an ID, e.g., truncated lines, are logged, counted, and skipped.
+fn parse_entity_line(line: &str, line_number: usize, malformed_count: &mut usize) -> Option<Value> {
+    let line = line.trim().trim_end_matches(',');
+    if line.is_empty() || line == "[" || line == "]" {
+        return None;
+    }
+
+    match serde_json::from_str::<Value>(line) {
+        Ok(entity) if entity["id"].is_string() => Some(entity),
+        Ok(_) => {
+            warn!("Line {} of the dump is not an entity.", line_number);
+            *malformed_count += 1;
+            None
+        }
+        Err(error) => {
+            warn!("Line {} of the dump is malformed: {}", line_number, error);
+            *malformed_count += 1;
+            None
+        }
+    }
+}
+
+// Extracts the values of the specified languages from the labels or descriptions of an entity.
+fn extract_language_values(values: &Value, languages: &[String]) -> HashMap<String, String> {
+    languages
+        .iter()
+        .filter_map(|language| {
+            let value = values[language.as_str()]["value"].as_str()?;
+            Some((language.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+// Extracts all statements of an entity whose main value is an entity.
+fn extract_statements(entity: &Value) -> Vec<Statement> {
+    let Some(claims) = entity["claims"].as_object() else {
+        return vec![];
+    };
+
+    let mut statements = vec![];
+
+    for (prop, its_claims) in claims {
+        // the claims of a property are a list in well-formed dumps, other values are skipped
+        let Some(its_claims) = its_claims.as_array() else {
+            continue;
+        };
+
+        for claim in its_claims {
+            let mainsnak = &claim["mainsnak"];
+            if mainsnak["datavalue"]["type"] != "wikibase-entityid" {
+                continue;
+            }
+
+            let Some(value) = snak_value(mainsnak) else {
+                continue;
+            };
+
+            // qualifiers are kept in the order of the dump
+            let qualifiers = claim["qualifiers-order"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .flat_map(|qualifier_prop| {
+                    claim["qualifiers"][qualifier_prop]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|snak| Some((qualifier_prop.to_owned(), snak_value(snak)?)))
+                })
+                .collect();
+
+            statements.push(Statement {
+                prop: prop.to_owned(),
+                value,
+                rank: claim["rank"].as_str().unwrap_or("normal").to_owned(),
+                qualifiers,
+            });
+        }
+    }
+
+    statements
+}
+
+// Renders the value of a snak as a string, e.g., Q5, +2001-01-01T00:00:00Z, or 52.5,13.4.
+// Snaks without a specific value are rendered as somevalue or novalue.
+fn snak_value(snak: &Value) -> Option<String> {
+    match snak["snaktype"].as_str()? {
+        "value" => {}
+        other => return Some(other.to_owned()),
+    }
+
+    let value = &snak["datavalue"]["value"];
+
+    let rendered = match snak["datavalue"]["type"].as_str()? {
+        "wikibase-entityid" => value["id"].as_str()?.to_owned(),
+        "string" => value.as_str()?.to_owned(),
+        "time" => value["time"].as_str()?.to_owned(),
+        "quantity" => value["amount"].as_str()?.to_owned(),
+        "monolingualtext" => value["text"].as_str()?.to_owned(),
+        "globecoordinate" => format!("{},{}", value["latitude"], value["longitude"]),
+        _ => value.to_string(),
+    };
+
+    Some(rendered)
+}
+
+// Selects the edges of the best ranked statements of each property, as in the truthy dump:
+// preferred statements if a property has any, normal statements otherwise; deprecated statements are never selected.
+fn best_ranked_edges(statements: &[Statement]) -> Vec<String> {
+    let preferred_props: HashSet<&str> = statements
+        .iter()
+        .filter(|statement| statement.rank == "preferred")
+        .map(|statement| statement.prop.as_str())
+        .collect();
+
+    statements
+        .iter()
+        .filter(|statement| {
+            if preferred_props.contains(statement.prop.as_str()) {
+                statement.rank == "preferred"
+            } else {
+                statement.rank == "normal"
+            }
+        })
+        .map(|statement| format!("{}-{}", statement.prop, statement.value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let mut malformed_count = 0;
+
+        assert!(parse_entity_line("[", 1, &mut malformed_count).is_none());
+        assert!(parse_entity_line("]", 4, &mut malformed_count).is_none());
+        assert_eq!(malformed_count, 0);
+
+        assert!(parse_entity_line(r#"{"id": "Q42", "claims""#, 2, &mut malformed_count).is_none());
+        assert!(parse_entity_line(r#"{"type": "item"},"#, 3, &mut malformed_count).is_none());
+        assert_eq!(malformed_count, 2);
+
+        let entity = parse_entity_line(r#"{"id": "Q42"},"#, 3, &mut malformed_count).unwrap();
+        assert_eq!(entity["id"], "Q42");
+    }
+
+    #[test]
+    fn malformed_claims_are_skipped() {
+        let entity: Value = serde_json::from_str(
+            r#"{"id": "Q42", "claims": {
+                "P31": {"mainsnak": {}},
+                "P27": [{"mainsnak": {"snaktype": "value", "datavalue": {"type": "wikibase-entityid", "value": {"id": "Q145"}}}, "rank": "preferred"}]
+            }}"#,
+        )
+        .unwrap();
+
+        let statements = extract_statements(&entity);
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].prop, "P27");
+        assert_eq!(statements[0].value, "Q145");
+    }
+
+    #[test]
+    fn only_best_ranked_statements_become_edges() {
+        let statement = |prop: &str, value: &str, rank: &str| Statement {
+            prop: prop.to_owned(),
+            value: value.to_owned(),
+            rank: rank.to_owned(),
+            qualifiers: vec![],
+        };
+        let statements = [
+            statement("P31", "Q5", "normal"),
+            statement("P27", "Q145", "preferred"),
+            statement("P27", "Q174193", "normal"),
+            statement("P69", "Q691283", "deprecated"),
+        ];
+
+        assert_eq!(best_ranked_edges(&statements), ["P31-Q5", "P27-Q145"]);
+    }
+}
@@ -0,0 +1,325 @@
+use log::info;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+
+use crate::embedding_store::cosine_distance;
+use crate::error::{PathfinderError, PathfinderResult};
+
+// The namespace of Wikidata entity IRIs, which is removed from the entity names of exports such as PyTorch-BigGraph
+const WIKIDATA_ENTITY_NAMESPACE: &str = "http://www.wikidata.org/entity/";
+
+// The number of matrix values converted at once while reading an NPY file
+const NPY_CHUNK_VALUES: usize = 1 << 16;
+
+/// Pre-trained knowledge graph embeddings of entities, e.g., TransE or ComplEx vectors.
+/// The vectors are read from a TSV file with an entity and its values per line,
+/// or from an NPY matrix with one row per entity along with a file of entity names.
+pub struct KgEmbeddings {
+    index: HashMap<String, usize>,
+    vectors: Vec<f32>,
+    dimensions: usize,
+}
+
+impl KgEmbeddings {
+    /// Loads KgEmbeddings from a TSV or NPY file.
+    /// # Arguments
+    /// * `embedding_path` - The TSV file or the NPY file with a matrix of f4 or f8 values
+    /// * `entity_names_path` - For NPY files, a JSON array or a text file with one entity per line in the order of the rows
+    /// # Returns
+    /// * The instance or an error if a file cannot be read or the names do not match the rows of the matrix
+    pub fn load(embedding_path: &str, entity_names_path: &str) -> PathfinderResult<Self> {
+        info!(
+            "Loading knowledge graph embeddings from {}.",
+            embedding_path
+        );
+
+        let file = File::open(embedding_path).map_err(|error| {
+            PathfinderError::Store(format!("{embedding_path} could not be opened: {error}"))
+        })?;
+        let file_length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let (names, vectors, dimensions) = if embedding_path.ends_with(".npy") {
+            let (vectors, dimensions) = read_npy(&mut reader, file_length)?;
+            (read_entity_names(entity_names_path)?, vectors, dimensions)
+        } else {
+            read_tsv(reader)?
+        };
+
+        if names.len() * dimensions != vectors.len() {
+            return Err(PathfinderError::DataConsistency(format!(
+                "{} entity names do not match {} embedding values with {} dimensions.",
+                names.len(),
+                vectors.len(),
+                dimensions
+            )));
+        }
+
+        info!(
+            "Loaded knowledge graph embeddings of {} entities with {} dimensions.",
+            names.len(),
+            dimensions
+        );
+
+        Ok(Self {
+            index: names
+                .into_iter()
+                .enumerate()
+                .map(|(row, name)| (normalize_entity_name(&name), row))
+                .collect(),
+            vectors,
+            dimensions,
+        })
+    }
+
+    /// Returns the cosine distance between the embeddings of two entities.
+    /// Returns None if an entity has no embedding or an embedding is zero.
+    pub fn distance(&self, entity_a: &str, entity_b: &str) -> Option<f64> {
+        let vector_a = self.vector(entity_a)?;
+        let vector_b = self.vector(entity_b)?;
+
+        cosine_distance(vector_a, vector_b)
+    }
+
+    fn vector(&self, entity: &str) -> Option<&[f32]> {
+        let row = *self.index.get(entity)?;
+        Some(&self.vectors[row * self.dimensions..(row + 1) * self.dimensions])
+    }
+}
+
+// Removes angle brackets and the Wikidata namespace from an entity name, e.g., <http://www.wikidata.org/entity/Q42>.
+fn normalize_entity_name(name: &str) -> String {
+    let name = name.trim();
+    let name = name
+        .strip_prefix('<')
+        .and_then(|n| n.strip_suffix('>'))
+        .unwrap_or(name);
+
+    name.strip_prefix(WIKIDATA_ENTITY_NAMESPACE)
+        .unwrap_or(name)
+        .to_owned()
+}
+
+// Reads embeddings from a TSV file with an entity name followed by its values per line.
+// Blank lines are skipped; all other lines must have as many values as the first one.
+fn read_tsv(reader: impl BufRead) -> PathfinderResult<(Vec<String>, Vec<f32>, usize)> {
+    let (mut names, mut vectors, mut dimensions) = (vec![], vec![], None);
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid_line = |reason: String| {
+            PathfinderError::Decode(format!(
+                "Line {} of the knowledge graph embeddings {reason}.",
+                index + 1
+            ))
+        };
+
+        let mut values = line.split('\t');
+        let name = values.next().unwrap_or_default();
+        let vector = values
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|error| invalid_line(format!("has a value that is no number: {error}")))?;
+
+        let expected_dimensions = *dimensions.get_or_insert(vector.len());
+        if vector.len() != expected_dimensions {
+            return Err(invalid_line(format!(
+                "has {} instead of {} values",
+                vector.len(),
+                expected_dimensions
+            )));
+        }
+
+        vectors.extend(vector);
+        names.push(name.to_owned());
+    }
+
+    Ok((names, vectors, dimensions.unwrap_or_default()))
+}
+
+// Reads entity names from a JSON array, as written by PyTorch-BigGraph, or from a text file with one name per line.
+fn read_entity_names(path: &str) -> PathfinderResult<Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|error| {
+        PathfinderError::Store(format!(
+            "Entity names could not be read from {path}: {error}"
+        ))
+    })?;
+
+    if path.ends_with(".json") {
+        serde_json::from_str(&content).map_err(|error| {
+            PathfinderError::Decode(format!(
+                "Entity names are no JSON array of strings: {error}"
+            ))
+        })
+    } else {
+        Ok(content.lines().map(str::to_owned).collect())
+    }
+}
+
+// Reads a two-dimensional matrix in the NPY format.
+// Only little endian f4 and f8 values in C order are supported, which is the default of numpy.save.
+// The values are converted while reading, so the file is never held in memory as a whole.
+// The length of the file is compared with the shape before the matrix is allocated.
+fn read_npy(reader: &mut impl Read, file_length: u64) -> PathfinderResult<(Vec<f32>, usize)> {
+    let invalid = |message: &str| PathfinderError::Decode(message.to_owned());
+
+    let mut preamble = [0; 8];
+    if reader.read_exact(&mut preamble).is_err() || !preamble.starts_with(b"\x93NUMPY") {
+        return Err(invalid("The knowledge graph embeddings are no NPY file."));
+    }
+
+    // version 1 stores the header length as u16, versions 2 and 3 as u32
+    let (header_length, header_start) = if preamble[6] == 1 {
+        let mut length = [0; 2];
+        reader.read_exact(&mut length)?;
+        (u16::from_le_bytes(length) as usize, 10)
+    } else {
+        let mut length = [0; 4];
+        reader.read_exact(&mut length)?;
+        (u32::from_le_bytes(length) as usize, 12)
+    };
+    let mut header = vec![0; header_length];
+    reader.read_exact(&mut header)?;
+    let header =
+        String::from_utf8(header).map_err(|_| invalid("The NPY header is no valid UTF-8."))?;
+
+    if header.contains("'fortran_order': True") {
+        return Err(invalid("NPY files in Fortran order are not supported."));
+    }
+
+    let shape: Vec<usize> = header
+        .split_once("'shape': (")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .ok_or_else(|| invalid("The NPY header has no shape."))?
+        .0
+        .split(',')
+        .filter_map(|v| v.trim().parse::<usize>().ok())
+        .collect();
+    let [rows, dimensions] = shape[..] else {
+        return Err(invalid("The NPY matrix is not two-dimensional."));
+    };
+
+    let value_size = if header.contains("'<f4'") {
+        4
+    } else if header.contains("'<f8'") {
+        8
+    } else {
+        return Err(invalid(&format!(
+            "NPY data type is not supported: {header}"
+        )));
+    };
+
+    let value_count = rows
+        .checked_mul(dimensions)
+        .ok_or_else(|| invalid("The NPY matrix is too large."))?;
+    let data_length = file_length.saturating_sub((header_start + header_length) as u64);
+    if data_length != value_count as u64 * value_size as u64 {
+        return Err(invalid(&format!(
+            "The NPY file has {data_length} bytes of data, but its shape requires {}.",
+            value_count * value_size
+        )));
+    }
+
+    let mut vectors = Vec::with_capacity(value_count);
+    let mut buffer = vec![0; NPY_CHUNK_VALUES * value_size];
+
+    while vectors.len() < value_count {
+        let chunk_values = NPY_CHUNK_VALUES.min(value_count - vectors.len());
+        let chunk = &mut buffer[..chunk_values * value_size];
+        reader.read_exact(chunk).map_err(|_| {
+            invalid(&format!(
+                "The NPY file ends after {} of {} values.",
+                vectors.len(),
+                value_count
+            ))
+        })?;
+
+        if value_size == 4 {
+            vectors.extend(
+                chunk
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            );
+        } else {
+            vectors.extend(chunk.chunks_exact(8).map(|bytes| {
+                let mut value = [0; 8];
+                value.copy_from_slice(bytes);
+                f64::from_le_bytes(value) as f32
+            }));
+        }
+    }
+
+    Ok((vectors, dimensions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read_npy_bytes(bytes: Vec<u8>) -> PathfinderResult<(Vec<f32>, usize)> {
+        let length = bytes.len() as u64;
+        read_npy(&mut Cursor::new(bytes), length)
+    }
+
+    fn npy_file(descr: &str, shape: &str, data: &[u8]) -> Vec<u8> {
+        let header =
+            format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}\n");
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend((header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn tsv_embeddings_are_read_and_blank_lines_are_skipped() {
+        let tsv = "<http://www.wikidata.org/entity/Q1>\t1\t0\n\nQ2\t0\t1\n";
+        let (names, vectors, dimensions) = read_tsv(Cursor::new(tsv)).unwrap();
+
+        assert_eq!(names.len(), 2);
+        assert_eq!(vectors, vec![1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(dimensions, 2);
+        assert_eq!(normalize_entity_name(&names[0]), "Q1");
+    }
+
+    #[test]
+    fn malformed_tsv_lines_are_reported() {
+        assert!(read_tsv(Cursor::new("Q1\t1\t0\nQ2\t1\n")).is_err());
+        assert!(read_tsv(Cursor::new("Q1\t1\tx\n")).is_err());
+    }
+
+    #[test]
+    fn npy_matrices_of_f4_and_f8_values_are_read() {
+        let data: Vec<u8> = [1.0_f32, 0.0, 0.5, 2.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let (vectors, dimensions) = read_npy_bytes(npy_file("<f4", "(2, 2)", &data)).unwrap();
+        assert_eq!(vectors, vec![1.0, 0.0, 0.5, 2.0]);
+        assert_eq!(dimensions, 2);
+
+        let data: Vec<u8> = [1.0_f64, 2.0, 3.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let (vectors, dimensions) = read_npy_bytes(npy_file("<f8", "(1, 3)", &data)).unwrap();
+        assert_eq!(vectors, vec![1.0, 2.0, 3.0]);
+        assert_eq!(dimensions, 3);
+    }
+
+    #[test]
+    fn invalid_npy_files_are_rejected() {
+        let data = [0; 12];
+        assert!(read_npy_bytes(npy_file("<f4", "(2, 2)", &data)).is_err());
+        assert!(read_npy_bytes(npy_file("<f4", "(4,)", &data)).is_err());
+        assert!(read_npy_bytes(npy_file("<i4", "(1, 3)", &data)).is_err());
+        assert!(read_npy_bytes(b"not an npy file".to_vec()).is_err());
+    }
+}
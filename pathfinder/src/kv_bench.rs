@@ -0,0 +1,99 @@
+use std::time::Instant;
+
+use crate::kv_store::{self, IVec, KvBackend, KvResult};
+
+// The seed of the shuffle of the sampled keys, so repeated benchmarks look up the same keys
+const SAMPLE_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// The backends compared by the benchmark
+const BACKENDS: [KvBackend; 2] = [KvBackend::Sled, KvBackend::Sqlite];
+
+/// Measures the latency of random lookups in each store for every backend the store exists in,
+/// e.g., after converting the sled stores with store convert, and prints the mean, median, and 99th percentile.
+/// The same keys are looked up in every backend; they are sampled from the default tree, which holds
+/// the labels, descriptions, and adjacency lists the search reads.
+/// # Arguments
+/// * `store_paths` - Pairs of store name and path as configured in the config.toml
+/// * `lookup_count` - The number of lookups per store and backend
+/// # Returns
+/// * An error if the keys cannot be sampled or looked up
+pub fn bench_lookups(store_paths: &[(&str, String)], lookup_count: usize) -> KvResult<()> {
+    println!(
+        "{:<18} {:<8} {:>8} {:>10} {:>10} {:>10}",
+        "store", "backend", "lookups", "mean µs", "p50 µs", "p99 µs"
+    );
+
+    for (name, path) in store_paths {
+        let backends: Vec<KvBackend> = BACKENDS
+            .into_iter()
+            .filter(|backend| kv_store::exists(path, *backend))
+            .collect();
+
+        let mut keys: Option<Vec<IVec>> = None;
+        for backend in backends {
+            let store = match kv_store::open(path, backend) {
+                Ok(store) => store,
+                Err(error) => {
+                    println!("{:<18} {:<8} {}", name, backend.as_str(), error);
+                    continue;
+                }
+            };
+
+            // the keys are sampled from the first backend, so all backends look up the same keys
+            let keys = match keys {
+                Some(ref keys) => keys,
+                None => keys.insert(sample_keys(&store, lookup_count)?),
+            };
+            if keys.is_empty() {
+                println!("{:<18} {:<8} empty", name, backend.as_str());
+                continue;
+            }
+
+            let mut latencies: Vec<f64> = vec![];
+            for key in keys.iter() {
+                let start = Instant::now();
+                store.get(key)?;
+                latencies.push(start.elapsed().as_secs_f64() * 1_000_000.0);
+            }
+            latencies.sort_by(|a, b| a.total_cmp(b));
+
+            println!(
+                "{:<18} {:<8} {:>8} {:>10.2} {:>10.2} {:>10.2}",
+                name,
+                backend.as_str(),
+                latencies.len(),
+                latencies.iter().sum::<f64>() / latencies.len() as f64,
+                percentile(&latencies, 0.5),
+                percentile(&latencies, 0.99)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// Samples keys of the default tree by shuffling all keys deterministically.
+// Keys are repeated if the tree has fewer keys than lookups.
+fn sample_keys(store: &kv_store::Db, lookup_count: usize) -> KvResult<Vec<IVec>> {
+    let mut keys = store.iter().keys().collect::<KvResult<Vec<IVec>>>()?;
+    if keys.is_empty() {
+        return Ok(keys);
+    }
+
+    // Fisher-Yates shuffle with a xorshift generator
+    let mut state = SAMPLE_SEED;
+    for i in (1..keys.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        keys.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+
+    Ok(keys.iter().cycle().take(lookup_count).cloned().collect())
+}
+
+// Returns the value at a quantile of sorted values.
+fn percentile(sorted_values: &[f64], quantile: f64) -> f64 {
+    let index = ((sorted_values.len() - 1) as f64 * quantile).round() as usize;
+    sorted_values[index]
+}
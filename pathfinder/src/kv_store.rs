@@ -0,0 +1,472 @@
+use std::fmt;
+use std::ops::Deref;
+
+#[cfg(feature = "sqlite")]
+use crate::sqlite_store::{self, SqliteDb, SqliteTree};
+
+use sled::transaction::ConflictableTransactionError;
+pub use sled::IVec;
+use sled::Transactional;
+
+// The number of entries copied at once when converting a store to another backend
+const CONVERSION_BATCH_SIZE: usize = 10_000;
+
+/// The embedded key value database the stores are kept in.
+/// sled is always available, SQLite only if the pathfinder is built with the sqlite feature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KvBackend {
+    Sled,
+    Sqlite,
+}
+
+impl KvBackend {
+    /// Parses a backend as specified in the config.toml.
+    pub fn from_config(backend: &str) -> Self {
+        match backend {
+            "sled" => KvBackend::Sled,
+            "sqlite" => KvBackend::Sqlite,
+            _ => panic!("Specified key value backend is not supported."),
+        }
+    }
+
+    /// Returns the name of the backend as used in the config.toml.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KvBackend::Sled => "sled",
+            KvBackend::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// An error of a key value backend.
+#[derive(Debug)]
+pub struct KvError(pub(crate) String);
+
+impl fmt::Display for KvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<sled::Error> for KvError {
+    fn from(error: sled::Error) -> Self {
+        KvError(error.to_string())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for KvError {
+    fn from(error: rusqlite::Error) -> Self {
+        KvError(error.to_string())
+    }
+}
+
+pub type KvResult<T> = Result<T, KvError>;
+
+/// A set of insertions and removals that is applied to a tree at once.
+#[derive(Default)]
+pub struct Batch {
+    // pairs of key and value, None for removals
+    pub(crate) operations: Vec<(IVec, Option<IVec>)>,
+}
+
+impl Batch {
+    /// Inserts a key value pair when the batch is applied.
+    pub fn insert<K: Into<IVec>, V: Into<IVec>>(&mut self, key: K, value: V) {
+        self.operations.push((key.into(), Some(value.into())));
+    }
+
+    /// Removes a key when the batch is applied.
+    pub fn remove<K: Into<IVec>>(&mut self, key: K) {
+        self.operations.push((key.into(), None));
+    }
+}
+
+/// An iterator over key value pairs ordered by their keys.
+pub struct Iter(Box<dyn Iterator<Item = KvResult<(IVec, IVec)>>>);
+
+impl Iter {
+    pub(crate) fn new(iter: impl Iterator<Item = KvResult<(IVec, IVec)>> + 'static) -> Self {
+        Self(Box::new(iter))
+    }
+
+    /// Iterates over the keys only.
+    pub fn keys(self) -> impl Iterator<Item = KvResult<IVec>> {
+        self.map(|entry| entry.map(|(key, _)| key))
+    }
+
+    /// Iterates over the values only.
+    pub fn values(self) -> impl Iterator<Item = KvResult<IVec>> {
+        self.map(|entry| entry.map(|(_, value)| value))
+    }
+}
+
+impl Iterator for Iter {
+    type Item = KvResult<(IVec, IVec)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+#[derive(Clone)]
+enum TreeBackend {
+    Sled(sled::Tree),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteTree),
+}
+
+/// An ordered key value map of a store; keys are compared byte by byte in all backends.
+#[derive(Clone)]
+pub struct Tree(TreeBackend);
+
+impl Tree {
+    /// Reads the value of a key.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> KvResult<Option<IVec>> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.get(key)?),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.get(key.as_ref()),
+        }
+    }
+
+    /// Writes the value of a key and returns the previous value.
+    pub fn insert<K: AsRef<[u8]>, V: Into<IVec>>(
+        &self,
+        key: K,
+        value: V,
+    ) -> KvResult<Option<IVec>> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.insert(key, value)?),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.insert(key.as_ref(), &value.into()),
+        }
+    }
+
+    /// Removes a key and returns its value.
+    pub fn remove<K: AsRef<[u8]>>(&self, key: K) -> KvResult<Option<IVec>> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.remove(key)?),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.remove(key.as_ref()),
+        }
+    }
+
+    /// Checks whether a key is stored.
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> KvResult<bool> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// Iterates over all entries ordered by their keys.
+    pub fn iter(&self) -> Iter {
+        self.scan_prefix([])
+    }
+
+    /// Iterates over the entries whose keys start with a prefix ordered by their keys.
+    pub fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Iter {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Iter::new(tree.scan_prefix(prefix).map(|entry| Ok(entry?))),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.scan_prefix(prefix.as_ref()),
+        }
+    }
+
+    /// Applies all insertions and removals of a batch atomically.
+    pub fn apply_batch(&self, batch: Batch) -> KvResult<()> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.apply_batch(sled_batch(batch))?),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.apply_batch(batch),
+        }
+    }
+
+    /// Returns the number of entries, which iterates over all entries in sled.
+    pub fn len(&self) -> KvResult<usize> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.len()),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.len(),
+        }
+    }
+
+    /// Checks whether the tree has no entries.
+    pub fn is_empty(&self) -> KvResult<bool> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.is_empty()),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.is_empty(),
+        }
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) -> KvResult<()> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => Ok(tree.clear()?),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.clear(),
+        }
+    }
+
+    /// Writes all pending changes to disk.
+    pub fn flush(&self) -> KvResult<()> {
+        match &self.0 {
+            TreeBackend::Sled(tree) => tree.flush().map(|_| ()).map_err(KvError::from),
+            // SQLite writes each change in a transaction
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(_) => Ok(()),
+        }
+    }
+
+    /// Returns the name of the tree.
+    pub fn name(&self) -> IVec {
+        match &self.0 {
+            TreeBackend::Sled(tree) => tree.name(),
+            #[cfg(feature = "sqlite")]
+            TreeBackend::Sqlite(tree) => tree.name(),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum DbBackend {
+    Sled(sled::Db),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteDb),
+}
+
+/// A store consisting of a default tree, which the store dereferences to, and any number of named trees.
+#[derive(Clone)]
+pub struct Db {
+    backend: DbBackend,
+    default_tree: Tree,
+}
+
+impl Db {
+    /// Opens a named tree, which is created if it does not exist.
+    pub fn open_tree<N: AsRef<[u8]>>(&self, name: N) -> KvResult<Tree> {
+        let backend = match &self.backend {
+            DbBackend::Sled(db) => TreeBackend::Sled(db.open_tree(name)?),
+            #[cfg(feature = "sqlite")]
+            DbBackend::Sqlite(db) => TreeBackend::Sqlite(db.open_tree(name.as_ref())?),
+        };
+        Ok(Tree(backend))
+    }
+
+    /// Returns the names of all trees including the default tree.
+    pub fn tree_names(&self) -> KvResult<Vec<IVec>> {
+        match &self.backend {
+            DbBackend::Sled(db) => Ok(db.tree_names()),
+            #[cfg(feature = "sqlite")]
+            DbBackend::Sqlite(db) => db.tree_names(),
+        }
+    }
+
+    /// Returns the size of the store on disk in bytes.
+    pub fn size_on_disk(&self) -> KvResult<u64> {
+        match &self.backend {
+            DbBackend::Sled(db) => Ok(db.size_on_disk()?),
+            #[cfg(feature = "sqlite")]
+            DbBackend::Sqlite(db) => db.size_on_disk(),
+        }
+    }
+
+    /// Applies the batches of several distinct trees of this store in a single transaction,
+    /// so either all or none of them are applied.
+    pub fn apply_batches(&self, batches: Vec<(&Tree, Batch)>) -> KvResult<()> {
+        match &self.backend {
+            DbBackend::Sled(_) => {
+                let mut trees = vec![];
+                let mut sled_batches = vec![];
+                for (tree, batch) in batches {
+                    match &tree.0 {
+                        TreeBackend::Sled(tree) => trees.push(tree),
+                        #[cfg(feature = "sqlite")]
+                        TreeBackend::Sqlite(_) => {
+                            return Err(KvError(
+                                "Trees of different backends cannot be written together."
+                                    .to_owned(),
+                            ))
+                        }
+                    }
+                    sled_batches.push(sled_batch(batch));
+                }
+
+                trees[..]
+                    .transaction(|transactional_trees| {
+                        for (tree, batch) in transactional_trees.iter().zip(&sled_batches) {
+                            tree.apply_batch(batch)?;
+                        }
+                        Ok::<(), ConflictableTransactionError>(())
+                    })
+                    .map_err(|error| KvError(format!("Transaction failed: {error:?}")))
+            }
+            #[cfg(feature = "sqlite")]
+            DbBackend::Sqlite(db) => {
+                let mut sqlite_batches = vec![];
+                for (tree, batch) in batches {
+                    let TreeBackend::Sqlite(tree) = &tree.0 else {
+                        return Err(KvError(
+                            "Trees of different backends cannot be written together.".to_owned(),
+                        ));
+                    };
+                    sqlite_batches.push((tree, batch));
+                }
+                db.apply_batches(sqlite_batches)
+            }
+        }
+    }
+}
+
+// Converts a batch into a sled batch.
+fn sled_batch(batch: Batch) -> sled::Batch {
+    let mut sled_batch = sled::Batch::default();
+    for (key, value) in batch.operations {
+        match value {
+            Some(value) => sled_batch.insert(key, value),
+            None => sled_batch.remove(key),
+        }
+    }
+    sled_batch
+}
+
+impl Deref for Db {
+    type Target = Tree;
+
+    fn deref(&self) -> &Tree {
+        &self.default_tree
+    }
+}
+
+/// Opens a store, which is created if it does not exist.
+/// # Arguments
+/// * `path` - The directory of a sled store; SQLite stores are kept in the file at this path with the suffix .sqlite3
+/// * `backend` - The key value backend
+/// # Returns
+/// * The store or an error if it cannot be opened or the backend is not part of this build
+pub fn open(path: &str, backend: KvBackend) -> KvResult<Db> {
+    let backend = match backend {
+        KvBackend::Sled => DbBackend::Sled(sled::open(path)?),
+        #[cfg(feature = "sqlite")]
+        KvBackend::Sqlite => DbBackend::Sqlite(SqliteDb::open(&sqlite_store::file_path(path))?),
+        #[cfg(not(feature = "sqlite"))]
+        KvBackend::Sqlite => {
+            return Err(KvError(
+                "The sqlite backend requires building the pathfinder with --features sqlite."
+                    .to_owned(),
+            ))
+        }
+    };
+
+    let default_tree = Tree(match &backend {
+        DbBackend::Sled(db) => TreeBackend::Sled((**db).clone()),
+        #[cfg(feature = "sqlite")]
+        DbBackend::Sqlite(db) => TreeBackend::Sqlite(db.default_tree()?),
+    });
+
+    Ok(Db {
+        backend,
+        default_tree,
+    })
+}
+
+/// Opens a sled store that is deleted when it is dropped, e.g., for tests.
+#[cfg(test)]
+pub fn open_temporary() -> Db {
+    let db = sled::Config::new().temporary(true).open().unwrap();
+    Db {
+        default_tree: Tree(TreeBackend::Sled((*db).clone())),
+        backend: DbBackend::Sled(db),
+    }
+}
+
+/// Checks whether a store of a backend exists at a path.
+pub fn exists(path: &str, backend: KvBackend) -> bool {
+    match backend {
+        KvBackend::Sled => std::path::Path::new(path).join("conf").exists(),
+        KvBackend::Sqlite => std::path::Path::new(&format!("{path}.sqlite3")).exists(),
+    }
+}
+
+/// Copies all trees of a store to a new store of another backend at the same path.
+/// # Arguments
+/// * `path` - The path of the store as configured in the config.toml
+/// * `from` - The backend of the existing store
+/// * `to` - The backend of the new store, which must not contain any entries
+/// # Returns
+/// * The number of copied entries or an error if a store cannot be opened or the new store is not empty
+pub fn convert(path: &str, from: KvBackend, to: KvBackend) -> KvResult<usize> {
+    let source = open(path, from)?;
+    let target = open(path, to)?;
+
+    let target_trees = target.tree_names()?;
+    for tree_name in &target_trees {
+        if !target.open_tree(tree_name)?.is_empty()? {
+            return Err(KvError(format!(
+                "The {} store at {} is not empty.",
+                to.as_str(),
+                path
+            )));
+        }
+    }
+
+    let mut entry_count = 0;
+
+    for tree_name in source.tree_names()? {
+        let source_tree = source.open_tree(&tree_name)?;
+        let target_tree = target.open_tree(&tree_name)?;
+
+        let mut batch = Batch::default();
+        for entry in source_tree.iter() {
+            let (key, value) = entry?;
+            batch.insert(key, value);
+            entry_count += 1;
+
+            if batch.operations.len() == CONVERSION_BATCH_SIZE {
+                target_tree.apply_batch(std::mem::take(&mut batch))?;
+            }
+        }
+        target_tree.apply_batch(batch)?;
+        target_tree.flush()?;
+    }
+
+    Ok(entry_count)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sled_stores_are_converted_to_sqlite() {
+        let dir = std::env::temp_dir().join(format!("{}_kv_store_convert", std::process::id()));
+        let path = dir.join("store").to_str().unwrap().to_owned();
+
+        {
+            let db = open(&path, KvBackend::Sled).unwrap();
+            db.insert("Q1", "default value").unwrap();
+            let tree = db.open_tree("entries").unwrap();
+            for i in 0..CONVERSION_BATCH_SIZE + 1 {
+                tree.insert(format!("k{i:06}"), "value").unwrap();
+            }
+            db.flush().unwrap();
+        }
+
+        let result = convert(&path, KvBackend::Sled, KvBackend::Sqlite);
+        let db = open(&path, KvBackend::Sqlite).unwrap();
+        let converted = (
+            db.get("Q1").unwrap(),
+            db.open_tree("entries").unwrap().len().unwrap(),
+            db.tree_names().unwrap(),
+        );
+        // a second conversion is refused, as the new store is not empty anymore
+        let repeated = convert(&path, KvBackend::Sled, KvBackend::Sqlite);
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result.unwrap(), CONVERSION_BATCH_SIZE + 2);
+        assert_eq!(converted.0, Some(IVec::from("default value")));
+        assert_eq!(converted.1, CONVERSION_BATCH_SIZE + 1);
+        assert!(converted.2.contains(&IVec::from("entries")));
+        assert!(repeated.is_err());
+    }
+}
@@ -0,0 +1,288 @@
+// Literal hubs are virtual nodes standing for normalised literal values (dates, coordinates, external IDs).
+// They connect all entities sharing such a value, e.g. Q1 -P569-> 1952-03-11 <-P569- Q2.
+// A literal hub is identified by a string of this form: lit:kind:value
+
+const LITERAL_HUB_PREFIX: &str = "lit:";
+
+/// The precision at which point in time values are compared, ordered from the coarsest to the finest precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DatePrecision {
+    Year,
+    Month,
+    Day,
+}
+
+impl DatePrecision {
+    /// Parses a precision from its configuration value (year, month, or day).
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "year" => DatePrecision::Year,
+            "month" => DatePrecision::Month,
+            "day" => DatePrecision::Day,
+            _ => panic!("Specified literal date precision is not supported."),
+        }
+    }
+}
+
+/// The parameters controlling how literal values are turned into literal hubs.
+#[derive(Clone, Debug)]
+pub struct LiteralHubConfig {
+    /// The precision at which dates are compared
+    pub date_precision: DatePrecision,
+    /// The number of decimal places coordinates are rounded to
+    pub coordinate_precision: usize,
+}
+
+impl LiteralHubConfig {
+    /// Creates the ID of the literal hub representing a literal value.
+    /// # Arguments
+    /// * `prop` - The property linking the entity to the value
+    /// * `datatype` - The Wikibase datatype of the property (Time, GlobeCoordinate, or ExternalId)
+    /// * `value` - The raw value as returned by the Wikidata API
+    /// # Returns
+    /// * The literal hub ID or None if the value cannot be normalised
+    pub fn normalize(&self, prop: &str, datatype: &str, value: &str) -> Option<String> {
+        let normalized_value = match datatype {
+            "Time" => format!("time:{}", self.normalize_time(value)?),
+            "GlobeCoordinate" => format!("coord:{}", self.normalize_coordinate(value)?),
+            // identifiers are only comparable within the scope of their property
+            "ExternalId" if !value.is_empty() => format!("{prop}:{value}"),
+            _ => return None,
+        };
+
+        Some(format!("{LITERAL_HUB_PREFIX}{normalized_value}"))
+    }
+
+    // Truncates an xsd:dateTime value like +1952-03-11T00:00:00Z to the configured precision.
+    // Wikidata marks the components beyond the precision of a value with zeros, e.g., +1952-00-00T00:00:00Z for a year,
+    // so such values are truncated to their own precision if it is coarser than the configured one.
+    fn normalize_time(&self, value: &str) -> Option<String> {
+        let (sign, rest) = match value.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", value.trim_start_matches('+')),
+        };
+
+        let date = rest.split('T').next()?;
+        let mut parts = date.splitn(3, '-');
+        let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+
+        let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        if !is_number(year) || !is_number(month) || !is_number(day) {
+            return None;
+        }
+
+        let value_precision = if month == "00" {
+            DatePrecision::Year
+        } else if day == "00" {
+            DatePrecision::Month
+        } else {
+            DatePrecision::Day
+        };
+
+        Some(match self.date_precision.min(value_precision) {
+            DatePrecision::Year => format!("{sign}{year}"),
+            DatePrecision::Month => format!("{sign}{year}-{month}"),
+            DatePrecision::Day => format!("{sign}{year}-{month}-{day}"),
+        })
+    }
+
+    // Rounds a WKT point like Point(13.4 52.52) to the configured number of decimal places.
+    // Coordinates on other globes than Earth are prefixed with the IRI of the globe and are skipped.
+    fn normalize_coordinate(&self, value: &str) -> Option<String> {
+        let inner = value.strip_prefix("Point(")?.strip_suffix(')')?;
+        let mut parts = inner.split_whitespace();
+        let longitude: f64 = parts.next()?.parse().ok()?;
+        let latitude: f64 = parts.next()?.parse().ok()?;
+
+        // avoid distinct hubs for -0.00 and 0.00
+        let round = |v: f64| {
+            let factor = 10f64.powi(self.coordinate_precision as i32);
+            let rounded = (v * factor).round() / factor;
+            if rounded == 0.0 {
+                0.0
+            } else {
+                rounded
+            }
+        };
+
+        Some(format!(
+            "{:.precision$},{:.precision$}",
+            round(latitude),
+            round(longitude),
+            precision = self.coordinate_precision
+        ))
+    }
+}
+
+/// Checks whether an entity ID refers to a literal hub.
+pub fn is_literal_hub(entity: &str) -> bool {
+    entity.starts_with(LITERAL_HUB_PREFIX)
+}
+
+// Splits a literal hub ID into its kind and its value.
+fn split_literal_hub(entity: &str) -> (&str, &str) {
+    entity
+        .trim_start_matches(LITERAL_HUB_PREFIX)
+        .split_once(':')
+        .unwrap_or(("", entity))
+}
+
+/// Returns the label of a literal hub, i.e., the normalised value.
+pub fn label(entity: &str) -> String {
+    split_literal_hub(entity).1.to_owned()
+}
+
+/// Returns a description of a literal hub naming the kind of value.
+pub fn description(entity: &str) -> String {
+    match split_literal_hub(entity).0 {
+        "time" => "point in time".to_owned(),
+        "coord" => "geographic coordinates".to_owned(),
+        prop => format!("identifier in property {prop}"),
+    }
+}
+
+/// Serializes a literal hub as a typed Turtle literal.
+pub fn turtle_literal(entity: &str) -> String {
+    let (kind, value) = split_literal_hub(entity);
+    let escaped_value = value.replace('"', "\\\"");
+
+    match kind {
+        "time" => {
+            // the datatype depends on the precision the date was truncated to
+            let datatype = match value.trim_start_matches('-').matches('-').count() {
+                0 => "xsd:gYear",
+                1 => "xsd:gYearMonth",
+                _ => "xsd:date",
+            };
+            format!("\"{escaped_value}\"^^{datatype}")
+        }
+        "coord" => {
+            let (latitude, longitude) = value.split_once(',').unwrap_or((value, ""));
+            format!("\"Point({longitude} {latitude})\"^^geo:wktLiteral")
+        }
+        _ => format!("\"{escaped_value}\""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(date_precision: DatePrecision) -> LiteralHubConfig {
+        LiteralHubConfig {
+            date_precision,
+            coordinate_precision: 2,
+        }
+    }
+
+    #[test]
+    fn dates_are_truncated_to_the_configured_precision() {
+        let value = "+1952-03-11T00:00:00Z";
+
+        assert_eq!(
+            config(DatePrecision::Day).normalize("P569", "Time", value),
+            Some("lit:time:1952-03-11".to_owned())
+        );
+        assert_eq!(
+            config(DatePrecision::Month).normalize("P569", "Time", value),
+            Some("lit:time:1952-03".to_owned())
+        );
+        assert_eq!(
+            config(DatePrecision::Year).normalize("P569", "Time", value),
+            Some("lit:time:1952".to_owned())
+        );
+    }
+
+    #[test]
+    fn dates_are_truncated_to_their_own_precision() {
+        let day = config(DatePrecision::Day);
+
+        assert_eq!(
+            day.normalize("P569", "Time", "+1952-00-00T00:00:00Z"),
+            Some("lit:time:1952".to_owned())
+        );
+        assert_eq!(
+            day.normalize("P569", "Time", "+1952-05-00T00:00:00Z"),
+            Some("lit:time:1952-05".to_owned())
+        );
+        assert_eq!(
+            day.normalize("P569", "Time", "-0044-03-15T00:00:00Z"),
+            Some("lit:time:-0044-03-15".to_owned())
+        );
+    }
+
+    #[test]
+    fn year_precision_dates_share_a_hub_with_full_dates() {
+        let year = config(DatePrecision::Year);
+
+        assert_eq!(
+            year.normalize("P569", "Time", "+1952-00-00T00:00:00Z"),
+            year.normalize("P569", "Time", "+1952-03-11T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn malformed_dates_are_skipped() {
+        let day = config(DatePrecision::Day);
+
+        assert_eq!(day.normalize("P569", "Time", "+1952-03T00:00:00Z"), None);
+        assert_eq!(day.normalize("P569", "Time", "+19x2-03-11T00:00:00Z"), None);
+        assert_eq!(day.normalize("P569", "Time", ""), None);
+    }
+
+    #[test]
+    fn coordinates_are_rounded_and_negative_zero_is_merged() {
+        let hub = config(DatePrecision::Day);
+
+        assert_eq!(
+            hub.normalize("P625", "GlobeCoordinate", "Point(13.4049 52.5200)"),
+            Some("lit:coord:52.52,13.40".to_owned())
+        );
+        assert_eq!(
+            hub.normalize("P625", "GlobeCoordinate", "Point(-0.001 0.001)"),
+            hub.normalize("P625", "GlobeCoordinate", "Point(0.001 -0.001)")
+        );
+        assert_eq!(
+            hub.normalize(
+                "P625",
+                "GlobeCoordinate",
+                "<http://www.wikidata.org/entity/Q405> Point(1 2)"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn external_ids_are_scoped_by_property() {
+        let hub = config(DatePrecision::Day);
+
+        assert_eq!(
+            hub.normalize("P214", "ExternalId", "113230702"),
+            Some("lit:P214:113230702".to_owned())
+        );
+        assert_eq!(hub.normalize("P214", "ExternalId", ""), None);
+        assert_eq!(hub.normalize("P1082", "Quantity", "42"), None);
+    }
+
+    #[test]
+    fn literal_hubs_are_serialized_with_their_datatype() {
+        assert_eq!(turtle_literal("lit:time:1952"), "\"1952\"^^xsd:gYear");
+        assert_eq!(
+            turtle_literal("lit:time:1952-05"),
+            "\"1952-05\"^^xsd:gYearMonth"
+        );
+        assert_eq!(
+            turtle_literal("lit:time:-0044-03-15"),
+            "\"-0044-03-15\"^^xsd:date"
+        );
+        assert_eq!(
+            turtle_literal("lit:coord:52.52,13.40"),
+            "\"Point(13.40 52.52)\"^^geo:wktLiteral"
+        );
+        assert_eq!(label("lit:P214:113230702"), "113230702");
+        assert_eq!(
+            description("lit:P214:113230702"),
+            "identifier in property P214"
+        );
+    }
+}
@@ -1,28 +1,106 @@
-use env_logger;
-use log::info;
-use pathfinder::Pathfinder;
+use log::{info, warn};
+use pathfinder::{FailureReason, Pathfinder};
 use simplers_optimization::Optimizer;
 use statrs::statistics::Statistics;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
+use std::time::Duration;
 use toml::Table;
 
 #[path = "./pathfinder.rs"]
 mod pathfinder;
 
+#[path = "./error.rs"]
+mod error;
+use crate::error::PathfinderError;
+
 #[path = "./costs_calculator.rs"]
 mod costs_calculator;
+use crate::costs_calculator::Heuristic;
+#[path = "./prop_frequencies.rs"]
+mod prop_frequencies;
+use crate::prop_frequencies::{PropFrequencies, PropFrequencyAggregation};
+#[path = "./kg_embeddings.rs"]
+mod kg_embeddings;
+use crate::kg_embeddings::KgEmbeddings;
 
 #[path = "./store_connector.rs"]
 mod store_connector;
-use crate::store_connector::StoreConnector;
+use crate::store_connector::{StoreConfig, StoreConnector, STORE_NAMES};
 
 #[path = "./api_connector.rs"]
 mod api_connector;
 use crate::api_connector::ApiConnector;
 
+#[path = "./graph_store.rs"]
+mod graph_store;
+use crate::graph_store::{CacheMisses, GraphStore};
+
+#[path = "./memory_store.rs"]
+mod memory_store;
+
+#[path = "./fixture_store.rs"]
+mod fixture_store;
+use crate::fixture_store::FixtureStore;
+
+#[path = "./embedding_provider.rs"]
+mod embedding_provider;
+use crate::embedding_provider::{EmbeddingProvider, WembedProvider};
+#[path = "./word_vectors.rs"]
+mod word_vectors;
+use crate::word_vectors::WordVectorProvider;
+#[path = "./embedding_store.rs"]
+mod embedding_store;
+#[path = "./entity_id.rs"]
+mod entity_id;
+
+#[path = "./literal_hub.rs"]
+mod literal_hub;
+use crate::literal_hub::{DatePrecision, LiteralHubConfig};
+
+#[path = "./bounded_tree.rs"]
+mod bounded_tree;
+use crate::bounded_tree::{EvictionConfig, EvictionPolicy};
+
+#[path = "./store_schema.rs"]
+mod store_schema;
+
+#[path = "./adjacency_encoding.rs"]
+mod adjacency_encoding;
+
+#[path = "./csr_snapshot.rs"]
+mod csr_snapshot;
+use crate::csr_snapshot::CsrStore;
+
+#[path = "./rdf_store.rs"]
+mod rdf_store;
+use crate::rdf_store::{RdfConfig, RdfStore};
+
+#[path = "./importer.rs"]
+mod importer;
+
+#[path = "./json_importer.rs"]
+mod json_importer;
+#[path = "./kv_store.rs"]
+mod kv_store;
+#[path = "./refresh.rs"]
+mod refresh;
+#[path = "./store_export.rs"]
+mod store_export;
+#[path = "./store_inspector.rs"]
+mod store_inspector;
+#[path = "./store_pruning.rs"]
+mod store_pruning;
+use crate::kv_store::KvBackend;
+#[path = "./kv_bench.rs"]
+mod kv_bench;
+#[cfg(feature = "sqlite")]
+#[path = "./sqlite_store.rs"]
+mod sqlite_store;
+
 fn main() {
     // load configuration
     let config_data: std::string::String =
@@ -30,14 +108,23 @@ fn main() {
     let config: toml::map::Map<std::string::String, toml::Value> =
         config_data.parse::<Table>().unwrap();
 
+    // labels and descriptions are looked up along the language fallback chain, which must not be empty
+    if config["languages"].as_array().unwrap().is_empty() {
+        panic!("At least one language must be specified in languages.");
+    }
+
     // read command line arguments
     let args: Vec<String> = env::args().collect();
 
     // parse the mode argument
     let mode = args.get(1).cloned().unwrap_or("playground".to_string());
 
-    // parse the logger level argument
-    let logger_level = args.get(2).cloned().unwrap_or("info".to_string());
+    // parse the optional logger level argument, which always comes last
+    // the arguments in between are passed to the mode, e.g., the dump path of the import mode
+    let (mode_args, logger_level) = match args.get(2..).unwrap_or_default() {
+        [rest @ .., level] if level == "info" || level == "debug" => (rest, level.clone()),
+        rest => (rest, "info".to_string()),
+    };
 
     // initialize logger based on specified logger level
     match logger_level.as_str() {
@@ -54,21 +141,201 @@ fn main() {
     let api_connector: ApiConnector = api_connector::ApiConnector::new(
         String::from(config["wembed_api"].as_str().unwrap()),
         String::from(config["wikidata_api"].as_str().unwrap()),
-    );
+        Duration::from_secs(config["api_timeout_seconds"].as_integer().unwrap() as u64),
+    )
+    .unwrap_or_else(|error| panic!("HTTP client could not be created: {error}"));
+
+    // the store modes maintain the sled stores instead of searching paths
+    if [
+        "import",
+        "import-json",
+        "migrate",
+        "snapshot",
+        "refresh",
+        "store",
+    ]
+    .contains(&mode.as_str())
+    {
+        // converting and benchmarking the key value backends works on the store paths, not on opened stores
+        let store_paths: Vec<(&str, String)> = STORE_NAMES
+            .iter()
+            .map(|name| {
+                let path = config[&format!("{name}_path")].as_str().unwrap().to_owned();
+                (*name, path)
+            })
+            .collect();
+        let kv_backend = KvBackend::from_config(config["kv_backend"].as_str().unwrap());
+
+        match (mode.as_str(), mode_args.first().map(String::as_str)) {
+            ("store", Some("convert")) => {
+                let target_backend =
+                    KvBackend::from_config(mode_args.get(1).expect("No target backend specified."));
+                for (name, path) in &store_paths {
+                    match kv_store::convert(path, kv_backend, target_backend) {
+                        Ok(entry_count) => info!(
+                            "Converted {} entries of {} from {} to {}.",
+                            entry_count,
+                            name,
+                            kv_backend.as_str(),
+                            target_backend.as_str()
+                        ),
+                        Err(error) => panic!("{name} could not be converted: {error}"),
+                    }
+                }
+                info!(
+                    "Set kv_backend = \"{}\" in the config.toml to use the converted stores.",
+                    target_backend.as_str()
+                );
+                return;
+            }
+            ("store", Some("bench")) => {
+                let lookup_count = mode_args.get(1).map_or(10_000, |n| {
+                    n.parse().expect("Number of lookups is not a number.")
+                });
+                kv_bench::bench_lookups(&store_paths, lookup_count)
+                    .unwrap_or_else(|error| panic!("Lookups could not be measured: {error}"));
+                return;
+            }
+            _ => {}
+        }
 
-    // create StoreConnector instance
-    let store_connector: StoreConnector = store_connector::StoreConnector::new(
-        &api_connector,
-        String::from(config["label_mapping_path"].as_str().unwrap()),
-        String::from(config["desc_mapping_path"].as_str().unwrap()),
-        String::from(config["distance_mapping_path"].as_str().unwrap()),
-        String::from(config["adjacency_list_path"].as_str().unwrap()),
-    );
+        // inspecting and exporting the stores does not write to them
+        let read_only = mode == "store"
+            && matches!(
+                mode_args.first().map(String::as_str),
+                Some("stats" | "show" | "export")
+            );
+        let store_connector = create_store_connector(&config, &api_connector, read_only);
+
+        match mode.as_str() {
+            "import" => {
+                let dump_path = mode_args.first().expect("No dump path specified.");
+                let entity_filter = mode_args.get(1).map(|p| read_entity_filter(p));
+                importer::import_truthy_dump(&store_connector, dump_path, entity_filter)
+                    .unwrap_or_else(|error| panic!("Import failed: {error}"));
+            }
+            "import-json" => {
+                let dump_path = mode_args.first().expect("No dump path specified.");
+                let offset = mode_args
+                    .get(1)
+                    .map_or(0, |o| o.parse().expect("Offset is not a number."));
+                let entity_filter = mode_args.get(2).map(|p| read_entity_filter(p));
+                json_importer::import_json_dump(&store_connector, dump_path, entity_filter, offset)
+                    .unwrap_or_else(|error| panic!("Import failed: {error}"));
+            }
+            "refresh" => {
+                if config["offline"].as_bool().unwrap() {
+                    panic!("The refresh mode requires the Wikidata API, so offline must be false.");
+                }
+
+                // either the maximum age in days of adjacency lists or a file with entities to refresh
+                let argument = mode_args
+                    .first()
+                    .expect("No maximum age or entity file specified.");
+                let entities = match argument.parse::<f64>() {
+                    Ok(max_age) => {
+                        store_connector
+                        .stale_entities((max_age * refresh::SECONDS_PER_DAY) as u64)
+                        .unwrap_or_else(|error| panic!("Stale entities could not be read: {error}"))
+                    }
+                    Err(_) => read_entity_filter(argument).into_iter().collect(),
+                };
+                refresh::refresh_entities(&store_connector, entities);
+            }
+            "store" => match mode_args.first().map(String::as_str) {
+                Some("stats") => {
+                    let top_n = mode_args
+                        .get(1)
+                        .map_or(10, |n| n.parse().expect("Number of hubs is not a number."));
+                    store_inspector::print_stats(&store_connector, top_n)
+                        .unwrap_or_else(|error| panic!("Stats could not be read: {error}"));
+                }
+                Some("show") => {
+                    let entity = mode_args.get(1).expect("No entity specified.");
+                    store_inspector::show_entity(
+                        &store_connector,
+                        &entity_id::normalize_entity_id(entity),
+                    )
+                    .unwrap_or_else(|error| panic!("Entity could not be read: {error}"));
+                }
+                Some("export") => {
+                    let export_path = mode_args.get(1).expect("No export directory specified.");
+                    store_export::export_stores(&store_connector, export_path)
+                        .unwrap_or_else(|error| panic!("Export failed: {error}"));
+                }
+                Some("import") => {
+                    let export_path = mode_args.get(1).expect("No export directory specified.");
+                    store_export::import_stores(&store_connector, export_path)
+                        .unwrap_or_else(|error| panic!("Import failed: {error}"));
+                }
+                Some("prune") => {
+                    let query_file_path = mode_args.get(1).expect("No query file specified.");
+                    let hops = mode_args
+                        .get(2)
+                        .expect("No number of hops specified.")
+                        .parse()
+                        .expect("Number of hops is not a number.");
+                    store_pruning::prune_stores(
+                        &store_connector,
+                        query_file_path,
+                        hops,
+                        config["incoming_edges"].as_bool().unwrap(),
+                    )
+                    .unwrap_or_else(|error| panic!("Pruning failed: {error}"));
+                }
+                _ => panic!(
+                    "Specified store command is not supported, use stats, show, export, import, prune, convert, or bench."
+                ),
+            },
+            "snapshot" => {
+                let snapshot_path = mode_args
+                    .first()
+                    .map_or(config["snapshot_path"].as_str().unwrap(), |p| p.as_str());
+                csr_snapshot::write_snapshot(&store_connector, snapshot_path)
+                    .unwrap_or_else(|error| panic!("Snapshot could not be written: {error}"));
+            }
+            _ => {
+                let migrated_count = store_connector
+                    .migrate_adjacency_lists()
+                    .unwrap_or_else(|error| panic!("Migration failed: {error}"));
+                info!(
+                    "{} adjacency lists were converted to format version {}.",
+                    migrated_count,
+                    adjacency_encoding::FORMAT_VERSION
+                );
+                let entity_count = store_connector
+                    .rebuild_incoming_list()
+                    .unwrap_or_else(|error| panic!("Incoming list could not be rebuilt: {error}"));
+                info!("Incoming list rebuilt for {} entities.", entity_count);
+            }
+        }
+        return;
+    }
+
+    // create the graph store for the configured backend
+    let graph_store: Box<dyn GraphStore> = match config["store_backend"].as_str().unwrap() {
+        "sled" => Box::new(create_store_connector(&config, &api_connector, false)),
+        "fixture" => Box::new(
+            FixtureStore::load(config["fixture_path"].as_str().unwrap())
+                .unwrap_or_else(|error| panic!("Fixture could not be loaded: {error}")),
+        ),
+        "rdf" => Box::new(create_rdf_store(&config, &api_connector)),
+        "csr" => Box::new(
+            CsrStore::load(
+                config["snapshot_path"].as_str().unwrap(),
+                create_store_connector(&config, &api_connector, false),
+            )
+            .unwrap_or_else(|error| panic!("Snapshot could not be loaded: {error}")),
+        ),
+        _ => panic!("Specified store backend is not supported."),
+    };
 
     // create Pathfinder instance
     let pathfinder = pathfinder::Pathfinder::new(
-        &store_connector,
+        graph_store.as_ref(),
         config["entity_limit"].as_integer().unwrap() as usize,
+        config["incoming_edges"].as_bool().unwrap(),
+        create_heuristic(&config),
     );
 
     // run function corresponding to specified mode
@@ -80,6 +347,149 @@ fn main() {
     }
 }
 
+fn create_embedding_provider<'a>(
+    config: &toml::map::Map<String, toml::Value>,
+    api_connector: &'a ApiConnector,
+) -> Box<dyn EmbeddingProvider + 'a> {
+    match config["embedding_provider"].as_str().unwrap() {
+        "wembed" => Box::new(WembedProvider::new(
+            api_connector,
+            String::from(config["embedding_model"].as_str().unwrap()),
+        )),
+        "word_vectors" => Box::new(
+            WordVectorProvider::load(config["word_vectors_path"].as_str().unwrap())
+                .unwrap_or_else(|error| panic!("Word vectors could not be loaded: {error}")),
+        ),
+        _ => panic!("Specified embedding provider is not supported."),
+    }
+}
+
+fn read_entity_filter(filter_path: &str) -> HashSet<String> {
+    importer::read_entity_filter(filter_path).unwrap_or_else(|error| panic!("{error}"))
+}
+
+fn create_heuristic(config: &toml::map::Map<String, toml::Value>) -> Heuristic {
+    let load_kg_embeddings = || {
+        KgEmbeddings::load(
+            config["kg_embedding_path"].as_str().unwrap(),
+            config["kg_entity_names_path"].as_str().unwrap(),
+        )
+        .unwrap_or_else(|error| panic!("Knowledge graph embeddings could not be loaded: {error}"))
+    };
+
+    match config["heuristic"].as_str().unwrap() {
+        "text" => Heuristic::Text,
+        "kg" => Heuristic::KgEmbedding(load_kg_embeddings()),
+        "blend" => {
+            let kg_weight = config["heuristic_kg_weight"].as_float().unwrap();
+            if !(0.0..=1.0).contains(&kg_weight) {
+                panic!("heuristic_kg_weight must be between 0 and 1, but is {kg_weight}.");
+            }
+            Heuristic::Blend(load_kg_embeddings(), kg_weight)
+        }
+        _ => panic!("Specified heuristic is not supported."),
+    }
+}
+
+fn create_rdf_store<'a>(
+    config: &toml::map::Map<String, toml::Value>,
+    api_connector: &'a ApiConnector,
+) -> RdfStore<'a> {
+    let strings = |key: &str| -> Vec<String> {
+        config[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_owned())
+            .collect()
+    };
+
+    RdfStore::load(
+        create_embedding_provider(config, api_connector),
+        RdfConfig {
+            rdf_path: String::from(config["rdf_path"].as_str().unwrap()),
+            label_predicates: strings("rdf_label_predicates"),
+            description_predicates: strings("rdf_description_predicates"),
+            prefixes: config["rdf_prefixes"]
+                .as_table()
+                .unwrap()
+                .iter()
+                .map(|(prefix, namespace)| {
+                    (prefix.to_owned(), namespace.as_str().unwrap().to_owned())
+                })
+                .collect(),
+            languages: strings("languages"),
+            prop_frequency_aggregation: PropFrequencyAggregation::from_config(
+                config["prop_frequency_aggregation"].as_str().unwrap(),
+            ),
+            offline: config["offline"].as_bool().unwrap(),
+        },
+    )
+}
+
+fn create_store_connector<'a>(
+    config: &toml::map::Map<String, toml::Value>,
+    api_connector: &'a ApiConnector,
+    read_only: bool,
+) -> StoreConnector<'a> {
+    // literal hubs are only created if the literal hub mode is enabled
+    let literal_hubs = if config["literal_hubs"].as_bool().unwrap() {
+        Some(LiteralHubConfig {
+            date_precision: DatePrecision::from_config(
+                config["literal_date_precision"].as_str().unwrap(),
+            ),
+            coordinate_precision: config["literal_coordinate_precision"].as_integer().unwrap()
+                as usize,
+        })
+    } else {
+        None
+    };
+
+    // the stores of semantic distances and embedding vectors are bounded if a maximum number of entries is set
+    // stores opened read-only are not bounded, as evicting entries and recording accesses writes to them
+    let eviction_policy = EvictionPolicy::from_config(config["eviction_policy"].as_str().unwrap());
+    let eviction = |key: &str| {
+        EvictionConfig::new(config[key].as_integer().unwrap() as usize, eviction_policy)
+            .filter(|_| !read_only)
+    };
+
+    // create StoreConnector instance
+    StoreConnector::new(
+        api_connector,
+        create_embedding_provider(config, api_connector),
+        StoreConfig {
+            label_mapping_path: String::from(config["label_mapping_path"].as_str().unwrap()),
+            desc_mapping_path: String::from(config["desc_mapping_path"].as_str().unwrap()),
+            distance_mapping_path: String::from(config["distance_mapping_path"].as_str().unwrap()),
+            embedding_store_path: String::from(config["embedding_store_path"].as_str().unwrap()),
+            adjacency_list_path: String::from(config["adjacency_list_path"].as_str().unwrap()),
+            literal_list_path: String::from(config["literal_list_path"].as_str().unwrap()),
+            redirect_mapping_path: String::from(config["redirect_mapping_path"].as_str().unwrap()),
+            statement_list_path: String::from(config["statement_list_path"].as_str().unwrap()),
+            sitelink_mapping_path: String::from(config["sitelink_mapping_path"].as_str().unwrap()),
+            kv_backend: KvBackend::from_config(config["kv_backend"].as_str().unwrap()),
+            prop_frequencies: PropFrequencies::load(
+                config["prop_frequency_path"].as_str().unwrap(),
+                PropFrequencyAggregation::from_config(
+                    config["prop_frequency_aggregation"].as_str().unwrap(),
+                ),
+            ),
+            distance_eviction: eviction("max_distance_entries"),
+            embedding_eviction: eviction("max_embedding_entries"),
+            literal_hubs,
+            languages: config["languages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap().to_owned())
+                .collect(),
+            offline: config["offline"].as_bool().unwrap(),
+            read_only,
+        },
+    )
+    .unwrap_or_else(|error| panic!("Stores could not be opened: {error}"))
+}
+
 fn optimizer(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfinder) {
     // collect sample queries for the optimization from the Wikidata query files
     let query_file_paths = config["query_file_paths"]
@@ -123,12 +533,19 @@ fn optimizer(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
 
     // create or clear file for results
     let mut file = File::create(config["optimizer_results_path"].as_str().unwrap()).unwrap();
-    writeln!(file, "alpha,beta,gamma,objective_value").unwrap();
+    writeln!(
+        file,
+        "heuristic,alpha,beta,gamma,objective_value,failed_queries"
+    )
+    .unwrap();
+
+    let entity_limit = config["entity_limit"].as_integer().unwrap() as usize;
 
     // the function to be optimized
     let f = |hyperparameter_config: &[f64]| {
         // to collect the scores of the individual pathfinder runs
         let mut scores: Vec<f64> = Vec::new();
+        let mut failed_queries = 0;
 
         // iterate sample queries
         for query in &some_queries {
@@ -140,23 +557,34 @@ fn optimizer(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
             );
 
             // find a path given the provided configuration
-            let (found_path_forwards, found_path_backwards, visited_entity_count, _) = pathfinder
-                .find_path(
-                    source_entity,
-                    target_entity,
-                    &(
-                        hyperparameter_config[0],
-                        hyperparameter_config[1],
-                        hyperparameter_config[2],
-                    ),
-                    false,
-                );
+            let result = pathfinder.find_path(
+                source_entity,
+                target_entity,
+                &(
+                    hyperparameter_config[0],
+                    hyperparameter_config[1],
+                    hyperparameter_config[2],
+                ),
+                false,
+            );
+
+            // queries failing due to an error are penalized like a search that exhausted the entity limit without
+            // finding a path instead of aborting the optimization, so configurations causing errors are not favored
+            let result = match result {
+                Ok(result) => result,
+                Err(error) => {
+                    warn!("TREC {} query could not be processed: {}", trec_id, error);
+                    failed_queries += 1;
+                    scores.push(entity_limit as f64 * 2.0);
+                    continue;
+                }
+            };
 
             // the score of a pathfinder run (lower is better) equals the visited entities
-            let mut score = visited_entity_count as f64;
+            let mut score = result.visited_entity_count as f64;
 
             // double the score to penalize no found path
-            if found_path_forwards.is_empty() && found_path_backwards.is_empty() {
+            if !result.is_success() {
                 score *= 2.0;
             }
 
@@ -164,7 +592,6 @@ fn optimizer(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
         }
 
         let mut file = OpenOptions::new()
-            .write(true)
             .append(true)
             .open(config["optimizer_results_path"].as_str().unwrap())
             .unwrap();
@@ -174,11 +601,13 @@ fn optimizer(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
 
         writeln!(
             file,
-            "{},{},{},{}",
+            "{},{},{},{},{},{}",
+            pathfinder.heuristic().as_str(),
             hyperparameter_config[0],
             hyperparameter_config[1],
             hyperparameter_config[2],
-            objective_value
+            objective_value,
+            failed_queries
         )
         .unwrap();
 
@@ -259,6 +688,11 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
         let mut total_successes = 0;
         let mut collected_counts: Vec<usize> = vec![];
         let mut collected_path_lengths: Vec<usize> = vec![];
+        let mut rewritten_queries = 0;
+        let mut failure_counts: HashMap<FailureReason, usize> = HashMap::new();
+        let mut cache_misses = CacheMisses::default();
+        let mut error_counts: HashMap<&str, usize> = HashMap::new();
+        let mut failed_queries: Vec<(&str, &str, &str, PathfinderError)> = vec![];
 
         // run pathfinder for test queries
         for query in &some_queries {
@@ -270,8 +704,48 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
             );
 
             // execute the pathfinding
-            let (found_path_forwards, found_path_backwards, visited_entity_count, _) =
-                pathfinder.find_path(source_entity, target_entity, hyperparameter_config, false);
+            let result = match pathfinder.find_path(
+                source_entity,
+                target_entity,
+                hyperparameter_config,
+                false,
+            ) {
+                Ok(result) => result,
+                // the query is counted as unsuccessful and the benchmark continues with the next query
+                Err(error) => {
+                    warn!("TREC {} query could not be processed: {}", trec_id, error);
+                    *error_counts.entry(error.kind()).or_insert(0) += 1;
+                    failed_queries.push((trec_id, source_entity, target_entity, error));
+                    collected_counts.push(0);
+                    collected_path_lengths.push(0);
+                    continue;
+                }
+            };
+            let (found_path_forwards, found_path_backwards) =
+                (&result.path_forwards, &result.path_backwards);
+
+            // stale IDs of merged entities are replaced, but the affected queries are counted
+            if result.endpoints_rewritten {
+                info!(
+                    "TREC {} query was rewritten to {} and {}",
+                    trec_id, result.source_entity, result.target_entity
+                );
+                rewritten_queries += 1;
+            }
+
+            cache_misses += result.cache_misses;
+
+            // collect why the search failed
+            if let Some(reason) = result.failure_reason {
+                info!(
+                    "TREC {} query failed ({}) with frontier sizes {}/{}",
+                    trec_id,
+                    reason.as_str(),
+                    result.frontier_sizes.0,
+                    result.frontier_sizes.1
+                );
+                *failure_counts.entry(reason).or_insert(0) += 1;
+            }
 
             // update results
             if found_path_forwards.is_empty() {
@@ -279,9 +753,9 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
                 collected_path_lengths.push(0);
             } else {
                 total_successes += 1;
-                collected_counts.push(visited_entity_count);
+                collected_counts.push(result.visited_entity_count);
 
-                let path_length = if found_path_backwards.len() > 0 {
+                let path_length = if !found_path_backwards.is_empty() {
                     found_path_forwards.len() + found_path_backwards.len() - 2
                 } else {
                     found_path_forwards.len() - 1
@@ -297,7 +771,7 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
         let visited_entities_cleaned: Vec<f32> = collected_counts
             .iter()
             .map(|n| *n as f32)
-            .filter(|n| n.to_owned() > 0.0)
+            .filter(|n| *n > 0.0)
             .collect();
 
         let average_visited_entities: f32 =
@@ -307,7 +781,7 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
         let path_lengths_cleaned: Vec<f32> = collected_path_lengths
             .iter()
             .map(|n| *n as f32)
-            .filter(|n| n.to_owned() > 0.0)
+            .filter(|n| *n > 0.0)
             .collect();
 
         let average_path_lengths: f32 =
@@ -325,6 +799,26 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
 
         info!("Average path lengths: {}", average_path_lengths);
 
+        info!("Queries with rewritten entities: {}", rewritten_queries);
+
+        info!("Cache misses: {}", cache_misses.total());
+
+        for reason in FailureReason::ALL {
+            info!(
+                "Failures due to {}: {}",
+                reason.as_str(),
+                failure_counts.get(&reason).unwrap_or(&0)
+            );
+        }
+
+        for kind in PathfinderError::KINDS {
+            info!(
+                "Errors of kind {}: {}",
+                kind,
+                error_counts.get(kind).unwrap_or(&0)
+            );
+        }
+
         // store results
         let result_path = format!(
             "{}_{}_{}_{}.toml",
@@ -337,47 +831,121 @@ fn benchmark(config: &toml::map::Map<String, toml::Value>, pathfinder: &Pathfind
         let mut file = File::create(result_path).unwrap();
 
         let toml_content = format!(
-            "number_of_queries = {}
+            "heuristic = \"{}\"
+number_of_queries = {}
 success_rate = {}
 average_visited_entities = {}
 path_lengths_entities = {}
+rewritten_queries = {}
 ",
+            pathfinder.heuristic().as_str(),
             some_queries.len(),
             success_rate,
             average_visited_entities,
             average_path_lengths,
+            rewritten_queries,
         );
 
         file.write_all(toml_content.as_bytes()).unwrap();
+
+        // aggregate failed queries by the reason of the failure
+        writeln!(file, "\n[failure_reasons]").unwrap();
+        for reason in FailureReason::ALL {
+            writeln!(
+                file,
+                "{} = {}",
+                reason.as_str(),
+                failure_counts.get(&reason).unwrap_or(&0)
+            )
+            .unwrap();
+        }
+
+        // misses only occur in offline mode, where missing data is not fetched
+        writeln!(
+            file,
+            "\n[cache_misses]
+total = {}
+adjacent_entities = {}
+labels = {}
+descriptions = {}
+distances = {}
+prop_frequencies = {}
+redirects = {}",
+            cache_misses.total(),
+            cache_misses.adjacent_entities,
+            cache_misses.labels,
+            cache_misses.descriptions,
+            cache_misses.distances,
+            cache_misses.prop_frequencies,
+            cache_misses.redirects
+        )
+        .unwrap();
+
+        // queries aborted by an error are aggregated by the kind of the error and listed individually
+        writeln!(file, "\n[errors]").unwrap();
+        for kind in PathfinderError::KINDS {
+            writeln!(file, "{} = {}", kind, error_counts.get(kind).unwrap_or(&0)).unwrap();
+        }
+
+        for (trec_id, source_entity, target_entity, error) in &failed_queries {
+            writeln!(
+                file,
+                "\n[[failed_queries]]
+trec_id = {}
+source_entity = {}
+target_entity = {}
+kind = \"{}\"
+error = {}",
+                toml::Value::from(*trec_id),
+                toml::Value::from(*source_entity),
+                toml::Value::from(*target_entity),
+                error.kind(),
+                toml::Value::from(error.to_string())
+            )
+            .unwrap();
+        }
     }
 }
 
 fn playground(pathfinder: &Pathfinder) {
     let hyperparameter_config = &(0.23031994047619048, 0.02808779761904762, 0.58984375);
 
+    // failed searches are logged such that the remaining queries are still run
+    let find_path = |entity_a: &str, entity_b: &str| {
+        pathfinder
+            .find_path(entity_a, entity_b, hyperparameter_config, false)
+            .inspect_err(|error| {
+                warn!(
+                    "Query {} and {} could not be processed: {}",
+                    entity_a, entity_b, error
+                )
+            })
+            .ok()
+    };
+
     let mut entity_a = "Q42";
     let mut entity_b = "Q5";
 
-    pathfinder.find_path(entity_a, entity_b, hyperparameter_config, false);
-    pathfinder.find_path(entity_b, entity_a, hyperparameter_config, false);
+    find_path(entity_a, entity_b);
+    find_path(entity_b, entity_a);
 
     entity_a = "Q3936";
     entity_b = "Q21198";
 
-    pathfinder.find_path(entity_a, entity_b, hyperparameter_config, false);
-    pathfinder.find_path(entity_b, entity_a, hyperparameter_config, false);
+    find_path(entity_a, entity_b);
+    find_path(entity_b, entity_a);
 
     entity_a = "Q42";
     entity_b = "Q389908";
 
-    pathfinder.find_path(entity_a, entity_b, hyperparameter_config, false);
-    pathfinder.find_path(entity_b, entity_a, hyperparameter_config, false);
+    find_path(entity_a, entity_b);
+    find_path(entity_b, entity_a);
 
     // actual test query from derived query set + printing path serialized turtle
     entity_a = "Q376657";
     entity_b = "Q1951366";
 
-    let (_, _, _, turtle_string) =
-        pathfinder.find_path(entity_a, entity_b, hyperparameter_config, false);
-    info!("{turtle_string}");
+    if let Some(result) = find_path(entity_a, entity_b) {
+        info!("{}", result.turtle);
+    }
 }
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::error::PathfinderResult;
+use crate::graph_store::GraphStore;
+
+/// A graph store keeping a small, complete graph in memory.
+/// It never calls external services which makes it suitable for testing the search on hand-made graphs.
+#[derive(Default)]
+pub struct MemoryStore {
+    adjacency_list: HashMap<String, Vec<(String, String)>>,
+    incoming_list: HashMap<String, Vec<(String, String)>>,
+    labels: HashMap<String, String>,
+    descriptions: HashMap<String, String>,
+    distances: HashMap<(String, String), f64>,
+    prop_frequencies: HashMap<String, f64>,
+    redirects: HashMap<String, String>,
+    /// The distance of entity pairs without an explicitly set distance
+    default_distance: f64,
+}
+
+impl MemoryStore {
+    /// Creates an empty MemoryStore.
+    /// # Arguments
+    /// * `default_distance` - The semantic distance of entity pairs without an explicitly set distance
+    /// # Returns
+    /// * The instance
+    pub fn new(default_distance: f64) -> Self {
+        Self {
+            default_distance,
+            ..Default::default()
+        }
+    }
+
+    /// Adds an edge from the subject to the object entity.
+    pub fn add_edge(&mut self, subject: &str, prop: &str, object: &str) {
+        self.adjacency_list
+            .entry(subject.to_owned())
+            .or_default()
+            .push((prop.to_owned(), object.to_owned()));
+        self.incoming_list
+            .entry(object.to_owned())
+            .or_default()
+            .push((prop.to_owned(), subject.to_owned()));
+    }
+
+    /// Sets the label of an entity or property.
+    pub fn set_label(&mut self, entity: &str, label: &str) {
+        self.labels.insert(entity.to_owned(), label.to_owned());
+    }
+
+    /// Sets the description of an entity or property.
+    pub fn set_description(&mut self, entity: &str, description: &str) {
+        self.descriptions
+            .insert(entity.to_owned(), description.to_owned());
+    }
+
+    /// Sets the semantic distance between two entities in both directions.
+    pub fn set_distance(&mut self, entity_a: &str, entity_b: &str, distance: f64) {
+        self.distances
+            .insert((entity_a.to_owned(), entity_b.to_owned()), distance);
+        self.distances
+            .insert((entity_b.to_owned(), entity_a.to_owned()), distance);
+    }
+
+    /// Redirects an entity to another entity, e.g., because it was merged into it.
+    pub fn set_redirect(&mut self, entity: &str, target: &str) {
+        self.redirects.insert(entity.to_owned(), target.to_owned());
+    }
+
+    /// Sets the normalised frequency of a property.
+    pub fn set_prop_frequency(&mut self, prop: &str, frequency: f64) {
+        self.prop_frequencies.insert(prop.to_owned(), frequency);
+    }
+}
+
+impl GraphStore for MemoryStore {
+    fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        Ok(self.adjacency_list.get(entity).cloned().unwrap_or_default())
+    }
+
+    fn get_incoming_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        Ok(self.incoming_list.get(entity).cloned().unwrap_or_default())
+    }
+
+    fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        Ok((
+            self.labels.get(entity).cloned().unwrap_or_default(),
+            String::new(),
+        ))
+    }
+
+    fn get_description_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        Ok((
+            self.descriptions.get(entity).cloned().unwrap_or_default(),
+            String::new(),
+        ))
+    }
+
+    fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64> {
+        if entity_a == entity_b {
+            return Ok(0.0);
+        }
+
+        Ok(*self
+            .distances
+            .get(&(entity_a.to_owned(), entity_b.to_owned()))
+            .unwrap_or(&self.default_distance))
+    }
+
+    // follows chains of redirects like the Wikidata API, but guards against cycles
+    fn resolve_entity(&self, entity: &str) -> PathfinderResult<String> {
+        let mut resolved_entity = entity.to_owned();
+
+        for _ in 0..self.redirects.len() {
+            match self.redirects.get(&resolved_entity) {
+                Some(target) => resolved_entity = target.to_owned(),
+                None => break,
+            }
+        }
+
+        Ok(resolved_entity)
+    }
+
+    // like the Wikidata API, properties with unknown frequency are ignored
+    fn get_prop_frequency(&self, props: &[String]) -> f64 {
+        let frequencies: Vec<f64> = props
+            .iter()
+            .filter_map(|prop| self.prop_frequencies.get(prop).copied())
+            .collect();
+
+        if frequencies.is_empty() {
+            0.0
+        } else {
+            frequencies.iter().sum::<f64>() / frequencies.len() as f64
+        }
+    }
+}
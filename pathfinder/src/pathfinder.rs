@@ -3,45 +3,182 @@ use log::{debug, info};
 use priority_queue::DoublePriorityQueue; // allows to extract minimum in contrast to PriorityQueue
 use std::collections::{HashMap, HashSet};
 
-use crate::costs_calculator::calculate_costs;
-use crate::store_connector::StoreConnector;
+use crate::costs_calculator::{calculate_costs, Heuristic};
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::graph_store::{CacheMisses, GraphStore};
 
 #[derive(PartialEq)]
 enum Direction {
     FromSourceToTarget,
     FromTargetToSource,
 }
+
+/// The reasons why no path could be found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FailureReason {
+    /// Adjacent entities could not be retrieved from the Wikidata API during the search
+    ApiFailure,
+    /// The source entity has no outgoing edges
+    SourceWithoutEdges,
+    /// The target entity has no outgoing edges
+    TargetWithoutEdges,
+    /// The entity limit was reached before the searches met
+    EntityLimitReached,
+    /// All entities reachable from the source and the target entity were visited without the searches meeting
+    DisconnectedComponents,
+}
+
+impl FailureReason {
+    /// All failure reasons in the order in which they are reported.
+    pub const ALL: [FailureReason; 5] = [
+        FailureReason::ApiFailure,
+        FailureReason::SourceWithoutEdges,
+        FailureReason::TargetWithoutEdges,
+        FailureReason::EntityLimitReached,
+        FailureReason::DisconnectedComponents,
+    ];
+
+    /// Returns a snake case name of the reason for result files.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::ApiFailure => "api_failure",
+            FailureReason::SourceWithoutEdges => "source_without_edges",
+            FailureReason::TargetWithoutEdges => "target_without_edges",
+            FailureReason::EntityLimitReached => "entity_limit_reached",
+            FailureReason::DisconnectedComponents => "disconnected_components",
+        }
+    }
+}
+
+/// The outcome of a single pathfinder run.
+pub struct PathResult {
+    /// The path starting at the source entity
+    pub path_forwards: Vec<String>,
+    /// The path starting at the target entity
+    pub path_backwards: Vec<String>,
+    /// The number of entities visited during the search
+    pub visited_entity_count: usize,
+    /// The path serialized as Turtle
+    pub turtle: String,
+    /// The source entity the search started at after following redirects
+    pub source_entity: String,
+    /// The target entity the search started at after following redirects
+    pub target_entity: String,
+    /// Whether the source or the target entity was rewritten, e.g., because it was merged into another entity
+    pub endpoints_rewritten: bool,
+    /// Why no path was found or None if a path was found
+    pub failure_reason: Option<FailureReason>,
+    /// The number of entities in the queues from source and from target when the search terminated
+    pub frontier_sizes: (usize, usize),
+    /// The lookups that could not be answered from local data during the search
+    pub cache_misses: CacheMisses,
+}
+
+impl PathResult {
+    /// Checks whether a path was found.
+    pub fn is_success(&self) -> bool {
+        !(self.path_forwards.is_empty() && self.path_backwards.is_empty())
+    }
+}
+
+// Incoming edges are traversed via inverse properties, e.g., ^P31 leads from a class to its instances
+const INVERSE_PROP_PREFIX: &str = "^";
+
 pub struct Pathfinder<'a> {
-    store_connector: &'a StoreConnector<'a>,
+    graph_store: &'a dyn GraphStore,
     entity_limit: usize,
+    follow_incoming_edges: bool,
+    heuristic: Heuristic,
 }
 
 impl<'a> Pathfinder<'a> {
-    pub fn new(store_connector: &'a StoreConnector<'a>, entity_limit: usize) -> Self {
+    pub fn new(
+        graph_store: &'a dyn GraphStore,
+        entity_limit: usize,
+        follow_incoming_edges: bool,
+        heuristic: Heuristic,
+    ) -> Self {
         // create Pathfinder instance with struct fields
         Self {
-            store_connector,
+            graph_store,
             entity_limit,
+            follow_incoming_edges,
+            heuristic,
+        }
+    }
+
+    /// Returns the heuristic used for the distance between the last entity of a path and the target entity.
+    pub fn heuristic(&self) -> &Heuristic {
+        &self.heuristic
+    }
+
+    // Returns the outgoing edges of an entity and, if enabled, its locally known incoming edges via inverse properties.
+    fn get_neighbours(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        let mut neighbours = self.graph_store.get_adjacent_entities(entity)?;
+
+        if self.follow_incoming_edges {
+            neighbours.extend(
+                self.graph_store
+                    .get_incoming_entities(entity)?
+                    .into_iter()
+                    .map(|(prop, some_entity)| {
+                        (format!("{INVERSE_PROP_PREFIX}{prop}"), some_entity)
+                    }),
+            );
         }
+
+        Ok(neighbours)
     }
 
+    /// Searches a path between two entities with a bidirectional A* search.
+    /// # Arguments
+    /// * `source_entity` - The entity the path starts at
+    /// * `target_entity` - The entity the path ends at
+    /// * `hyperparameter_config` - The weights alpha, beta, and gamma of the costs
+    /// * `consider_prop_frequency` - Whether paths via frequent properties are more costly
+    /// # Returns
+    /// * The found path or the reason why no path was found
+    /// * An error if data required by the search could not be read, e.g., from a corrupted store
     pub fn find_path(
         &self,
         source_entity: &str,
         target_entity: &str,
         hyperparameter_config: &(f64, f64, f64),
         consider_prop_frequency: bool,
-    ) -> (Vec<String>, Vec<String>, usize, String) {
-        // initialize mappings and adjacency list based on source and target entity
-        self.store_connector.get_adjacent_entities(source_entity);
-        self.store_connector.get_adjacent_entities(target_entity);
+    ) -> PathfinderResult<PathResult> {
+        let initial_cache_misses = self.graph_store.cache_misses();
+
+        // used to detect failed requests for adjacent entities, including those made while resolving the endpoints
+        let initial_adjacency_failure_count = self.graph_store.adjacency_failure_count();
+
+        // accept entity references like wd:P50 and replace stale IDs of merged entities
+        // resolving also initializes mappings and adjacency list based on source and target entity
+        let resolved_source_entity = self.graph_store.resolve_entity(source_entity)?;
+        let resolved_target_entity = self.graph_store.resolve_entity(target_entity)?;
+
+        // a different notation of the same entity, e.g., wd:Q42 for Q42, is not a rewrite
+        let endpoints_rewritten = resolved_source_entity
+            != self.graph_store.normalize_entity(source_entity)
+            || resolved_target_entity != self.graph_store.normalize_entity(target_entity);
+
+        if endpoints_rewritten {
+            info!(
+                "Query entities {} and {} were rewritten to {} and {}.",
+                source_entity, target_entity, resolved_source_entity, resolved_target_entity
+            );
+        }
+
+        let (source_entity, target_entity) = (
+            resolved_source_entity.as_str(),
+            resolved_target_entity.as_str(),
+        );
 
         info!(
             "***** Search path between {} ({}) and {} ({}) using alpha={}, beta={}, gamma={}",
             source_entity,
-            self.store_connector.get_label(source_entity),
+            self.graph_store.get_label(source_entity)?,
             target_entity,
-            self.store_connector.get_label(target_entity),
+            self.graph_store.get_label(target_entity)?,
             hyperparameter_config.0,
             hyperparameter_config.1,
             hyperparameter_config.2
@@ -50,6 +187,9 @@ impl<'a> Pathfinder<'a> {
         // initialize set of visited entities that is used to check if entity limit is reached
         let mut visited_entities: HashSet<String> = HashSet::new();
 
+        // whether the source or target entity was expanded without adjacent entities, recorded for the failure reason
+        let (mut source_without_edges, mut target_without_edges) = (false, false);
+
         // initialize found path
         let mut found_path_forwards: Vec<String> = vec![];
         let mut found_path_backwards: Vec<String> = vec![];
@@ -133,8 +273,7 @@ impl<'a> Pathfinder<'a> {
 
             debug!(
                 "*** Processing path {} ({})",
-                self.path_to_string(&path, &empty_vec, &props, &empty_vec)
-                    .unwrap(),
+                self.path_to_string(&path, &empty_vec, &props, &empty_vec)?,
                 if direction == Direction::FromSourceToTarget {
                     "source -> target"
                 } else {
@@ -204,10 +343,44 @@ impl<'a> Pathfinder<'a> {
                 Direction::FromTargetToSource => &mut queue_from_target,
             };
 
+            // the costs of paths starting at the target entity are calculated with respect to the source entity
+            let directional_target_entity = match direction {
+                Direction::FromSourceToTarget => target_entity,
+                Direction::FromTargetToSource => source_entity,
+            };
+
+            let neighbours = self.get_neighbours(&current_entity)?;
+            if neighbours.is_empty() {
+                match direction {
+                    Direction::FromSourceToTarget if current_entity == source_entity => {
+                        source_without_edges = true
+                    }
+                    Direction::FromTargetToSource if current_entity == target_entity => {
+                        target_without_edges = true
+                    }
+                    _ => {}
+                }
+            }
+
+            // the semantic distances of the adjacent entities to the directional target entity are needed for their costs,
+            // either in the average distance of the path once they are expanded or in the heuristic
+            let (alpha, _, gamma) = hyperparameter_config;
+            let entities: Vec<String> = neighbours.iter().map(|(_, e)| e.to_owned()).collect();
+            let entities = if *alpha != 0.0 {
+                entities
+            } else if *gamma != 0.0 {
+                self.heuristic
+                    .text_distance_entities(&entities, directional_target_entity)
+            } else {
+                vec![]
+            };
+            if !entities.is_empty() {
+                self.graph_store
+                    .prefetch_embeddings(&entities, directional_target_entity)?;
+            }
+
             // insert adjacent entities into priority queue if they not have been visited before
-            for (prop, adjacent_entity) in
-                self.store_connector.get_adjacent_entities(&current_entity)
-            {
+            for (prop, adjacent_entity) in neighbours {
                 // cycle detection
                 if path.contains(&adjacent_entity) {
                     continue;
@@ -220,27 +393,30 @@ impl<'a> Pathfinder<'a> {
                 let mut candidate_props = props.clone();
                 candidate_props.push(prop.clone());
 
-                // if prop frequency is considered retrieve it
-                let average_prop_frequency = if consider_prop_frequency {
-                    self.store_connector
-                    .get_average_prop_frequency(&candidate_props)
+                // if prop frequency is considered retrieve it; inverse props are as frequent as their props
+                let prop_frequency = if consider_prop_frequency {
+                    let base_props: Vec<String> = candidate_props
+                        .iter()
+                        .map(|p| split_inverse(p).0.to_owned())
+                        .collect();
+                    self.graph_store.get_prop_frequency(&base_props)
                 } else {
                     0.0
                 };
-                
+
                 // calculate costs of path
                 let tentative_costs = calculate_costs(
-                    self.store_connector,
-                    source_entity,
-                    target_entity,
+                    self.graph_store,
+                    &self.heuristic,
+                    directional_target_entity,
                     &candidate_path,
-                    average_prop_frequency,
+                    prop_frequency,
                     hyperparameter_config,
-                );
+                )?;
 
                 // update mappings with respect to path costs
                 if !costs.contains_key(&adjacent_entity)
-                    || tentative_costs < costs.get(&adjacent_entity).unwrap().to_owned()
+                    || tentative_costs < *costs.get(&adjacent_entity).unwrap()
                 {
                     came_from.insert(adjacent_entity.clone(), current_entity.to_owned());
                     prev_prop.insert(adjacent_entity.clone(), prop);
@@ -258,8 +434,31 @@ impl<'a> Pathfinder<'a> {
             );
         }
 
+        let frontier_sizes = (queue_from_source.len(), queue_from_target.len());
+        let mut failure_reason = None;
+
         if found_path_forwards.is_empty() && found_path_backwards.is_empty() {
-            info!("No path could be found. :(");
+            // the reasons are checked from the most to the least specific one
+            let reason =
+                if self.graph_store.adjacency_failure_count() > initial_adjacency_failure_count {
+                    FailureReason::ApiFailure
+                } else if source_without_edges {
+                    FailureReason::SourceWithoutEdges
+                } else if target_without_edges {
+                    FailureReason::TargetWithoutEdges
+                } else if visited_entities.len() >= self.entity_limit {
+                    FailureReason::EntityLimitReached
+                } else {
+                    FailureReason::DisconnectedComponents
+                };
+
+            info!(
+                "No path could be found ({}) with {}/{} entities left in queue_from_source/queue_from_target. :(",
+                reason.as_str(),
+                frontier_sizes.0,
+                frontier_sizes.1
+            );
+            failure_reason = Some(reason);
         } else {
             info!(
                 "A path was found: {}",
@@ -268,34 +467,37 @@ impl<'a> Pathfinder<'a> {
                     &found_path_backwards,
                     &props_forwards,
                     &props_backwards
-                )
-                .unwrap()
+                )?
             );
         }
 
         // serialize path as turtle
-        let turtle_string = self
-            .path_to_turtle(
-                &found_path_forwards,
-                &found_path_backwards,
-                &props_forwards,
-                &props_backwards,
-            )
-            .unwrap();
-
-        (
-            found_path_forwards,
-            found_path_backwards,
-            visited_entities.len(),
-            turtle_string,
-        )
+        let turtle_string = self.path_to_turtle(
+            &found_path_forwards,
+            &found_path_backwards,
+            &props_forwards,
+            &props_backwards,
+        )?;
+
+        Ok(PathResult {
+            path_forwards: found_path_forwards,
+            path_backwards: found_path_backwards,
+            visited_entity_count: visited_entities.len(),
+            turtle: turtle_string,
+            source_entity: resolved_source_entity.clone(),
+            target_entity: resolved_target_entity.clone(),
+            endpoints_rewritten,
+            failure_reason,
+            frontier_sizes,
+            cache_misses: self.graph_store.cache_misses() - initial_cache_misses,
+        })
     }
 
-    fn reconstruct_path<'c>(
+    fn reconstruct_path(
         &self,
         came_from: &HashMap<String, String>,
         prev_prop: &HashMap<String, String>,
-        current_entity: &'c str,
+        current_entity: &str,
     ) -> (Vec<String>, Vec<String>) {
         let mut current_entity = current_entity;
         let mut path = vec![current_entity.to_string()];
@@ -320,29 +522,44 @@ impl<'a> Pathfinder<'a> {
         (path, props)
     }
 
+    // Returns the label of an entity for logging.
+    // Labels in a fallback language are suffixed with their language tag.
+    fn display_label(&self, entity: &str) -> PathfinderResult<String> {
+        let (entity, _) = split_inverse(entity);
+        let (label, language) = self.graph_store.get_label_with_language(entity)?;
+
+        if language.is_empty() || language == self.graph_store.preferred_language() {
+            Ok(label)
+        } else {
+            Ok(format!("{label}@{language}"))
+        }
+    }
+
     // Returns a pretty string representation of the path.
     fn path_to_string(
         &self,
-        path_forwards: &Vec<String>,
-        path_backwards: &Vec<String>,
-        props_forwards: &Vec<String>,
-        props_backwards: &Vec<String>,
-    ) -> Result<String, String> {
+        path_forwards: &[String],
+        path_backwards: &[String],
+        props_forwards: &[String],
+        props_backwards: &[String],
+    ) -> PathfinderResult<String> {
         let mut path_string;
 
         if !path_forwards.is_empty() {
-            let stub = path_forwards.first().ok_or("")?;
-            path_string = format!("{stub} ({})", self.store_connector.get_label(stub));
+            let stub = path_forwards.first().unwrap();
+            path_string = format!("{stub} ({})", self.display_label(stub)?);
         } else {
-            let stub = path_backwards.last().ok_or("")?;
-            path_string = format!("{stub} ({})", self.store_connector.get_label(stub));
+            let stub = path_backwards
+                .last()
+                .ok_or_else(|| PathfinderError::DataConsistency("The path is empty.".to_owned()))?;
+            path_string = format!("{stub} ({})", self.display_label(stub)?);
         }
 
         for (prop, entity) in props_forwards.iter().zip(path_forwards.iter().skip(1)) {
             path_string += &format!(
                 " -{prop} ({})-> {entity} ({})",
-                self.store_connector.get_label(prop),
-                self.store_connector.get_label(entity)
+                self.display_label(prop)?,
+                self.display_label(entity)?
             )
         }
 
@@ -353,8 +570,8 @@ impl<'a> Pathfinder<'a> {
         {
             path_string += &format!(
                 " <-{prop} ({})- {entity} ({})",
-                self.store_connector.get_label(prop),
-                self.store_connector.get_label(entity)
+                self.display_label(prop)?,
+                self.display_label(entity)?
             )
         }
 
@@ -365,32 +582,23 @@ impl<'a> Pathfinder<'a> {
     // TODO use a proper rdf serialization library for this function
     fn path_to_turtle(
         &self,
-        path_forwards: &Vec<String>,
-        path_backwards: &Vec<String>,
-        props_forwards: &Vec<String>,
-        props_backwards: &Vec<String>,
-    ) -> Result<String, String> {
-        let mut path_turtle = "@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
-        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
-        @prefix schema: <http://schema.org/> .
-        @prefix wd: <http://www.wikidata.org/entity/> .
-        @prefix wdt: <http://www.wikidata.org/prop/direct/> ."
-            .to_string();
+        path_forwards: &[String],
+        path_backwards: &[String],
+        props_forwards: &[String],
+        props_backwards: &[String],
+    ) -> PathfinderResult<String> {
+        let mut path_turtle = self
+            .graph_store
+            .turtle_prefixes()
+            .iter()
+            .map(|(prefix, namespace)| format!("@prefix {prefix}: <{namespace}> ."))
+            .join("\n");
 
         // serialize forwards path
         for (subject, predicate, object) in
             izip!(path_forwards, props_forwards, path_forwards.iter().skip(1))
         {
-            let subject_label = self.store_connector.get_label(subject).replace('"', "\"");
-            let subject_description = self
-                .store_connector
-                .get_description(subject)
-                .replace('"', "\"");
-            let triple = format!(
-                "\nwd:{subject} rdfs:label \"{subject_label}\" ; schema:description \"{subject_description}\" ; wdt:{predicate} wd:{object} ."
-            );
-
-            path_turtle += &triple;
+            path_turtle += &self.edge_to_turtle(subject, predicate, object)?;
         }
 
         // serialize backwards path
@@ -399,40 +607,285 @@ impl<'a> Pathfinder<'a> {
             props_backwards,
             path_backwards.iter().skip(1)
         ) {
-            let subject_label = self
-                .store_connector
-                .get_label(subject)
-                .replace("\"", "\\\"");
-            let subject_description = self
-                .store_connector
-                .get_description(subject)
-                .replace("\"", "\\\"");
-            let triple = format!(
-                "\nwd:{subject} rdfs:label \"{subject_label}\" ; schema:description \"{subject_description}\" ; wdt:{predicate} wd:{object} ."
-            );
-
-            path_turtle += &triple;
+            path_turtle += &self.edge_to_turtle(subject, predicate, object)?;
         }
 
         // add labels and descriptions for properties
-        let unique_props: Vec<&String> = props_forwards
+        let unique_props: Vec<&str> = props_forwards
             .iter()
             .chain(props_backwards)
+            .map(|prop| split_inverse(prop).0)
             .unique()
             .collect();
 
         for prop in unique_props {
-            let prop_label = self.store_connector.get_label(prop).replace("\"", "\\\"");
-            let prop_description = self
-                .store_connector
-                .get_description(prop)
-                .replace("\"", "\\\"");
-            let prop_data: String =
-                format!("\nwd:{prop} rdfs:label \"{prop_label}\" ; schema:description \"{prop_description}\" .");
+            let prop_label = turtle_literal(self.graph_store.get_label_with_language(prop)?);
+            let prop_description =
+                turtle_literal(self.graph_store.get_description_with_language(prop)?);
+            let prop_data: String = format!(
+                "\n{} {} {prop_label} ; {} {prop_description} .",
+                self.graph_store.entity_term(prop),
+                self.graph_store.label_term(prop),
+                self.graph_store.description_term(prop)
+            );
 
             path_turtle += &prop_data;
         }
 
         Ok(path_turtle)
     }
+
+    // Serializes an edge of a path along with the label and description of its subject.
+    // Edges via inverse props are serialized in their original direction.
+    fn edge_to_turtle(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> PathfinderResult<String> {
+        let subject_label = turtle_literal(self.graph_store.get_label_with_language(subject)?);
+        let subject_description =
+            turtle_literal(self.graph_store.get_description_with_language(subject)?);
+        let subject_data = format!(
+            "\n{} {} {subject_label} ; {} {subject_description}",
+            self.graph_store.entity_term(subject),
+            self.graph_store.label_term(subject),
+            self.graph_store.description_term(subject)
+        );
+
+        Ok(match split_inverse(predicate) {
+            (prop, true) => format!(
+                "{subject_data} .\n{} {} {} .",
+                self.graph_store.entity_term(object),
+                self.graph_store.prop_term(prop),
+                self.graph_store.entity_term(subject)
+            ),
+            (prop, false) => format!(
+                "{subject_data} ; {} {} .",
+                self.graph_store.prop_term(prop),
+                self.graph_store.entity_term(object)
+            ),
+        })
+    }
+}
+
+// Splits an inverse prop like ^P31 into the prop and whether it is inverse.
+fn split_inverse(prop: &str) -> (&str, bool) {
+    match prop.strip_prefix(INVERSE_PROP_PREFIX) {
+        Some(prop) => (prop, true),
+        None => (prop, false),
+    }
+}
+
+// Serializes a label or description as a Turtle string literal with a language tag if the language is known.
+fn turtle_literal((value, language): (String, String)) -> String {
+    let escaped_value = value.replace('"', "\\\"");
+
+    if language.is_empty() {
+        format!("\"{escaped_value}\"")
+    } else {
+        format!("\"{escaped_value}\"@{language}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture_store::FixtureStore;
+    use crate::memory_store::MemoryStore;
+    use std::cell::{Cell, RefCell};
+
+    // A store whose requests for the adjacent entities of some entities fail, which records all requests.
+    // Like the StoreConnector, it requests the adjacent entities of an endpoint while resolving it.
+    struct FailingStore {
+        graph: MemoryStore,
+        failing_entities: HashSet<String>,
+        adjacency_failure_count: Cell<usize>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl FailingStore {
+        fn new(graph: MemoryStore, failing_entities: &[&str]) -> Self {
+            Self {
+                graph,
+                failing_entities: failing_entities.iter().map(|e| e.to_string()).collect(),
+                adjacency_failure_count: Cell::new(0),
+                requests: RefCell::new(vec![]),
+            }
+        }
+
+        fn request_count(&self, entity: &str) -> usize {
+            self.requests
+                .borrow()
+                .iter()
+                .filter(|e| *e == entity)
+                .count()
+        }
+    }
+
+    impl GraphStore for FailingStore {
+        fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+            self.requests.borrow_mut().push(entity.to_owned());
+            if self.failing_entities.contains(entity) {
+                self.adjacency_failure_count
+                    .set(self.adjacency_failure_count.get() + 1);
+                return Ok(vec![]);
+            }
+            self.graph.get_adjacent_entities(entity)
+        }
+
+        fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+            self.graph.get_label_with_language(entity)
+        }
+
+        fn get_description_with_language(
+            &self,
+            entity: &str,
+        ) -> PathfinderResult<(String, String)> {
+            self.graph.get_description_with_language(entity)
+        }
+
+        fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64> {
+            self.graph.get_semantic_distance(entity_a, entity_b)
+        }
+
+        fn get_prop_frequency(&self, props: &[String]) -> f64 {
+            self.graph.get_prop_frequency(props)
+        }
+
+        fn resolve_entity(&self, entity: &str) -> PathfinderResult<String> {
+            self.get_adjacent_entities(entity)?;
+            Ok(entity.to_owned())
+        }
+
+        fn adjacency_failure_count(&self) -> usize {
+            self.adjacency_failure_count.get()
+        }
+    }
+
+    fn chain_graph() -> MemoryStore {
+        let mut graph = MemoryStore::new(0.5);
+        graph.add_edge("Q1", "P1", "Q2");
+        graph.add_edge("Q3", "P1", "Q4");
+        graph
+    }
+
+    const HYPERPARAMETERS: (f64, f64, f64) = (1.0, 0.0, 1.0);
+
+    fn fixture_store() -> FixtureStore {
+        FixtureStore::load(concat!(env!("CARGO_MANIFEST_DIR"), "/../data/fixture.toml")).unwrap()
+    }
+
+    // Joins both halves of a found path into the sequence of entities from source to target.
+    fn entities_on_path(result: &PathResult) -> Vec<String> {
+        let mut entities = result.path_forwards.clone();
+        for entity in result.path_backwards.iter().rev() {
+            if entities.last() != Some(entity) {
+                entities.push(entity.to_owned());
+            }
+        }
+        entities
+    }
+
+    #[test]
+    fn path_is_found() {
+        let store = fixture_store();
+        let pathfinder = Pathfinder::new(&store, 100, false, Heuristic::Text);
+
+        let result = pathfinder
+            .find_path("Q42", "Q350", &HYPERPARAMETERS, false)
+            .unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(result.failure_reason, None);
+        assert!(!result.endpoints_rewritten);
+        assert_eq!(entities_on_path(&result), ["Q42", "Q691283", "Q350"]);
+        assert!(result.turtle.contains("wd:Q691283"));
+    }
+
+    #[test]
+    fn disconnected_entities_have_no_path() {
+        let store = fixture_store();
+        let pathfinder = Pathfinder::new(&store, 100, false, Heuristic::Text);
+
+        let result = pathfinder
+            .find_path("Q42", "Q1", &HYPERPARAMETERS, false)
+            .unwrap();
+
+        assert!(!result.is_success());
+        assert_eq!(
+            result.failure_reason,
+            Some(FailureReason::DisconnectedComponents)
+        );
+        assert_eq!(result.frontier_sizes, (0, 0));
+    }
+
+    #[test]
+    fn search_stops_at_entity_limit() {
+        let store = fixture_store();
+        let pathfinder = Pathfinder::new(&store, 1, false, Heuristic::Text);
+
+        let result = pathfinder
+            .find_path("Q42", "Q350", &HYPERPARAMETERS, false)
+            .unwrap();
+
+        assert!(!result.is_success());
+        assert_eq!(
+            result.failure_reason,
+            Some(FailureReason::EntityLimitReached)
+        );
+        assert_eq!(result.visited_entity_count, 1);
+    }
+
+    #[test]
+    fn redirected_endpoint_is_rewritten() {
+        let store = fixture_store();
+        let pathfinder = Pathfinder::new(&store, 100, false, Heuristic::Text);
+
+        let result = pathfinder
+            .find_path("Q100000042", "Q145", &HYPERPARAMETERS, false)
+            .unwrap();
+
+        assert!(result.is_success());
+        assert!(result.endpoints_rewritten);
+        assert_eq!(result.source_entity, "Q42");
+        assert_eq!(entities_on_path(&result), ["Q42", "Q145"]);
+    }
+
+    #[test]
+    fn failed_requests_while_resolving_are_api_failures() {
+        let store = FailingStore::new(chain_graph(), &["Q1"]);
+        let pathfinder = Pathfinder::new(&store, 100, false, Heuristic::Text);
+
+        let result = pathfinder
+            .find_path("Q1", "Q3", &HYPERPARAMETERS, false)
+            .unwrap();
+
+        assert_eq!(result.failure_reason, Some(FailureReason::ApiFailure));
+    }
+
+    #[test]
+    fn endpoints_without_edges_are_not_requested_again() {
+        let store = FailingStore::new(chain_graph(), &[]);
+        let pathfinder = Pathfinder::new(&store, 100, false, Heuristic::Text);
+
+        let result = pathfinder
+            .find_path("Q2", "Q3", &HYPERPARAMETERS, false)
+            .unwrap();
+        assert_eq!(
+            result.failure_reason,
+            Some(FailureReason::SourceWithoutEdges)
+        );
+        // once while resolving and once while expanding
+        assert_eq!(store.request_count("Q2"), 2);
+        assert_eq!(store.request_count("Q3"), 2);
+
+        let result = pathfinder
+            .find_path("Q1", "Q4", &HYPERPARAMETERS, false)
+            .unwrap();
+        assert_eq!(
+            result.failure_reason,
+            Some(FailureReason::TargetWithoutEdges)
+        );
+    }
 }
@@ -0,0 +1,165 @@
+use itertools::Itertools;
+use log::info;
+use std::collections::HashMap;
+
+/// How the normalised frequencies of the properties on a path are combined into one value.
+#[derive(Clone, Copy)]
+pub enum PropFrequencyAggregation {
+    /// The average frequency, as calculated by the Wikidata API
+    Average,
+    /// The frequency of the most frequent property
+    Max,
+    /// The sum of all frequencies, which grows with the length of the path
+    Sum,
+    /// The average logarithmic frequency ln(count) / ln(max) of each property,
+    /// which, in contrast to the frequency, distinguishes rare properties
+    LogFrequency,
+}
+
+impl PropFrequencyAggregation {
+    /// Parses an aggregation as specified in the config.toml.
+    pub fn from_config(aggregation: &str) -> Self {
+        match aggregation {
+            "average" => PropFrequencyAggregation::Average,
+            "max" => PropFrequencyAggregation::Max,
+            "sum" => PropFrequencyAggregation::Sum,
+            "log_frequency" => PropFrequencyAggregation::LogFrequency,
+            "idf" => panic!("The prop frequency aggregation idf was renamed to log_frequency."),
+            _ => panic!("Specified prop frequency aggregation is not supported."),
+        }
+    }
+}
+
+/// The number of uses of each property, from which the normalised frequencies of paths are calculated locally.
+pub struct PropFrequencies {
+    counts: HashMap<String, f64>,
+    max_count: f64,
+    aggregation: PropFrequencyAggregation,
+}
+
+impl PropFrequencies {
+    /// Loads PropFrequencies from a CSV file with the columns property_id and counts, e.g., wd_properties.csv.
+    /// # Arguments
+    /// * `path` - The path of the CSV file
+    /// * `aggregation` - How the frequencies of several properties are combined
+    /// # Returns
+    /// * The instance
+    pub fn load(path: &str, aggregation: PropFrequencyAggregation) -> Self {
+        let mut reader = csv::Reader::from_path(path).expect("Prop frequencies could not be read.");
+
+        let counts: HashMap<String, f64> = reader
+            .records()
+            .map(|r| r.unwrap())
+            .map(|r| {
+                (
+                    r.get(0).unwrap().to_owned(),
+                    r.get(1).unwrap().parse().unwrap(),
+                )
+            })
+            .collect();
+
+        info!("Loaded frequencies of {} properties.", counts.len());
+
+        Self::from_counts(counts, aggregation)
+    }
+
+    /// Creates PropFrequencies from the number of uses of each property.
+    pub fn from_counts(
+        counts: HashMap<String, f64>,
+        aggregation: PropFrequencyAggregation,
+    ) -> Self {
+        let max_count = counts.values().cloned().fold(0.0, f64::max);
+
+        Self {
+            counts,
+            max_count,
+            aggregation,
+        }
+    }
+
+    /// Counts a use of a property.
+    pub fn add(&mut self, prop: &str) {
+        let count = self.counts.entry(prop.to_owned()).or_default();
+        *count += 1.0;
+        self.max_count = self.max_count.max(*count);
+    }
+
+    /// Returns the number of properties with known frequency.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns the aggregated normalised frequency of one or more properties.
+    /// Like the Wikidata API, each property is considered once and properties with unknown frequency are ignored.
+    pub fn get(&self, props: &[String]) -> f64 {
+        let counts: Vec<f64> = props
+            .iter()
+            .unique()
+            .filter_map(|prop| self.counts.get(prop).copied())
+            .collect();
+
+        if counts.is_empty() {
+            return 0.0;
+        }
+
+        let frequencies = counts.iter().map(|count| count / self.max_count);
+
+        match self.aggregation {
+            PropFrequencyAggregation::Average => frequencies.sum::<f64>() / counts.len() as f64,
+            PropFrequencyAggregation::Max => frequencies.fold(0.0, f64::max),
+            PropFrequencyAggregation::Sum => frequencies.sum(),
+            PropFrequencyAggregation::LogFrequency => {
+                // with a maximum count of 1, all properties are equally frequent
+                if self.max_count <= 1.0 {
+                    return 1.0;
+                }
+
+                let max_log_count = self.max_count.ln();
+                counts
+                    .iter()
+                    .map(|count| count.max(1.0).ln() / max_log_count)
+                    .sum::<f64>()
+                    / counts.len() as f64
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prop_frequencies(aggregation: PropFrequencyAggregation) -> PropFrequencies {
+        PropFrequencies::from_counts(
+            HashMap::from([
+                ("P31".to_owned(), 10_000.0),
+                ("P17".to_owned(), 100.0),
+                ("P1".to_owned(), 1.0),
+            ]),
+            aggregation,
+        )
+    }
+
+    #[test]
+    fn frequencies_are_aggregated() {
+        let props = ["P17".to_owned(), "P31".to_owned()];
+
+        let average = prop_frequencies(PropFrequencyAggregation::Average).get(&props);
+        assert!((average - 0.505).abs() < 1e-9);
+        let max = prop_frequencies(PropFrequencyAggregation::Max).get(&props);
+        assert_eq!(max, 1.0);
+        assert_eq!(
+            prop_frequencies(PropFrequencyAggregation::Sum).get(&[]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn log_frequencies_separate_rare_properties() {
+        let log_frequencies = prop_frequencies(PropFrequencyAggregation::LogFrequency);
+
+        assert_eq!(log_frequencies.get(&["P31".to_owned()]), 1.0);
+        assert!((log_frequencies.get(&["P17".to_owned()]) - 0.5).abs() < 1e-9);
+        assert_eq!(log_frequencies.get(&["P1".to_owned()]), 0.0);
+    }
+}
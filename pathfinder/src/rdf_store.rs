@@ -0,0 +1,376 @@
+use itertools::Itertools;
+use log::{debug, info, warn};
+use rio_api::model::{Literal, Subject, Term, Triple};
+use rio_api::parser::TriplesParser;
+use rio_turtle::{NTriplesParser, TurtleError, TurtleParser};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use crate::embedding_provider::EmbeddingProvider;
+use crate::embedding_store::cosine_distance;
+use crate::error::PathfinderResult;
+use crate::graph_store::{CacheMisses, GraphStore};
+use crate::importer::open_dump;
+use crate::prop_frequencies::{PropFrequencies, PropFrequencyAggregation};
+
+// The semantic distance used in offline mode, as in the StoreConnector
+const UNKNOWN_DISTANCE: f64 = 1.0;
+
+/// The location of an RDF dataset and how to interpret it.
+pub struct RdfConfig {
+    /// An N-Triples (.nt) or Turtle file, optionally compressed with gzip or bzip2
+    pub rdf_path: String,
+    /// The predicates of labels in order of preference, e.g., http://www.w3.org/2000/01/rdf-schema#label
+    pub label_predicates: Vec<String>,
+    /// The predicates of descriptions in order of preference, e.g., http://www.w3.org/2000/01/rdf-schema#comment
+    pub description_predicates: Vec<String>,
+    /// Pairs of prefix and namespace IRI in addition to the prefixes declared in a Turtle file
+    pub prefixes: Vec<(String, String)>,
+    /// The language fallback chain for labels and descriptions
+    pub languages: Vec<String>,
+    /// How the frequencies of the properties on a path are combined
+    pub prop_frequency_aggregation: PropFrequencyAggregation,
+    /// In offline mode, only local embedding providers are called
+    pub offline: bool,
+}
+
+/// A graph store holding an arbitrary RDF dataset in memory.
+/// Entities and properties are identified by their IRIs; every triple with an IRI as object is an edge.
+/// Semantic distances are calculated from the embedding vectors of labels and descriptions, which are kept in memory.
+pub struct RdfStore<'a> {
+    embedding_provider: Box<dyn EmbeddingProvider + 'a>,
+    // None marks entities that could not be embedded
+    embeddings: RefCell<HashMap<String, Option<Vec<f32>>>>,
+    adjacency_list: HashMap<String, Vec<(String, String)>>,
+    incoming_list: HashMap<String, Vec<(String, String)>>,
+    // the values of an entity per predicate index and language
+    labels: HashMap<String, HashMap<(usize, String), String>>,
+    descriptions: HashMap<String, HashMap<(usize, String), String>>,
+    prop_frequencies: PropFrequencies,
+    label_predicates: Vec<String>,
+    description_predicates: Vec<String>,
+    prefixes: Vec<(String, String)>,
+    languages: Vec<String>,
+    offline: bool,
+    cache_misses: Cell<CacheMisses>,
+}
+
+impl<'a> RdfStore<'a> {
+    /// Loads an RdfStore from an RDF file.
+    /// # Arguments
+    /// * `embedding_provider` - The EmbeddingProvider used for semantic distances
+    /// * `config` - The location of the dataset and how to interpret it
+    /// # Returns
+    /// * The instance
+    pub fn load(embedding_provider: Box<dyn EmbeddingProvider + 'a>, config: RdfConfig) -> Self {
+        // the first predicates are used for the Turtle output of labels and descriptions
+        if config.label_predicates.is_empty() || config.description_predicates.is_empty() {
+            panic!("At least one label and one description predicate must be specified for the RDF dataset.");
+        }
+
+        let mut store = Self {
+            embedding_provider,
+            embeddings: RefCell::new(HashMap::new()),
+            adjacency_list: HashMap::new(),
+            incoming_list: HashMap::new(),
+            labels: HashMap::new(),
+            descriptions: HashMap::new(),
+            prop_frequencies: PropFrequencies::from_counts(
+                HashMap::new(),
+                config.prop_frequency_aggregation,
+            ),
+            label_predicates: config.label_predicates,
+            description_predicates: config.description_predicates,
+            prefixes: config.prefixes,
+            languages: config.languages,
+            offline: config.offline,
+            cache_misses: Cell::new(CacheMisses::default()),
+        };
+
+        info!("Loading RDF dataset from {}.", config.rdf_path);
+        let reader = open_dump(&config.rdf_path)
+            .unwrap_or_else(|error| panic!("RDF dataset could not be read: {error}"));
+        let path = config
+            .rdf_path
+            .trim_end_matches(".gz")
+            .trim_end_matches(".bz2");
+
+        // the prefixes declared in the dataset are used for the Turtle output
+        if path.ends_with(".nt") {
+            let mut parser = NTriplesParser::new(reader);
+            parser
+                .parse_all(&mut |triple| store.add_triple(triple))
+                .expect("RDF dataset could not be parsed.");
+        } else {
+            let mut parser = TurtleParser::new(reader, None);
+            parser
+                .parse_all(&mut |triple| store.add_triple(triple))
+                .expect("RDF dataset could not be parsed.");
+
+            for (prefix, namespace) in parser.prefixes() {
+                if !store.prefixes.iter().any(|(p, _)| p == prefix) {
+                    store
+                        .prefixes
+                        .push((prefix.to_owned(), namespace.to_owned()));
+                }
+            }
+        }
+
+        info!(
+            "Loaded {} entities with outgoing edges and {} properties.",
+            store.adjacency_list.len(),
+            store.prop_frequencies.len()
+        );
+
+        store
+    }
+
+    // Adds a triple as edge, label, or description; triples with blank nodes are ignored.
+    fn add_triple(&mut self, triple: Triple) -> Result<(), TurtleError> {
+        let Subject::NamedNode(subject) = triple.subject else {
+            return Ok(());
+        };
+        let predicate = triple.predicate.iri;
+
+        match triple.object {
+            Term::NamedNode(object) => {
+                self.adjacency_list
+                    .entry(subject.iri.to_owned())
+                    .or_default()
+                    .push((predicate.to_owned(), object.iri.to_owned()));
+                self.incoming_list
+                    .entry(object.iri.to_owned())
+                    .or_default()
+                    .push((predicate.to_owned(), subject.iri.to_owned()));
+                self.prop_frequencies.add(predicate);
+            }
+            Term::Literal(literal) => {
+                let (value, language) = match literal {
+                    Literal::Simple { value } => (value, ""),
+                    Literal::LanguageTaggedString { value, language } => (value, language),
+                    Literal::Typed { value, .. } => (value, ""),
+                };
+
+                let key = |index| (index, language.to_lowercase());
+
+                if let Some(index) = self.label_predicates.iter().position(|p| p == predicate) {
+                    self.labels
+                        .entry(subject.iri.to_owned())
+                        .or_default()
+                        .insert(key(index), value.to_owned());
+                } else if let Some(index) = self
+                    .description_predicates
+                    .iter()
+                    .position(|p| p == predicate)
+                {
+                    self.descriptions
+                        .entry(subject.iri.to_owned())
+                        .or_default()
+                        .insert(key(index), value.to_owned());
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    // Looks up a value along the language fallback chain, then values without language.
+    // Among values of the same language, the value of the predicate configured first is preferred.
+    fn get_language_value(
+        values: &HashMap<String, HashMap<(usize, String), String>>,
+        predicate_count: usize,
+        languages: &[String],
+        entity: &str,
+    ) -> (String, String) {
+        let Some(entity_values) = values.get(entity) else {
+            return (String::new(), String::new());
+        };
+
+        languages
+            .iter()
+            .map(String::as_str)
+            .chain([""])
+            .find_map(|language| {
+                (0..predicate_count).find_map(|index| {
+                    entity_values
+                        .get(&(index, language.to_owned()))
+                        .map(|value| (value.to_owned(), language.to_owned()))
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    // Embeds the entities that were not embedded yet with a single request of the embedding provider.
+    fn embed_entities(&self, entities: &[String]) -> PathfinderResult<()> {
+        let texts: Vec<(String, String)> = entities
+            .iter()
+            .filter(|entity| !self.embeddings.borrow().contains_key(*entity))
+            .unique()
+            .map(|entity| {
+                let text = format!(
+                    "{} {}",
+                    self.get_label(entity)?,
+                    self.get_description(entity)?
+                );
+                Ok((entity.to_owned(), text))
+            })
+            .collect::<PathfinderResult<_>>()?;
+
+        if texts.is_empty() {
+            return Ok(());
+        }
+
+        let vectors = self
+            .embedding_provider
+            .embed(&texts)
+            .unwrap_or_else(|error| {
+                warn!("{} entities could not be embedded: {}", texts.len(), error);
+                vec![None; texts.len()]
+            });
+
+        let mut embeddings = self.embeddings.borrow_mut();
+        for ((entity, _), vector) in texts.into_iter().zip(vectors) {
+            embeddings.insert(entity, vector);
+        }
+
+        Ok(())
+    }
+
+    // Abbreviates an IRI with the longest matching prefix or wraps it in angle brackets.
+    fn compact_iri(&self, iri: &str) -> String {
+        self.prefixes
+            .iter()
+            .filter_map(|(prefix, namespace)| {
+                let local_name = iri.strip_prefix(namespace.as_str())?;
+                is_local_name(local_name)
+                    .then(|| (namespace.len(), format!("{prefix}:{local_name}")))
+            })
+            .max_by_key(|(namespace_length, _)| *namespace_length)
+            .map_or_else(|| format!("<{iri}>"), |(_, prefixed_name)| prefixed_name)
+    }
+}
+
+impl GraphStore for RdfStore<'_> {
+    fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        Ok(self.adjacency_list.get(entity).cloned().unwrap_or_default())
+    }
+
+    fn get_incoming_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        Ok(self.incoming_list.get(entity).cloned().unwrap_or_default())
+    }
+
+    fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        Ok(Self::get_language_value(
+            &self.labels,
+            self.label_predicates.len(),
+            &self.languages,
+            entity,
+        ))
+    }
+
+    fn get_description_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        Ok(Self::get_language_value(
+            &self.descriptions,
+            self.description_predicates.len(),
+            &self.languages,
+            entity,
+        ))
+    }
+
+    fn preferred_language(&self) -> &str {
+        self.languages.first().map_or("", String::as_str)
+    }
+
+    fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64> {
+        if self.offline && !self.embedding_provider.is_local() {
+            let mut cache_misses = self.cache_misses.get();
+            cache_misses.distances += 1;
+            self.cache_misses.set(cache_misses);
+            return Ok(UNKNOWN_DISTANCE);
+        }
+
+        self.embed_entities(&[entity_a.to_owned(), entity_b.to_owned()])?;
+
+        let embeddings = self.embeddings.borrow();
+        let distance = match (&embeddings[entity_a], &embeddings[entity_b]) {
+            (Some(vector_a), Some(vector_b)) => {
+                cosine_distance(vector_a, vector_b).unwrap_or(UNKNOWN_DISTANCE)
+            }
+            _ => UNKNOWN_DISTANCE,
+        };
+
+        debug!(
+            "Semantic distance between {} and {}: {}",
+            entity_a, entity_b, distance
+        );
+
+        Ok(distance)
+    }
+
+    // the embeddings are kept in memory, so already embedded entities are skipped anyway
+    fn prefetch_embeddings(&self, entities: &[String], target: &str) -> PathfinderResult<()> {
+        if !self.offline || self.embedding_provider.is_local() {
+            let mut entities = entities.to_vec();
+            entities.push(target.to_owned());
+            self.embed_entities(&entities)?;
+        }
+
+        Ok(())
+    }
+
+    // frequencies are counted in the dataset
+    fn get_prop_frequency(&self, props: &[String]) -> f64 {
+        self.prop_frequencies.get(props)
+    }
+
+    // query entities may be given as full IRIs, in angle brackets, or as prefixed names
+    fn normalize_entity(&self, entity: &str) -> String {
+        let entity = entity.trim();
+
+        if let Some(iri) = entity.strip_prefix('<').and_then(|e| e.strip_suffix('>')) {
+            return iri.to_owned();
+        }
+
+        if let Some((prefix, local_name)) = entity.split_once(':') {
+            if let Some((_, namespace)) = self.prefixes.iter().find(|(p, _)| p == prefix) {
+                return format!("{namespace}{local_name}");
+            }
+        }
+
+        entity.to_owned()
+    }
+
+    fn cache_misses(&self) -> CacheMisses {
+        self.cache_misses.get()
+    }
+
+    fn turtle_prefixes(&self) -> Vec<(String, String)> {
+        self.prefixes.clone()
+    }
+
+    fn entity_term(&self, entity: &str) -> String {
+        self.compact_iri(entity)
+    }
+
+    fn prop_term(&self, prop: &str) -> String {
+        self.compact_iri(prop)
+    }
+
+    fn label_term(&self, _entity: &str) -> String {
+        self.compact_iri(&self.label_predicates[0])
+    }
+
+    fn description_term(&self, _entity: &str) -> String {
+        self.compact_iri(&self.description_predicates[0])
+    }
+}
+
+// Checks whether the local part of an IRI can be written as a prefixed name without escaping.
+fn is_local_name(local_name: &str) -> bool {
+    !local_name.is_empty()
+        && !local_name.ends_with('.')
+        && local_name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
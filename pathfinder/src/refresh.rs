@@ -0,0 +1,97 @@
+use itertools::Itertools;
+use log::{debug, info, warn};
+
+use crate::store_connector::StoreConnector;
+
+// The number of seconds of a day, the unit of the maximum age of the refresh mode
+pub const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Fetches the adjacency lists of entities again and reports the changed edges.
+/// Lists imported from a dump or of unknown source are replaced without comparing them with the fetched lists.
+/// Entities whose list cannot be fetched or stored are counted as failed and skipped.
+/// # Arguments
+/// * `store_connector` - The StoreConnector holding the adjacency lists
+/// * `entities` - The entities to refresh, e.g., the stale entities of the store
+pub fn refresh_entities(store_connector: &StoreConnector, mut entities: Vec<String>) {
+    entities.sort();
+    info!("Refreshing {} adjacency lists.", entities.len());
+
+    let (mut changed_count, mut added_count, mut removed_count, mut failed_count) = (0, 0, 0, 0);
+    let mut replaced_count = 0;
+
+    for entity in &entities {
+        let change = match store_connector.refresh_adjacency_list(entity) {
+            Ok(Some(change)) => change,
+            Ok(None) => {
+                failed_count += 1;
+                continue;
+            }
+            Err(error) => {
+                warn!(
+                    "Adjacency list of {} could not be refreshed: {}",
+                    entity, error
+                );
+                failed_count += 1;
+                continue;
+            }
+        };
+
+        if !change.compared {
+            debug!(
+                "{} (fetched from {}) was replaced by {} edges of the Wikidata API without comparison.",
+                change.entity,
+                change
+                    .previous_fetch
+                    .map_or("an unknown source".to_owned(), |record| record.source),
+                change.edge_count
+            );
+            replaced_count += 1;
+            continue;
+        }
+
+        if change.added.is_empty() && change.removed.is_empty() {
+            continue;
+        }
+
+        let previous_fetch = change.previous_fetch.map_or_else(
+            || "at an unknown time".to_owned(),
+            |record| {
+                format!(
+                    "{:.1} days ago from {}",
+                    record.age() as f64 / SECONDS_PER_DAY,
+                    record.source
+                )
+            },
+        );
+        let format_edges = |edges: &[(String, String)]| {
+            edges
+                .iter()
+                .map(|(prop, adjacent_entity)| format!("{prop} {adjacent_entity}"))
+                .join(", ")
+        };
+
+        info!(
+            "{} (fetched {}) changed: {} added edges [{}], {} removed edges [{}]",
+            change.entity,
+            previous_fetch,
+            change.added.len(),
+            format_edges(&change.added),
+            change.removed.len(),
+            format_edges(&change.removed)
+        );
+
+        changed_count += 1;
+        added_count += change.added.len();
+        removed_count += change.removed.len();
+    }
+
+    info!(
+        "Refresh finished: {} of {} adjacency lists changed with {} added and {} removed edges; {} lists of other sources were replaced without comparison; {} could not be refreshed.",
+        changed_count,
+        entities.len(),
+        added_count,
+        removed_count,
+        replaced_count,
+        failed_count
+    );
+}
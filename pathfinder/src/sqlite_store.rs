@@ -0,0 +1,377 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::kv_store::{Batch, IVec, Iter, KvError, KvResult};
+
+// The name of the default tree, which equals the one of sled so both backends report the same tree names
+const DEFAULT_TREE_NAME: &[u8] = b"__sled__default";
+
+// The prefix of the tables holding trees; the rest of a table name is the hex encoded tree name
+const TABLE_PREFIX: &str = "tree_";
+
+// The number of rows read per query when iterating over a tree
+const ITERATION_CHUNK_SIZE: usize = 1_000;
+
+/// Returns the path of the SQLite file of a store configured at a path.
+pub fn file_path(path: &str) -> String {
+    format!("{path}.sqlite3")
+}
+
+/// A store in a single SQLite file, each tree is a table with a blob key as primary key.
+#[derive(Clone)]
+pub struct SqliteDb {
+    connection: Rc<Connection>,
+    path: String,
+}
+
+impl SqliteDb {
+    /// Opens or creates the SQLite file of a store.
+    pub fn open(path: &str) -> KvResult<Self> {
+        let connection = Connection::open(path)?;
+
+        // the write-ahead log allows fast single writes, which the stores use for caching
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+
+        Ok(Self {
+            connection: Rc::new(connection),
+            path: path.to_owned(),
+        })
+    }
+
+    /// Applies the batches of several trees in a single transaction.
+    pub fn apply_batches(&self, batches: Vec<(&SqliteTree, Batch)>) -> KvResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for (tree, batch) in batches {
+            tree.write_batch(&transaction, batch)?;
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Opens the default tree.
+    pub fn default_tree(&self) -> KvResult<SqliteTree> {
+        self.open_tree(DEFAULT_TREE_NAME)
+    }
+
+    /// Opens a tree, creating its table if it does not exist.
+    pub fn open_tree(&self, name: &[u8]) -> KvResult<SqliteTree> {
+        let table = format!("{TABLE_PREFIX}{}", hex(name));
+        self.connection.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {table} (key BLOB PRIMARY KEY, value BLOB NOT NULL) WITHOUT ROWID"
+            ),
+            [],
+        )?;
+
+        Ok(SqliteTree {
+            connection: self.connection.clone(),
+            table,
+            name: IVec::from(name),
+        })
+    }
+
+    /// Returns the names of all trees.
+    pub fn tree_names(&self) -> KvResult<Vec<IVec>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")?;
+
+        let mut names: Vec<IVec> = vec![];
+        for table in statement.query_map([], |row| row.get::<_, String>(0))? {
+            if let Some(name) = table?.strip_prefix(TABLE_PREFIX) {
+                names.push(IVec::from(unhex(name)?));
+            }
+        }
+
+        // like sled, the default tree always exists
+        if !names.iter().any(|name| name == DEFAULT_TREE_NAME) {
+            names.insert(0, IVec::from(DEFAULT_TREE_NAME));
+        }
+
+        Ok(names)
+    }
+
+    /// Returns the size of the SQLite file and its write-ahead log in bytes.
+    pub fn size_on_disk(&self) -> KvResult<u64> {
+        Ok([self.path.clone(), format!("{}-wal", self.path)]
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum())
+    }
+}
+
+/// A tree of a SQLite store.
+#[derive(Clone)]
+pub struct SqliteTree {
+    connection: Rc<Connection>,
+    table: String,
+    name: IVec,
+}
+
+impl SqliteTree {
+    pub fn get(&self, key: &[u8]) -> KvResult<Option<IVec>> {
+        let mut statement = self
+            .connection
+            .prepare_cached(&format!("SELECT value FROM {} WHERE key = ?1", self.table))?;
+
+        Ok(statement
+            .query_row(params![key], |row| row.get::<_, Vec<u8>>(0))
+            .optional()?
+            .map(IVec::from))
+    }
+
+    pub fn insert(&self, key: &[u8], value: &[u8]) -> KvResult<Option<IVec>> {
+        let previous = self.get(key)?;
+        self.connection
+            .prepare_cached(&format!(
+                "INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+                self.table
+            ))?
+            .execute(params![key, value])?;
+
+        Ok(previous)
+    }
+
+    pub fn remove(&self, key: &[u8]) -> KvResult<Option<IVec>> {
+        let previous = self.get(key)?;
+        self.connection
+            .prepare_cached(&format!("DELETE FROM {} WHERE key = ?1", self.table))?
+            .execute(params![key])?;
+
+        Ok(previous)
+    }
+
+    // SQLite compares blobs byte by byte like sled, so the entries are ordered the same way
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Iter {
+        Iter::new(SqliteIter {
+            tree: self.clone(),
+            prefix: prefix.to_vec(),
+            lower_bound: None,
+            buffer: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    // All operations of a batch are applied in a single transaction.
+    pub fn apply_batch(&self, batch: Batch) -> KvResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        self.write_batch(&transaction, batch)?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    // Writes the operations of a batch within a transaction.
+    fn write_batch(&self, transaction: &Transaction, batch: Batch) -> KvResult<()> {
+        let mut insert = transaction.prepare_cached(&format!(
+            "INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+            self.table
+        ))?;
+        let mut remove =
+            transaction.prepare_cached(&format!("DELETE FROM {} WHERE key = ?1", self.table))?;
+
+        for (key, value) in batch.operations {
+            match value {
+                Some(value) => insert.execute(params![key.as_ref(), value.as_ref()])?,
+                None => remove.execute(params![key.as_ref()])?,
+            };
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> KvResult<usize> {
+        let count = self.connection.query_row(
+            &format!("SELECT COUNT(*) FROM {}", self.table),
+            [],
+            |row| row.get::<_, i64>(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn is_empty(&self) -> KvResult<bool> {
+        let row = self
+            .connection
+            .query_row(&format!("SELECT 1 FROM {} LIMIT 1", self.table), [], |_| {
+                Ok(())
+            })
+            .optional()?;
+        Ok(row.is_none())
+    }
+
+    pub fn clear(&self) -> KvResult<()> {
+        self.connection
+            .execute(&format!("DELETE FROM {}", self.table), [])?;
+        Ok(())
+    }
+
+    pub fn name(&self) -> IVec {
+        self.name.clone()
+    }
+
+    // Reads the next rows after the lower bound, or from the prefix on if nothing was read yet.
+    fn read_chunk(&self, prefix: &[u8], lower_bound: Option<&[u8]>) -> KvResult<Vec<(IVec, IVec)>> {
+        let (condition, bound) = match lower_bound {
+            Some(bound) => ("key > ?1", bound),
+            None => ("key >= ?1", prefix),
+        };
+        let mut statement = self.connection.prepare_cached(&format!(
+            "SELECT key, value FROM {} WHERE {} ORDER BY key LIMIT {}",
+            self.table, condition, ITERATION_CHUNK_SIZE
+        ))?;
+
+        let rows = statement
+            .query_map(params![bound], |row| {
+                Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?
+            .map(|row| row.map(|(key, value)| (IVec::from(key), IVec::from(value))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(rows)
+    }
+}
+
+// Iterates over the rows of a tree in chunks, so a tree is never read into memory at once.
+struct SqliteIter {
+    tree: SqliteTree,
+    prefix: Vec<u8>,
+    // the key of the last returned entry
+    lower_bound: Option<IVec>,
+    buffer: VecDeque<(IVec, IVec)>,
+    finished: bool,
+}
+
+impl Iterator for SqliteIter {
+    type Item = KvResult<(IVec, IVec)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.finished {
+            match self
+                .tree
+                .read_chunk(&self.prefix, self.lower_bound.as_deref())
+            {
+                Ok(rows) => {
+                    self.finished = rows.len() < ITERATION_CHUNK_SIZE;
+                    self.buffer.extend(rows);
+                }
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        let (key, value) = self.buffer.pop_front()?;
+        if !key.starts_with(&self.prefix) {
+            self.finished = true;
+            self.buffer.clear();
+            return None;
+        }
+
+        self.lower_bound = Some(key.clone());
+        Some(Ok((key, value)))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// Decodes the hex encoded name of a tree; tables whose name is not hex encoded were not created as trees.
+fn unhex(hex: &str) -> KvResult<Vec<u8>> {
+    let invalid = || KvError(format!("The table {TABLE_PREFIX}{hex} is no tree."));
+    if !hex.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(iter: Iter) -> Vec<IVec> {
+        iter.keys().map(|key| key.unwrap()).collect()
+    }
+
+    #[test]
+    fn iteration_continues_across_chunks() {
+        let db = SqliteDb::open(":memory:").unwrap();
+        let tree = db.open_tree(b"entries").unwrap();
+        let mut batch = Batch::default();
+        for i in 0..2 * ITERATION_CHUNK_SIZE + 1 {
+            batch.insert(format!("k{i:05}").as_str(), "value");
+        }
+        tree.apply_batch(batch).unwrap();
+
+        let all_keys = keys(tree.scan_prefix(b""));
+        assert_eq!(all_keys.len(), 2 * ITERATION_CHUNK_SIZE + 1);
+        assert!(all_keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(tree.len().unwrap(), 2 * ITERATION_CHUNK_SIZE + 1);
+
+        // the prefix k01 covers the keys 1000 to 1999, which end exactly at a chunk boundary
+        assert_eq!(keys(tree.scan_prefix(b"k01")).len(), ITERATION_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn prefix_scans_stop_at_the_end_of_the_prefix() {
+        let db = SqliteDb::open(":memory:").unwrap();
+        let tree = db.open_tree(b"entries").unwrap();
+        for key in ["a", "a\u{0}1", "a1", "a2", "b1", "ab"] {
+            tree.insert(key.as_bytes(), b"value").unwrap();
+        }
+
+        assert_eq!(keys(tree.scan_prefix(b"a1")), [IVec::from("a1")]);
+        assert_eq!(keys(tree.scan_prefix(b"a")).len(), 5);
+        assert!(keys(tree.scan_prefix(b"c")).is_empty());
+    }
+
+    #[test]
+    fn batches_of_several_trees_are_atomic() {
+        let db = SqliteDb::open(":memory:").unwrap();
+        let (first, second) = (
+            db.open_tree(b"first").unwrap(),
+            db.open_tree(b"second").unwrap(),
+        );
+
+        // the batch of the second tree fails, as its table was dropped
+        db.connection
+            .execute(&format!("DROP TABLE {}", second.table), [])
+            .unwrap();
+        let (mut first_batch, mut second_batch) = (Batch::default(), Batch::default());
+        first_batch.insert("k1", "value");
+        second_batch.insert("k2", "value");
+
+        assert!(db
+            .apply_batches(vec![(&first, first_batch), (&second, second_batch)])
+            .is_err());
+        assert!(first.is_empty().unwrap());
+    }
+
+    #[test]
+    fn tables_that_are_no_trees_are_errors() {
+        let db = SqliteDb::open(":memory:").unwrap();
+        db.open_tree(b"entries").unwrap();
+        assert_eq!(
+            db.tree_names().unwrap(),
+            [IVec::from(DEFAULT_TREE_NAME), IVec::from("entries")]
+        );
+
+        db.connection
+            .execute("CREATE TABLE tree_xyz (key BLOB PRIMARY KEY)", [])
+            .unwrap();
+        assert!(db.tree_names().is_err());
+    }
+}
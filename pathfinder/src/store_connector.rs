@@ -1,13 +1,140 @@
-use bincode;
+use itertools::Itertools;
+
+use crate::adjacency_encoding::{self, AdjacencyIds, IdInterner};
+use crate::api_connector::{ApiConnector, MAX_ADJACENCY_DEPTH};
+use crate::bounded_tree::{BoundedTree, EvictionConfig};
+use crate::embedding_provider::EmbeddingProvider;
+use crate::embedding_store::{self, EmbeddingStore};
+use crate::entity_id::{entity_kind, normalize_entity_id, EntityKind};
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::graph_store::{CacheMisses, GraphStore};
+use crate::kv_store::{self, Batch, Db, IVec, KvBackend, Tree};
+use crate::literal_hub::{self, LiteralHubConfig};
+use crate::prop_frequencies::PropFrequencies;
+use crate::store_schema::{self, Metadata, StoreParameters, SCHEMA_VERSION};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use sled::{Batch, Db};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// The maximum number of redirects followed when resolving an entity
+const MAX_REDIRECT_HOPS: usize = 5;
+
+// The semantic distance used in offline mode for pairs of entities whose distance is not stored
+// This equals the cosine distance of orthogonal, i.e., unrelated embeddings
+const UNKNOWN_DISTANCE: f64 = 1.0;
+
+// The language of the labels and descriptions returned by the adjacent entities endpoint of the Wikidata API
+const DEFAULT_LANGUAGE: &str = "en";
+
+// The maximum number of entities embedded per request of the embedding provider
+const EMBEDDING_BATCH_SIZE: usize = 64;
+
+// The names of the stores in the order of StoreConnector::stores, as used in the config.toml without _path
+pub const STORE_NAMES: [&str; 9] = [
+    "label_mapping",
+    "desc_mapping",
+    "distance_mapping",
+    "embedding_store",
+    "adjacency_list",
+    "literal_list",
+    "redirect_mapping",
+    "statement_list",
+    "sitelink_mapping",
+];
+
+/// The locations of the key value stores and the options of a StoreConnector.
+pub struct StoreConfig {
+    pub label_mapping_path: String,
+    pub desc_mapping_path: String,
+    pub distance_mapping_path: String,
+    pub embedding_store_path: String,
+    pub adjacency_list_path: String,
+    pub literal_list_path: String,
+    pub redirect_mapping_path: String,
+    pub statement_list_path: String,
+    pub sitelink_mapping_path: String,
+    /// The embedded key value database the stores are kept in
+    pub kv_backend: KvBackend,
+    /// The number of uses of each property
+    pub prop_frequencies: PropFrequencies,
+    /// Semantic distances are evicted if set
+    pub distance_eviction: Option<EvictionConfig>,
+    /// Embedding vectors are evicted if set
+    pub embedding_eviction: Option<EvictionConfig>,
+    /// Literal hubs are only created if set
+    pub literal_hubs: Option<LiteralHubConfig>,
+    /// The language fallback chain for labels and descriptions, e.g., de, en, mul
+    pub languages: Vec<String>,
+    /// In offline mode, the APIs are never called and data missing in the stores is reported as cache misses
+    pub offline: bool,
+    /// If set, the stores are only inspected: they are neither migrated nor are their parameters recorded,
+    /// and stores of an older schema version are refused
+    pub read_only: bool,
+}
+
+/// A statement of an entity with an entity value, as imported from the Wikidata JSON dump.
+#[derive(Serialize, Deserialize)]
+pub struct Statement {
+    pub prop: String,
+    pub value: String,
+    /// preferred, normal, or deprecated
+    pub rank: String,
+    /// Pairs of qualifier property and qualifier value, e.g., (P580, +2001-01-01T00:00:00Z)
+    pub qualifiers: Vec<(String, String)>,
+}
+
+/// A literal value of an entity as property, Wikibase datatype, and raw value.
+pub type Literal = (String, String, String);
+
+/// The numeric ID of each entity with a stored adjacency list and its list, followed by the entity IDs ordered by their numeric IDs.
+pub type AllAdjacencyIds = (Vec<(u32, AdjacencyIds)>, Vec<String>);
+
+/// The on-disk size of a store and the number of entries of each of its trees.
+pub struct StoreSize {
+    pub name: &'static str,
+    pub size_on_disk: u64,
+    /// Pairs of tree name and number of entries, the default tree is named default
+    pub tree_lengths: Vec<(String, usize)>,
+}
+
+/// When and from which source the adjacency list of an entity was stored.
+#[derive(Serialize, Deserialize)]
+pub struct FetchRecord {
+    /// Seconds since the Unix epoch
+    pub fetched_at: u64,
+    /// One of the SOURCE constants
+    pub source: String,
+}
 
-use crate::api_connector::ApiConnector;
-use log::{debug, warn};
+impl FetchRecord {
+    /// Returns the number of seconds since the adjacency list was stored.
+    pub fn age(&self) -> u64 {
+        unix_time().saturating_sub(self.fetched_at)
+    }
+}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct StoreValue {
-    data: Vec<String>,
+/// The source of adjacency lists fetched from the Wikidata API.
+pub const SOURCE_WIKIDATA_API: &str = "wikidata_api";
+/// The source of adjacency lists imported from a truthy N-Triples dump.
+pub const SOURCE_TRUTHY_DUMP: &str = "truthy_dump";
+/// The source of adjacency lists imported from a JSON dump.
+pub const SOURCE_JSON_DUMP: &str = "json_dump";
+
+/// The edges of an adjacency list that changed when it was fetched again.
+/// Only lists of the same source are compared, as dumps and the Wikidata API differ in their snapshot of Wikidata.
+pub struct AdjacencyChange {
+    pub entity: String,
+    /// Pairs of property and adjacent entity that were not stored before, empty if the lists were not compared
+    pub added: Vec<(String, String)>,
+    /// Pairs of property and adjacent entity that are no longer part of the adjacency list, empty if the lists were not compared
+    pub removed: Vec<(String, String)>,
+    /// The fetch record of the replaced adjacency list, None for lists stored before fetches were recorded
+    pub previous_fetch: Option<FetchRecord>,
+    /// The number of edges of the fetched adjacency list
+    pub edge_count: usize,
+    /// Whether the replaced list was fetched from the Wikidata API as well, such that both lists were compared
+    pub compared: bool,
 }
 
 pub struct StoreConnector<'a> {
@@ -15,217 +142,2235 @@ pub struct StoreConnector<'a> {
     label_mapping: Db,
     desc_mapping: Db,
     distance_mapping: Db,
+    // semantic distances keyed by embedding model and ordered pair of entity IDs
+    // the default tree of the distance mapping holds the distances of schema version 2 keyed by labels and descriptions
+    distances: BoundedTree,
+    embedding_store: EmbeddingStore,
+    embedding_provider: Box<dyn EmbeddingProvider + 'a>,
     adjacency_list: Db,
+    // the fetch record of each adjacency list
+    fetch_records: Tree,
+    interner: IdInterner,
+    // the (property, entity) pairs pointing to each entity, kept in the adjacency list store
+    // such that both are written in the same transaction
+    incoming_lists: Tree,
+    literal_list: Db,
+    redirect_mapping: Db,
+    statement_list: Db,
+    sitelink_mapping: Db,
+    prop_frequencies: PropFrequencies,
+    literal_hubs: Option<LiteralHubConfig>,
+    languages: Vec<String>,
+    offline: bool,
+    adjacency_failure_count: Cell<usize>,
+    cache_misses: Cell<CacheMisses>,
+    // the entities whose embedding request failed in this run, they are not requested again
+    failed_embeddings: RefCell<HashSet<String>>,
 }
 
 impl<'a> StoreConnector<'a> {
+    // Opens the stores and migrates them to the current schema version.
+    // Returns an error if a store cannot be opened or was created by a newer version of the pathfinder.
     pub fn new(
         api_connector: &'a ApiConnector,
-        label_mapping_path: String,
-        desc_mapping_path: String,
-        distance_mapping_path: String,
-        adjacency_list_path: String,
-    ) -> Self {
+        embedding_provider: Box<dyn EmbeddingProvider + 'a>,
+        config: StoreConfig,
+    ) -> PathfinderResult<Self> {
         // load key value stores
         let db_paths = [
-            label_mapping_path,
-            desc_mapping_path,
-            distance_mapping_path,
-            adjacency_list_path,
+            config.label_mapping_path,
+            config.desc_mapping_path,
+            config.distance_mapping_path,
+            config.embedding_store_path,
+            config.adjacency_list_path,
+            config.literal_list_path,
+            config.redirect_mapping_path,
+            config.statement_list_path,
+            config.sitelink_mapping_path,
         ];
 
-        let [label_mapping, desc_mapping, distance_mapping, adjacency_list] =
-            db_paths.map(|path| sled::open(path).unwrap());
+        let mut stores = vec![];
+        for path in db_paths {
+            let store = kv_store::open(&path, config.kv_backend).map_err(|error| {
+                PathfinderError::Store(format!("Store at {path} could not be opened: {error}"))
+            })?;
+            stores.push(store);
+        }
+
+        let Ok(
+            [label_mapping, desc_mapping, distance_mapping, embedding_store, adjacency_list, literal_list, redirect_mapping, statement_list, sitelink_mapping],
+        ) = <[Db; 9]>::try_from(stores)
+        else {
+            unreachable!()
+        };
 
         // create instance with loaded stores
-        Self {
+        let store_connector = Self {
             api_connector,
             desc_mapping,
             label_mapping,
+            distances: BoundedTree::open(
+                "distances",
+                distance_mapping.open_tree("distances").map_err(|error| {
+                    PathfinderError::Store(format!("Distances could not be opened: {error}"))
+                })?,
+                distance_mapping
+                    .open_tree("distance_accesses")
+                    .map_err(|error| {
+                        PathfinderError::Store(format!(
+                            "Distance accesses could not be opened: {error}"
+                        ))
+                    })?,
+                config.distance_eviction,
+            )
+            .map_err(|error| {
+                PathfinderError::Store(format!("Distances could not be opened: {error}"))
+            })?,
             distance_mapping,
+            embedding_store: EmbeddingStore::new(
+                embedding_store,
+                embedding_provider.model().to_owned(),
+                config.embedding_eviction,
+            )
+            .map_err(|error| {
+                PathfinderError::Store(format!("Embedding vectors could not be opened: {error}"))
+            })?,
+            embedding_provider,
+            interner: IdInterner::open(&adjacency_list).map_err(|error| {
+                PathfinderError::Store(format!("Interned IDs could not be opened: {error}"))
+            })?,
+            fetch_records: adjacency_list.open_tree("fetch_records").map_err(|error| {
+                PathfinderError::Store(format!("Fetch records could not be opened: {error}"))
+            })?,
+            incoming_lists: adjacency_list
+                .open_tree("incoming_lists")
+                .map_err(|error| {
+                    PathfinderError::Store(format!("Incoming lists could not be opened: {error}"))
+                })?,
             adjacency_list,
+            literal_list,
+            redirect_mapping,
+            statement_list,
+            sitelink_mapping,
+            prop_frequencies: config.prop_frequencies,
+            literal_hubs: config.literal_hubs,
+            languages: config.languages,
+            offline: config.offline,
+            adjacency_failure_count: Cell::new(0),
+            cache_misses: Cell::new(CacheMisses::default()),
+            failed_embeddings: RefCell::new(HashSet::new()),
+        };
+
+        store_connector.open_schema(config.read_only)?;
+
+        Ok(store_connector)
+    }
+
+    fn stores(&self) -> [&Db; 9] {
+        [
+            &self.label_mapping,
+            &self.desc_mapping,
+            &self.distance_mapping,
+            self.embedding_store.db(),
+            &self.adjacency_list,
+            &self.literal_list,
+            &self.redirect_mapping,
+            &self.statement_list,
+            &self.sitelink_mapping,
+        ]
+    }
+
+    // Returns the on-disk size and the number of entries of each tree of all stores.
+    pub fn store_sizes(&self) -> PathfinderResult<Vec<StoreSize>> {
+        let mut store_sizes = vec![];
+        for (name, store) in STORE_NAMES.into_iter().zip(self.stores()) {
+            let mut tree_lengths = vec![];
+            for tree_name in store.tree_names()? {
+                let length = store.open_tree(&tree_name)?.len()?;
+                let tree_name = if tree_name == store.name() {
+                    "default".to_owned()
+                } else {
+                    String::from_utf8_lossy(&tree_name).into_owned()
+                };
+                tree_lengths.push((tree_name, length));
+            }
+
+            store_sizes.push(StoreSize {
+                name,
+                size_on_disk: store.size_on_disk()?,
+                tree_lengths,
+            });
         }
+
+        Ok(store_sizes)
     }
 
-    // Fetches the entities adjacent to the specified entity.
-    // If the entity has not been seen before, its data and the data of the adjacent entities is fetched.
-    pub fn get_adjacent_entities(&self, entity: &str) -> Vec<(String, String)> {
-        if !self.label_mapping.contains_key(entity).unwrap()
-            || !self.desc_mapping.contains_key(entity).unwrap()
-            || !self.adjacency_list.contains_key(entity).unwrap()
-        {
-            let (q_label_data, q_desc_data, p_label_data, p_desc_data, adjacent_entities_data) =
-                self.api_connector.fetch_adjacent_entity_data(entity);
+    // Checks the schema version of the stores, migrates older stores, and records the creation parameters.
+    // Newly created stores have no version yet and are initialized with the current version.
+    // Stores opened read-only are only checked.
+    fn open_schema(&self, read_only: bool) -> PathfinderResult<()> {
+        let mut versions = vec![];
+        for store in self.stores() {
+            versions.extend(store_schema::read_version(store)?);
+        }
 
-            let mut batch = Batch::default();
+        if let Some(&newest_version) = versions.iter().max() {
+            if newest_version > SCHEMA_VERSION {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "The stores have schema version {newest_version}, but only versions up to {SCHEMA_VERSION} are supported."
+                )));
+            }
+        }
 
-            // update the adjacency list for all retrieved entities
-            for (some_entity, its_adjacent_entities) in adjacent_entities_data {
-                if !self.adjacency_list.contains_key(&some_entity).unwrap() {
-                    let its_adjacent_entities_parsed = its_adjacent_entities.as_array().unwrap();
-                    let cleaned_entities: Vec<String> = its_adjacent_entities_parsed
-                        .iter()
-                        .map(|elem| elem.as_str().unwrap().to_owned())
-                        .collect();
-
-                    // a key is a single entity and the value is a vector with elements of this form: some_property-adjacent_entity
-                    batch.insert(
-                        some_entity.as_str(),
-                        bincode::serialize(&cleaned_entities).unwrap(),
-                    );
-                }
+        let version = versions.iter().min().copied().unwrap_or(SCHEMA_VERSION);
+        if read_only {
+            if version < SCHEMA_VERSION {
+                return Err(PathfinderError::DataConsistency(format!(
+                    "The stores have schema version {version} and must be migrated to version {SCHEMA_VERSION} with the migrate command first."
+                )));
             }
+            return Ok(());
+        }
 
-            self.adjacency_list.apply_batch(batch).unwrap();
+        for from_version in version..SCHEMA_VERSION {
+            info!(
+                "Migrating stores from schema version {} to {}.",
+                from_version,
+                from_version + 1
+            );
+            self.migrate_schema(from_version)?;
+        }
 
-            // update the label mapping for all retrieved entities and properties
-            batch = Batch::default();
-            q_label_data.iter().for_each(|(e, l)| {
-                batch.insert(e.as_str(), l.as_str().unwrap());
-            });
+        for store in self.stores() {
+            store_schema::write_version(store, SCHEMA_VERSION)?;
+        }
 
-            p_label_data.iter().for_each(|(e, l)| {
-                batch.insert(e.as_str(), l.as_str().unwrap());
-            });
+        for ((name, store), parameters) in STORE_NAMES
+            .into_iter()
+            .zip(self.stores())
+            .zip(self.fetch_parameters())
+        {
+            for (key, recorded, current) in
+                store_schema::changed_creation_parameters(store, &parameters)?
+            {
+                warn!(
+                    "The store {} was created with {} {}, but {} is configured now.",
+                    name, key, recorded, current
+                );
+            }
+            store_schema::write_creation_parameters(store, &parameters)?;
+        }
 
-            self.label_mapping.apply_batch(batch).unwrap();
+        Ok(())
+    }
 
-            // update the desc mapping for all retrieved entities
-            batch = Batch::default();
-            q_desc_data.iter().for_each(|(e, l)| {
-                batch.insert(e.as_str(), l.as_str().unwrap());
-            });
+    // Returns the parameters with which each store is filled by fetching from the APIs, in the order of STORE_NAMES.
+    // Distances and embedding vectors are keyed by embedding model, statements and sitelink counts are only imported.
+    fn fetch_parameters(&self) -> [StoreParameters; 9] {
+        let languages = ("languages", self.languages.join(","));
+        [
+            vec![languages.clone()],
+            vec![languages],
+            vec![],
+            vec![],
+            vec![
+                ("adjacency_depth", MAX_ADJACENCY_DEPTH.to_string()),
+                ("filters", adjacency_filters()),
+            ],
+            vec![("filters", format!("dates, coordinates, and external IDs via properties with a label and a description in {DEFAULT_LANGUAGE}"))],
+            vec![],
+            vec![],
+            vec![],
+        ]
+    }
 
-            p_desc_data.iter().for_each(|(e, l)| {
-                batch.insert(e.as_str(), l.as_str().unwrap());
-            });
+    // Records the parameters of an import of a dump in the metadata of the stores the import writes to.
+    // The adjacency list store additionally records the filters applied to the imported edges.
+    pub fn record_import(&self, source: &str, parameters: StoreParameters) -> PathfinderResult<()> {
+        let mut parameters = parameters;
+        parameters.insert(0, ("source", source.to_owned()));
 
-            self.desc_mapping.apply_batch(batch).unwrap();
+        let mut stores = vec![&self.label_mapping, &self.desc_mapping];
+        if source == SOURCE_JSON_DUMP {
+            stores.extend([&self.statement_list, &self.sitelink_mapping]);
+        }
+        for store in stores {
+            store_schema::write_import_parameters(store, &parameters)?;
         }
 
-        // read from store
-        let bytes = self.adjacency_list.get(entity).unwrap().unwrap();
-        let raw: Vec<String> = bincode::deserialize(&bytes).unwrap();
+        parameters.push(("filters", adjacency_filters()));
+        store_schema::write_import_parameters(&self.adjacency_list, &parameters)
+    }
 
-        let mut adjacent_entities: Vec<(String, String)> = vec![];
+    // Reads the metadata of each store as pairs of key and value, e.g., the parameters of the latest import.
+    pub fn store_metadata(&self) -> PathfinderResult<Vec<(&'static str, Metadata)>> {
+        STORE_NAMES
+            .into_iter()
+            .zip(self.stores())
+            .map(|(name, store)| Ok((name, store_schema::read_metadata(store)?)))
+            .collect()
+    }
 
-        for entry in raw {
-            let split: Vec<&str> = entry.split("-").collect();
-            adjacent_entities.push((split[0].to_string(), split[1].to_string()))
+    // Migrates the stores from a schema version to the next one.
+    fn migrate_schema(&self, from_version: u32) -> PathfinderResult<()> {
+        match from_version {
+            // the incoming lists are rebuilt by the migration from version 3
+            1 => {
+                self.migrate_adjacency_lists()?;
+            }
+            // distances keyed by labels and descriptions are kept and moved to the new keys when they are read
+            2 => {}
+            // the incoming lists moved from their own store into the adjacency list store
+            // the parameters were recorded for the adjacency lists only and are recorded for each store now
+            3 => {
+                self.rebuild_incoming_list()?;
+                store_schema::clear_creation_parameters(&self.adjacency_list)?;
+            }
+            _ => unreachable!("No migration from schema version {from_version}."),
         }
 
-        debug!(
-            "get_adjacent_entities received entity {} and returned {} entities.",
-            entity,
-            adjacent_entities.len()
+        Ok(())
+    }
+
+    // Calculates a semantic distance that is not stored yet.
+    // Stored embedding vectors are preferred over distances keyed by labels and descriptions and over the embedding provider.
+    // In offline mode, None is returned if the distance cannot be calculated locally.
+    // None is also returned if the cosine distance is undefined, e.g., for a zero vector.
+    fn calculate_semantic_distance(
+        &self,
+        entity_a: &str,
+        entity_b: &str,
+    ) -> PathfinderResult<Option<f64>> {
+        if let (Some(vector_a), Some(vector_b)) = (
+            self.embedding_store.get(entity_a)?,
+            self.embedding_store.get(entity_b)?,
+        ) {
+            return Ok(embedding_store::cosine_distance(&vector_a, &vector_b));
+        }
+
+        if let Some(distance) = self.read_legacy_distance(entity_a, entity_b)? {
+            return Ok(distance);
+        }
+
+        // local embedding providers are used in offline mode as well
+        if self.offline && !self.embedding_provider.is_local() {
+            self.record_miss(|m| m.distances += 1);
+            return Ok(None);
+        }
+
+        self.embed_entities(&[entity_a, entity_b])?;
+
+        match (
+            self.embedding_store.get(entity_a)?,
+            self.embedding_store.get(entity_b)?,
+        ) {
+            (Some(vector_a), Some(vector_b)) => {
+                Ok(embedding_store::cosine_distance(&vector_a, &vector_b))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // Reads a distance keyed by labels and descriptions as stored by schema version 2.
+    // Returns Some(None) for a stored distance that is not a number.
+    fn read_legacy_distance(
+        &self,
+        entity_a: &str,
+        entity_b: &str,
+    ) -> PathfinderResult<Option<Option<f64>>> {
+        if self.distance_mapping.is_empty()? {
+            return Ok(None);
+        }
+
+        // distances of schema version 2 were stored for one order of the texts only
+        let (text_a, text_b) = (
+            self.embedding_text(entity_a)?,
+            self.embedding_text(entity_b)?,
         );
+        for key in [
+            format!("{}&{}", text_a, text_b),
+            format!("{}&{}", text_b, text_a),
+        ] {
+            if let Some(value) = self.distance_mapping.get(key)? {
+                return Ok(Some(std::str::from_utf8(&value)?.parse().ok()));
+            }
+        }
 
-        adjacent_entities
+        Ok(None)
     }
 
-    pub fn get_description(&self, entity: &str) -> String {
-        let contains = self.desc_mapping.contains_key(&entity).unwrap();
+    // Returns the text an entity is embedded with.
+    // Both labels and descriptions are used as the descriptions mitigate ambiguity in entity labels.
+    fn embedding_text(&self, entity: &str) -> PathfinderResult<String> {
+        Ok(format!(
+            "{} {}",
+            self.get_label(entity)?,
+            self.get_description(entity)?
+        ))
+    }
 
-        let description = match contains {
-            true => {
-                let value = self.desc_mapping.get(&entity).unwrap().unwrap();
-                String::from(std::str::from_utf8(&value).unwrap())
+    // Embeds the entities without stored embedding vector in batches and stores their vectors.
+    // Failures of the embedding provider are counted like failed requests, so the search continues without distances.
+    fn embed_entities(&self, entities: &[&str]) -> PathfinderResult<()> {
+        let mut missing_entities: Vec<&str> = vec![];
+        for entity in entities.iter().copied().unique() {
+            if self.embedding_store.get(entity)?.is_none()
+                && !self.failed_embeddings.borrow().contains(entity)
+            {
+                missing_entities.push(entity);
             }
-            false => self.fallback_get_label_description(&entity).1,
-        };
+        }
+
+        for batch in missing_entities.chunks(EMBEDDING_BATCH_SIZE) {
+            let texts = batch
+                .iter()
+                .map(|entity| Ok((entity.to_string(), self.embedding_text(entity)?)))
+                .collect::<PathfinderResult<Vec<(String, String)>>>()?;
+
+            match self.embedding_provider.embed(&texts) {
+                // entities the provider cannot embed are marked with an empty vector
+                Ok(vectors) => {
+                    for (entity, vector) in batch.iter().zip(vectors) {
+                        self.embedding_store
+                            .insert(entity, &vector.unwrap_or_default())?;
+                    }
+                }
+                // failed requests may succeed in a later run, so their entities are only skipped in this run
+                Err(error) => {
+                    warn!("{} entities could not be embedded: {}", batch.len(), error);
+                    self.failed_embeddings
+                        .borrow_mut()
+                        .extend(batch.iter().map(|entity| entity.to_string()));
+                }
+            }
+        }
+
+        debug!("Embedded {} entities.", missing_entities.len());
+
+        Ok(())
+    }
 
-        description
+    // Counts a lookup that could not be answered from the stores in offline mode.
+    fn record_miss(&self, update: impl FnOnce(&mut CacheMisses)) {
+        let mut cache_misses = self.cache_misses.get();
+        update(&mut cache_misses);
+        self.cache_misses.set(cache_misses);
     }
 
-    pub fn get_label(&self, entity: &str) -> String {
-        let contains = self.label_mapping.contains_key(&entity).unwrap();
+    // Looks up the entity an entity redirects to.
+    // An empty value in the redirect mapping marks an entity known not to be redirected.
+    fn get_redirect(&self, entity: &str) -> PathfinderResult<Option<String>> {
+        // only Wikibase entities can be redirected
+        if entity_kind(entity).is_none() {
+            return Ok(None);
+        }
 
-        let label = match contains {
-            true => {
-                let value = self.label_mapping.get(&entity).unwrap().unwrap();
-                String::from(std::str::from_utf8(&value).unwrap())
+        let target = match self.redirect_mapping.get(entity)? {
+            Some(value) => String::from(std::str::from_utf8(&value)?),
+            None if self.offline => {
+                self.record_miss(|m| m.redirects += 1);
+                return Ok(None);
             }
-            false => self.fallback_get_label_description(&entity).0,
+            None => match self.api_connector.fetch_redirect(entity) {
+                Ok(target) => {
+                    self.redirect_mapping.insert(entity, target.as_str())?;
+                    target
+                }
+                Err(error) => {
+                    warn!("Redirect of {} could not be fetched: {}", entity, error);
+                    return Ok(None);
+                }
+            },
         };
 
-        label
+        Ok((!target.is_empty()).then_some(target))
     }
 
-    // semantic distance is calculated using both entity labels and descriptions
-    // the descriptions mitigate ambiguity in entity labels
-    pub fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> f64 {
-        let string_a = format!(
-            "{} {}",
-            self.get_label(entity_a),
-            self.get_description(entity_a)
-        );
-        let string_b = format!(
-            "{} {}",
-            self.get_label(entity_b),
-            self.get_description(entity_b)
-        );
+    // Fetches the literal hubs adjacent to the specified entity.
+    // The raw literal values are stored such that they can be normalised with a different precision later on.
+    fn get_adjacent_literal_hubs(
+        &self,
+        entity: &str,
+        literal_hubs: &LiteralHubConfig,
+    ) -> PathfinderResult<Vec<(String, String)>> {
+        let raw: Vec<Literal> = match self.literal_list.get(entity)? {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None if self.offline => {
+                self.record_miss(|m| m.adjacent_entities += 1);
+                return Ok(vec![]);
+            }
+            None => {
+                let (literal_data, p_label_data, p_desc_data) =
+                    match self.api_connector.fetch_adjacent_literal_data(entity) {
+                        Ok(data) => data,
+                        Err(error) => {
+                            warn!(
+                                "Adjacent literals of {} could not be fetched: {}",
+                                entity, error
+                            );
+                            return Ok(vec![]);
+                        }
+                    };
 
-        let combined_string = format!("{}&{}", string_a, string_b);
+                // a key is a single entity and the value is a vector with elements of this form: (some_property, datatype, raw_value)
+                self.literal_list
+                    .insert(entity, bincode::serialize(&literal_data)?)?;
 
-        let contains = self
-            .distance_mapping
-            .contains_key(&combined_string)
-            .unwrap();
+                // update the label and desc mapping for the properties linking to the literals
+                self.store_language_values(
+                    &self.label_mapping,
+                    string_pairs(p_label_data.iter()),
+                    DEFAULT_LANGUAGE,
+                )?;
+                self.store_language_values(
+                    &self.desc_mapping,
+                    string_pairs(p_desc_data.iter()),
+                    DEFAULT_LANGUAGE,
+                )?;
+
+                let fetched_props: Vec<String> = p_label_data.keys().cloned().collect();
+                self.fetch_missing_languages(&fetched_props)?;
+
+                literal_data
+            }
+        };
+
+        Ok(raw
+            .iter()
+            .filter_map(|(prop, datatype, value)| {
+                literal_hubs
+                    .normalize(prop, datatype, value)
+                    .map(|hub| (prop.to_owned(), hub))
+            })
+            .unique()
+            .collect())
+    }
 
-        let distance = match contains {
-            true => {
-                let value = self
-                    .distance_mapping
-                    .get(&combined_string)
-                    .unwrap()
-                    .unwrap();
-                let value_as_string = String::from(std::str::from_utf8(&value).unwrap());
+    // Looks up a label or description along the language fallback chain.
+    // If a language of the chain has never been fetched for the entity, a fallback request is made first.
+    // In offline mode, an empty value is returned as unknown value if no language of the chain is stored.
+    fn get_language_value(
+        &self,
+        mapping: &Db,
+        entity: &str,
+        record_miss: fn(&mut CacheMisses),
+    ) -> PathfinderResult<(String, String)> {
+        let mut stored_count = 0;
+        for language in &self.languages {
+            stored_count += contains_language_value(mapping, entity, language)? as usize;
+        }
+
+        if stored_count < self.languages.len() {
+            if !self.offline {
+                self.fallback_get_label_description(entity)?;
+            } else if stored_count == 0 {
+                self.record_miss(record_miss);
+            }
+        }
 
-                value_as_string.parse().unwrap()
+        Ok(self
+            .read_stored_language_value(mapping, entity)?
+            .unwrap_or_default())
+    }
+
+    // Reads a label or description along the language fallback chain without fetching missing languages.
+    // An empty value marks a language in which the entity has no label or description.
+    fn read_stored_language_value(
+        &self,
+        mapping: &Db,
+        entity: &str,
+    ) -> PathfinderResult<Option<(String, String)>> {
+        for language in &self.languages {
+            if let Some(value) = read_language_value(mapping, entity, language)? {
+                if !value.is_empty() {
+                    return Ok(Some((value, language.to_owned())));
+                }
             }
-            false => {
-                let value = self
-                    .api_connector
-                    .fetch_semantic_distance(&string_a, &string_b);
-                let value_as_string = &value.to_string();
+        }
+
+        Ok(None)
+    }
+
+    // Reads the stored label of an entity and its language without fetching it, e.g., to inspect the stores.
+    pub fn read_stored_label(&self, entity: &str) -> PathfinderResult<Option<(String, String)>> {
+        self.read_stored_language_value(&self.label_mapping, entity)
+    }
+
+    // Reads the stored description of an entity and its language without fetching it.
+    pub fn read_stored_description(
+        &self,
+        entity: &str,
+    ) -> PathfinderResult<Option<(String, String)>> {
+        self.read_stored_language_value(&self.desc_mapping, entity)
+    }
+
+    // Stores labels or descriptions of a single language.
+    fn store_language_values<'b>(
+        &self,
+        mapping: &Db,
+        values: impl Iterator<Item = (&'b str, &'b str)>,
+        language: &str,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        values.for_each(|(e, l)| {
+            batch.insert(language_key(e, language).as_str(), l);
+        });
+        mapping.apply_batch(batch)?;
+
+        Ok(())
+    }
+
+    // Stores the labels of entities or properties in a specific language, e.g., when importing a dump.
+    pub fn store_labels<'b>(
+        &self,
+        labels: impl Iterator<Item = (&'b str, &'b str)>,
+        language: &str,
+    ) -> PathfinderResult<()> {
+        self.store_language_values(&self.label_mapping, labels, language)
+    }
+
+    // Stores the descriptions of entities or properties in a specific language, e.g., when importing a dump.
+    pub fn store_descriptions<'b>(
+        &self,
+        descriptions: impl Iterator<Item = (&'b str, &'b str)>,
+        language: &str,
+    ) -> PathfinderResult<()> {
+        self.store_language_values(&self.desc_mapping, descriptions, language)
+    }
 
-                self.distance_mapping
-                    .insert(combined_string, value_as_string.as_str())
-                    .unwrap();
+    // Marks all languages of the fallback chain in which an entity has no label or description with an empty value.
+    // Marked languages are not requested from the Wikidata API anymore.
+    pub fn mark_missing_languages(&self, entities: &[String]) -> PathfinderResult<()> {
+        for mapping in [&self.label_mapping, &self.desc_mapping] {
+            let mut batch = Batch::default();
 
-                value_as_string.parse().unwrap()
+            for entity in entities {
+                for language in &self.languages {
+                    if !contains_language_value(mapping, entity, language)? {
+                        batch.insert(language_key(entity, language).as_str(), "");
+                    }
+                }
             }
+
+            mapping.apply_batch(batch)?;
+        }
+
+        Ok(())
+    }
+
+    // Stores the statements of entities including rank and qualifiers, replacing already stored statements.
+    pub fn store_statements(
+        &self,
+        statements: Vec<(String, Vec<Statement>)>,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (some_entity, its_statements) in statements {
+            batch.insert(some_entity.as_str(), bincode::serialize(&its_statements)?);
+        }
+        self.statement_list.apply_batch(batch)?;
+        Ok(())
+    }
+
+    // Stores the number of sitelinks, i.e., of linked Wikipedia articles and other wiki pages, of entities.
+    pub fn store_sitelink_counts(
+        &self,
+        sitelink_counts: Vec<(String, usize)>,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (some_entity, its_sitelink_count) in sitelink_counts {
+            batch.insert(
+                some_entity.as_str(),
+                its_sitelink_count.to_string().as_str(),
+            );
+        }
+        self.sitelink_mapping.apply_batch(batch)?;
+        Ok(())
+    }
+
+    // Reads the stored statements of an entity, which are only available after importing the JSON dump.
+    pub fn read_statements(&self, entity: &str) -> PathfinderResult<Option<Vec<Statement>>> {
+        match self.statement_list.get(entity)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Reads the stored number of sitelinks of an entity.
+    pub fn read_sitelink_count(&self, entity: &str) -> PathfinderResult<Option<usize>> {
+        let Some(value) = self.sitelink_mapping.get(entity)? else {
+            return Ok(None);
         };
+        let value = std::str::from_utf8(&value)?;
+        value.parse().map(Some).map_err(|_| {
+            PathfinderError::Decode(format!(
+                "The sitelink count {value} of {entity} is no number."
+            ))
+        })
+    }
 
-        debug!(
-            "Semantic distance between {} and {}: {}",
-            entity_a, entity_b, distance
-        );
+    // Returns the IDs of all entities and properties with data in any store ordered by ID, e.g., to export the stores.
+    pub fn stored_entities(&self) -> PathfinderResult<BTreeSet<String>> {
+        let mut entities = BTreeSet::new();
+
+        // keys of stores created before labels and descriptions were language-tagged are plain entity IDs
+        for mapping in [&self.label_mapping, &self.desc_mapping] {
+            for key in mapping.iter().keys() {
+                let key = key?;
+                let key = std::str::from_utf8(&key)?;
+                entities.insert(key.rsplit_once('@').map_or(key, |(e, _)| e).to_owned());
+            }
+        }
+
+        for store in [
+            &self.adjacency_list,
+            &self.literal_list,
+            &self.redirect_mapping,
+            &self.statement_list,
+            &self.sitelink_mapping,
+        ] {
+            for key in store.iter().keys() {
+                entities.insert(String::from(std::str::from_utf8(&key?)?));
+            }
+        }
 
-        distance
+        Ok(entities)
     }
 
-    // Retrieves the average frequency of one or more properties
-    // This value is not stored locally
-    pub fn get_average_prop_frequency(&self, props: &Vec<String>) -> f64 {
-        return self.api_connector.fetch_average_prop_frequency(props);
+    // Reads the labels of an entity in all stored languages as pairs of language and label.
+    // An empty label marks a language in which the entity has no label.
+    pub fn read_all_labels(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        read_all_language_values(&self.label_mapping, entity)
     }
 
-    // For making fallback request if label or description was not be retrieved before.
-    // Label and description of an entity should already have been retrieved when fetching the adjacent entities of the entity pointing to this entity.
-    fn fallback_get_label_description(&self, entity: &str) -> (String, String) {
-        warn!("Fallback request for label of {} triggered.", entity);
-        let (fetched_label, fetched_description) =
-            self.api_connector.fetch_label_description(&entity);
+    // Reads the descriptions of an entity in all stored languages as pairs of language and description.
+    pub fn read_all_descriptions(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        read_all_language_values(&self.desc_mapping, entity)
+    }
 
-        self.label_mapping
-            .insert(entity, fetched_label.as_str())
-            .unwrap();
+    // Reads the stored redirect target of an entity, which is empty if the entity is known not to be redirected.
+    pub fn read_stored_redirect(&self, entity: &str) -> PathfinderResult<Option<String>> {
+        match self.redirect_mapping.get(entity)? {
+            Some(value) => Ok(Some(String::from(std::str::from_utf8(&value)?))),
+            None => Ok(None),
+        }
+    }
 
-        self.desc_mapping
-            .insert(entity, fetched_description.as_str())
-            .unwrap();
+    // Stores redirect targets, where an empty target marks an entity that is not redirected.
+    pub fn store_redirects(&self, redirects: Vec<(String, String)>) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (some_entity, its_target) in redirects {
+            batch.insert(some_entity.as_str(), its_target.as_str());
+        }
+        self.redirect_mapping.apply_batch(batch)?;
+        Ok(())
+    }
+
+    // Reads the stored literal values of an entity as (property, datatype, raw value) triples.
+    pub fn read_literals(&self, entity: &str) -> PathfinderResult<Option<Vec<Literal>>> {
+        match self.literal_list.get(entity)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Stores the literal values of entities, replacing already stored values.
+    pub fn store_literals(&self, literals: Vec<(String, Vec<Literal>)>) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (some_entity, its_literals) in literals {
+            batch.insert(some_entity.as_str(), bincode::serialize(&its_literals)?);
+        }
+        self.literal_list.apply_batch(batch)?;
+        Ok(())
+    }
+
+    // Reads the semantic distances of all embedding models as (model, entity, entity, distance) tuples.
+    pub fn read_all_semantic_distances(
+        &self,
+    ) -> impl Iterator<Item = PathfinderResult<(String, String, String, f64)>> + '_ {
+        self.distances.tree().iter().map(|entry| {
+            let (key, value) = entry?;
+            let key = std::str::from_utf8(&key)?;
+            let invalid =
+                || PathfinderError::Decode(format!("The semantic distance {key:?} is invalid."));
+
+            let (model, entity_a, entity_b) = key
+                .split('\0')
+                .map(str::to_owned)
+                .collect_tuple()
+                .ok_or_else(invalid)?;
+            let distance = f64::from_le_bytes(value.as_ref().try_into().map_err(|_| invalid())?);
+            Ok((model, entity_a, entity_b, distance))
+        })
+    }
+
+    // Stores semantic distances given as (model, entity, entity, distance) tuples.
+    pub fn store_semantic_distances(
+        &self,
+        distances: Vec<(String, String, String, f64)>,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (model, entity_a, entity_b, distance) in distances {
+            batch.insert(
+                distance_key(&model, &entity_a, &entity_b).as_str(),
+                &distance.to_le_bytes(),
+            );
+        }
+        self.distances.apply_batch(batch)
+    }
+
+    // Reads the distances of schema version 2 as pairs of the key, i.e., both texts joined by &, and the distance.
+    pub fn read_legacy_semantic_distances(
+        &self,
+    ) -> impl Iterator<Item = PathfinderResult<(String, f64)>> + '_ {
+        self.distance_mapping.iter().map(|entry| {
+            let (key, value) = entry?;
+            let key = String::from(std::str::from_utf8(&key)?);
+            let distance = std::str::from_utf8(&value)?.parse().map_err(|_| {
+                PathfinderError::Decode(format!(
+                    "The legacy semantic distance of {key} is no number."
+                ))
+            })?;
+            Ok((key, distance))
+        })
+    }
+
+    // Stores distances of schema version 2, so exported stores keep the distances that are not converted yet.
+    pub fn store_legacy_semantic_distances(
+        &self,
+        distances: Vec<(String, f64)>,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (key, distance) in distances {
+            batch.insert(key.as_str(), distance.to_string().as_str());
+        }
+        self.distance_mapping.apply_batch(batch)?;
+        Ok(())
+    }
+
+    // The store of the embedding vectors, which also holds the vectors of other embedding models.
+    pub fn embedding_store(&self) -> &EmbeddingStore {
+        &self.embedding_store
+    }
+
+    // Removes all data of the entities for which the predicate returns false from the stores,
+    // e.g., to keep only the entities reachable from a query set. Labels and descriptions of properties are kept,
+    // as are the distances keyed by labels and descriptions, which cannot be assigned to entities.
+    // The incoming list is rebuilt afterwards. Returns the number of removed entries per store.
+    pub fn retain_entities(
+        &self,
+        keep: impl Fn(&str) -> bool,
+    ) -> PathfinderResult<Vec<(&'static str, usize)>> {
+        let keep_entity_or_property =
+            |entity: &str| keep(entity) || entity_kind(entity) == Some(EntityKind::Property);
+
+        let mut removed_counts = vec![];
+        for (name, mapping) in [
+            ("label_mapping", &self.label_mapping),
+            ("desc_mapping", &self.desc_mapping),
+        ] {
+            // keys of stores created before labels and descriptions were language-tagged are plain entity IDs
+            let removed_count = remove_keys(mapping, |key| {
+                keep_entity_or_property(key.rsplit_once('@').map_or(key, |(e, _)| e))
+            })?;
+            removed_counts.push((name, removed_count));
+        }
 
-        (fetched_label, fetched_description)
+        let mut removed_distances: Vec<IVec> = vec![];
+        for key in self.distances.tree().iter().keys() {
+            let key = key?;
+            if std::str::from_utf8(&key)?
+                .split('\0')
+                .skip(1)
+                .any(|entity| !keep(entity))
+            {
+                removed_distances.push(key);
+            }
+        }
+        for key in &removed_distances {
+            self.distances.remove(key)?;
+        }
+        removed_counts.push(("distance_mapping", removed_distances.len()));
+        removed_counts.push(("embedding_store", self.embedding_store.retain(&keep)?));
+
+        remove_keys(&self.fetch_records, &keep)?;
+        for (name, store) in [
+            ("adjacency_list", &self.adjacency_list),
+            ("literal_list", &self.literal_list),
+            ("redirect_mapping", &self.redirect_mapping),
+            ("statement_list", &self.statement_list),
+            ("sitelink_mapping", &self.sitelink_mapping),
+        ] {
+            removed_counts.push((name, remove_keys(store, &keep)?));
+        }
+
+        self.rebuild_incoming_list()?;
+
+        Ok(removed_counts)
+    }
+
+    // Returns the number of stored semantic distances of the configured embedding model
+    // and the number of distances keyed by labels and descriptions of schema version 2.
+    pub fn semantic_distance_counts(&self) -> PathfinderResult<(usize, usize)> {
+        let prefix = format!("{}\0", self.embedding_store.model());
+        Ok((
+            self.distances.tree().scan_prefix(prefix).count(),
+            self.distance_mapping.len()?,
+        ))
+    }
+
+    // Returns the number of stored embedding vectors of the configured embedding model.
+    pub fn embedding_count(&self) -> usize {
+        self.embedding_store.len()
+    }
+
+    // The language fallback chain of labels and descriptions.
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    // Checks whether an entity has a non-empty label and description in the language of the Wikidata API.
+    // The Wikidata API only returns adjacent entities that fulfill this condition.
+    pub fn has_label_and_description(&self, entity: &str) -> PathfinderResult<bool> {
+        for mapping in [&self.label_mapping, &self.desc_mapping] {
+            if read_language_value(mapping, entity, DEFAULT_LANGUAGE)?
+                .is_none_or(|value| value.is_empty())
+            {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    // Stores the adjacency lists of entities in the compact format and records when and from which source they were fetched.
+    // A list contains elements of this form: some_property-adjacent_entity
+    // If merge is set, the lists are combined with already stored lists instead of replacing them.
+    pub fn store_adjacency_lists(
+        &self,
+        adjacency_lists: Vec<(String, Vec<String>)>,
+        merge: bool,
+        source: &str,
+    ) -> PathfinderResult<()> {
+        let fetched_at = unix_time();
+        self.store_fetch_records(
+            adjacency_lists
+                .iter()
+                .map(|(some_entity, _)| {
+                    let record = FetchRecord {
+                        fetched_at,
+                        source: source.to_owned(),
+                    };
+                    (some_entity.to_owned(), record)
+                })
+                .collect(),
+        )?;
+
+        self.write_adjacency_lists(adjacency_lists, merge)
+    }
+
+    // Stores adjacency lists along with their original fetch records, e.g., when importing exported stores.
+    // Lists without a record keep an unknown fetch time.
+    pub fn restore_adjacency_lists(
+        &self,
+        adjacency_lists: Vec<(String, Vec<String>)>,
+        fetch_records: Vec<(String, FetchRecord)>,
+    ) -> PathfinderResult<()> {
+        self.store_fetch_records(fetch_records)?;
+        self.write_adjacency_lists(adjacency_lists, false)
+    }
+
+    fn store_fetch_records(
+        &self,
+        fetch_records: Vec<(String, FetchRecord)>,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        for (some_entity, record) in fetch_records {
+            batch.insert(some_entity.as_str(), bincode::serialize(&record)?);
+        }
+        self.fetch_records.apply_batch(batch)?;
+
+        Ok(())
+    }
+
+    // Interns the entities and properties of adjacency lists and writes them in the compact format.
+    fn write_adjacency_lists(
+        &self,
+        adjacency_lists: Vec<(String, Vec<String>)>,
+        merge: bool,
+    ) -> PathfinderResult<()> {
+        let mut id_lists = vec![];
+        for (some_entity, its_adjacent_entities) in adjacency_lists {
+            let pairs = its_adjacent_entities
+                .iter()
+                .map(|edge| self.intern_edge(edge))
+                .collect::<PathfinderResult<AdjacencyIds>>()?;
+            id_lists.push((some_entity, pairs));
+        }
+
+        self.write_adjacency_ids(id_lists, merge)
+    }
+
+    // Interns the property and the adjacent entity of an edge of this form: some_property-adjacent_entity
+    fn intern_edge(&self, edge: &str) -> PathfinderResult<(u32, u32)> {
+        // split at the first hyphen only as IDs of adjacent entities may contain hyphens
+        let (prop, adjacent_entity) = edge.split_once('-').ok_or_else(|| {
+            PathfinderError::DataConsistency(format!("Edge {edge} lacks a property."))
+        })?;
+
+        Ok((
+            self.interner.intern(prop)?,
+            self.interner.intern(adjacent_entity)?,
+        ))
+    }
+
+    // Writes adjacency lists of numeric IDs and updates the incoming list of each added or removed adjacent entity.
+    fn write_adjacency_ids(
+        &self,
+        id_lists: Vec<(String, AdjacencyIds)>,
+        merge: bool,
+    ) -> PathfinderResult<()> {
+        let mut batch = Batch::default();
+        // the added and removed (property, entity) pairs pointing to an adjacent entity
+        let mut incoming_changes: HashMap<u32, (AdjacencyIds, AdjacencyIds)> = HashMap::new();
+
+        for (some_entity, mut pairs) in id_lists {
+            let entity_id = self.interner.intern(&some_entity)?;
+            let stored = self.read_adjacency_ids(&some_entity)?.unwrap_or_default();
+
+            if merge {
+                pairs.extend(stored.iter().copied());
+            }
+
+            batch.insert(some_entity.as_str(), adjacency_encoding::encode(&mut pairs));
+
+            let stored: HashSet<(u32, u32)> = stored.into_iter().collect();
+            let written: HashSet<(u32, u32)> = pairs.into_iter().collect();
+
+            for &(prop, adjacent_entity) in written.difference(&stored) {
+                incoming_changes
+                    .entry(adjacent_entity)
+                    .or_default()
+                    .0
+                    .push((prop, entity_id));
+            }
+            for &(prop, adjacent_entity) in stored.difference(&written) {
+                incoming_changes
+                    .entry(adjacent_entity)
+                    .or_default()
+                    .1
+                    .push((prop, entity_id));
+            }
+        }
+
+        let mut incoming_batch = Batch::default();
+
+        for (adjacent_entity, (added, removed)) in incoming_changes {
+            let key = self.interner.name(adjacent_entity)?;
+            let mut pairs = self.read_incoming_ids(&key)?;
+            pairs.retain(|pair| !removed.contains(pair));
+            pairs.extend(added);
+
+            // entities without incoming edges have no incoming list, as after a rebuild
+            if pairs.is_empty() {
+                incoming_batch.remove(key.as_str());
+            } else {
+                incoming_batch.insert(key.as_str(), adjacency_encoding::encode(&mut pairs));
+            }
+        }
+
+        // the lists and the incoming lists pointing back to them are written together, so they never diverge
+        self.adjacency_list.apply_batches(vec![
+            (&self.adjacency_list, batch),
+            (&self.incoming_lists, incoming_batch),
+        ])?;
+
+        Ok(())
+    }
+
+    // Reads the (property, entity) pairs pointing to an entity from the incoming list.
+    // Incoming lists are always written in the compact format.
+    fn read_incoming_ids(&self, entity: &str) -> PathfinderResult<AdjacencyIds> {
+        let Some(bytes) = self.incoming_lists.get(entity)? else {
+            return Ok(vec![]);
+        };
+
+        adjacency_encoding::decode(&bytes)?.ok_or_else(|| {
+            PathfinderError::DataConsistency(format!(
+                "Incoming list of {entity} is not in the compact format."
+            ))
+        })
+    }
+
+    // Rebuilds the incoming list from all stored adjacency lists, e.g., for stores created before it existed.
+    // Returns the number of entities with incoming edges.
+    pub fn rebuild_incoming_list(&self) -> PathfinderResult<usize> {
+        let mut incoming_lists: HashMap<u32, AdjacencyIds> = HashMap::new();
+
+        // the entities of the incoming lists are interned, unlike by read_all_adjacency_ids
+        self.for_each_adjacency_ids(|some_entity, its_adjacent_entities| {
+            for (prop, adjacent_entity) in its_adjacent_entities {
+                incoming_lists
+                    .entry(adjacent_entity)
+                    .or_default()
+                    .push((prop, some_entity));
+            }
+            Ok(())
+        })?;
+
+        self.incoming_lists.clear()?;
+
+        let mut batch = Batch::default();
+        let entity_count = incoming_lists.len();
+
+        for (adjacent_entity, mut pairs) in incoming_lists {
+            batch.insert(
+                self.interner.name(adjacent_entity)?.as_str(),
+                adjacency_encoding::encode(&mut pairs),
+            );
+        }
+
+        self.incoming_lists.apply_batch(batch)?;
+
+        Ok(entity_count)
+    }
+
+    // Reads the stored adjacency list of an entity as pairs of numeric property and entity IDs.
+    // Lists in the legacy format are decoded as well, which interns their entities.
+    fn read_adjacency_ids(&self, entity: &str) -> PathfinderResult<Option<AdjacencyIds>> {
+        match self.adjacency_list.get(entity)? {
+            Some(bytes) => Ok(Some(self.decode_adjacency_ids(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_adjacency_ids(&self, bytes: &[u8]) -> PathfinderResult<AdjacencyIds> {
+        if let Some(pairs) = adjacency_encoding::decode(bytes)? {
+            return Ok(pairs);
+        }
+
+        let legacy: Vec<String> = bincode::deserialize(bytes)?;
+        legacy.iter().map(|edge| self.intern_edge(edge)).collect()
+    }
+
+    // Reads the stored adjacency list of an entity without fetching it from the Wikidata API.
+    pub fn read_adjacency_list(&self, entity: &str) -> PathfinderResult<Option<Vec<String>>> {
+        let Some(pairs) = self.read_adjacency_ids(entity)? else {
+            return Ok(None);
+        };
+
+        pairs
+            .into_iter()
+            .map(|(prop, adjacent_entity)| {
+                Ok(format!(
+                    "{}-{}",
+                    self.interner.name(prop)?,
+                    self.interner.name(adjacent_entity)?
+                ))
+            })
+            .collect::<PathfinderResult<Vec<String>>>()
+            .map(Some)
+    }
+
+    // Reads the stored adjacency list of an entity as pairs of property and adjacent entity.
+    fn read_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        self.read_adjacency_ids(entity)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(prop, adjacent_entity)| {
+                Ok((
+                    self.interner.name(prop)?,
+                    self.interner.name(adjacent_entity)?,
+                ))
+            })
+            .collect()
+    }
+
+    // Removes all edges from the adjacency lists of the specified entities for which the predicate returns false.
+    // The predicate receives the entity, the property, and the adjacent entity.
+    // Returns the number of removed edges.
+    pub fn retain_adjacent_entities<'e>(
+        &self,
+        entities: impl IntoIterator<Item = &'e String>,
+        keep: impl Fn(&str, &str, &str) -> PathfinderResult<bool>,
+    ) -> PathfinderResult<usize> {
+        let mut removed_count = 0;
+        let mut retained_lists: Vec<(String, AdjacencyIds)> = vec![];
+
+        for some_entity in entities {
+            let Some(stored_pairs) = self.read_adjacency_ids(some_entity)? else {
+                continue;
+            };
+            let stored_count = stored_pairs.len();
+
+            let mut pairs = vec![];
+            for (prop, adjacent_entity) in stored_pairs {
+                if keep(
+                    some_entity,
+                    &self.interner.name(prop)?,
+                    &self.interner.name(adjacent_entity)?,
+                )? {
+                    pairs.push((prop, adjacent_entity));
+                }
+            }
+
+            if pairs.len() < stored_count {
+                removed_count += stored_count - pairs.len();
+                retained_lists.push((some_entity.to_owned(), pairs));
+            }
+        }
+
+        self.write_adjacency_ids(retained_lists, false)?;
+
+        Ok(removed_count)
+    }
+
+    // Calls the visitor with the numeric ID and the list of each stored adjacency list one at a time,
+    // e.g., to stream the lists to a snapshot of the graph.
+    pub fn for_each_adjacency_ids(
+        &self,
+        mut visit: impl FnMut(u32, AdjacencyIds) -> PathfinderResult<()>,
+    ) -> PathfinderResult<()> {
+        for entry in self.adjacency_list.iter() {
+            let (key, bytes) = entry?;
+            let some_entity = std::str::from_utf8(&key)?;
+            visit(
+                self.interner.intern(some_entity)?,
+                self.decode_adjacency_ids(&bytes)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Calls the visitor with the numeric ID and the incoming list of each entity with incoming edges one at a time.
+    pub fn for_each_incoming_ids(
+        &self,
+        mut visit: impl FnMut(u32, AdjacencyIds) -> PathfinderResult<()>,
+    ) -> PathfinderResult<()> {
+        for entry in self.incoming_lists.iter() {
+            let (key, _) = entry?;
+            let some_entity = std::str::from_utf8(&key)?;
+            visit(
+                self.interner.intern(some_entity)?,
+                self.read_incoming_ids(some_entity)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Returns all interned entity and property IDs ordered by their numeric IDs.
+    pub fn interned_names(&self) -> Vec<String> {
+        self.interner.names()
+    }
+
+    // Reads all stored adjacency lists as numeric IDs, e.g., to calculate statistics of the graph.
+    // Returns the numeric ID of each entity with its list and the entity IDs ordered by their numeric IDs.
+    // Nothing is interned: entities that are not interned, as no edge points to them, get IDs following the interned ones.
+    pub fn read_all_adjacency_ids(&self) -> PathfinderResult<AllAdjacencyIds> {
+        let mut names = self.interner.names();
+        let mut adjacency_lists = vec![];
+        for entry in self.adjacency_list.iter() {
+            let (key, bytes) = entry?;
+            let some_entity = std::str::from_utf8(&key)?;
+            let id = self.interner.id(some_entity).unwrap_or_else(|| {
+                names.push(some_entity.to_owned());
+                names.len() as u32 - 1
+            });
+            adjacency_lists.push((id, self.decode_adjacency_ids(&bytes)?));
+        }
+
+        Ok((adjacency_lists, names))
+    }
+
+    // Reads when and from which source the adjacency list of an entity was stored.
+    pub fn read_fetch_record(&self, entity: &str) -> PathfinderResult<Option<FetchRecord>> {
+        match self.fetch_records.get(entity)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Returns the entities whose adjacency lists were stored more than the maximum age in seconds ago.
+    // Lists stored before fetches were recorded are of unknown age and thus stale.
+    pub fn stale_entities(&self, max_age: u64) -> PathfinderResult<Vec<String>> {
+        let mut stale_entities = vec![];
+        for key in self.adjacency_list.iter().keys() {
+            let entity = String::from(std::str::from_utf8(&key?)?);
+            if self
+                .read_fetch_record(&entity)?
+                .is_none_or(|record| record.age() > max_age)
+            {
+                stale_entities.push(entity);
+            }
+        }
+
+        Ok(stale_entities)
+    }
+
+    // Fetches the adjacency list of an entity again from the Wikidata API and replaces the stored one.
+    // The labels and descriptions of the entity and its adjacent entities are updated as well.
+    // Returns None if the data could not be fetched.
+    pub fn refresh_adjacency_list(
+        &self,
+        entity: &str,
+    ) -> PathfinderResult<Option<AdjacencyChange>> {
+        let previous_fetch = self.read_fetch_record(entity)?;
+        let stored: HashSet<String> = self
+            .read_adjacency_list(entity)?
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+
+        let (q_label_data, q_desc_data, p_label_data, p_desc_data, mut adjacent_entities_data) =
+            match self.api_connector.fetch_adjacent_entity_data(entity) {
+                Ok(data) => data,
+                Err(error) => {
+                    warn!(
+                        "Adjacent entities of {} could not be fetched: {}",
+                        entity, error
+                    );
+                    self.adjacency_failure_count
+                        .set(self.adjacency_failure_count.get() + 1);
+                    return Ok(None);
+                }
+            };
+
+        // entities without adjacent entities are not part of the response
+        let fetched: HashSet<String> = adjacent_entities_data
+            .remove(entity)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+
+        self.store_adjacency_lists(
+            vec![(entity.to_owned(), fetched.iter().cloned().collect())],
+            false,
+            SOURCE_WIKIDATA_API,
+        )?;
+
+        self.store_language_values(
+            &self.label_mapping,
+            string_pairs(q_label_data.iter().chain(p_label_data.iter())),
+            DEFAULT_LANGUAGE,
+        )?;
+        self.store_language_values(
+            &self.desc_mapping,
+            string_pairs(q_desc_data.iter().chain(p_desc_data.iter())),
+            DEFAULT_LANGUAGE,
+        )?;
+
+        compare_adjacency_lists(entity, previous_fetch, &stored, &fetched).map(Some)
+    }
+
+    // Converts all adjacency lists in the legacy format, i.e., bincode-serialized strings, to the compact format.
+    // Returns the number of converted lists.
+    pub fn migrate_adjacency_lists(&self) -> PathfinderResult<usize> {
+        let mut migrated_count = 0;
+
+        for entry in self.adjacency_list.iter() {
+            let (key, bytes) = entry?;
+
+            if !adjacency_encoding::is_compact(&bytes) {
+                let mut pairs = self.decode_adjacency_ids(&bytes)?;
+                self.adjacency_list
+                    .insert(key, adjacency_encoding::encode(&mut pairs))?;
+                migrated_count += 1;
+            }
+        }
+
+        Ok(migrated_count)
+    }
+
+    // Fetches labels and descriptions in all languages of the fallback chain that are not stored yet.
+    fn fetch_missing_languages(&self, entities: &[String]) -> PathfinderResult<()> {
+        let mut missing_entities: Vec<String> = vec![];
+        for entity in entities {
+            for language in &self.languages {
+                if !contains_language_value(&self.label_mapping, entity, language)?
+                    || !contains_language_value(&self.desc_mapping, entity, language)?
+                {
+                    missing_entities.push(entity.to_owned());
+                    break;
+                }
+            }
+        }
+
+        if !missing_entities.is_empty() {
+            self.fetch_labels_descriptions(&missing_entities)?;
+        }
+
+        Ok(())
+    }
+
+    // Fetches and stores labels and descriptions in all languages of the fallback chain.
+    // Languages without a label or description are stored as empty values to prevent repeated requests.
+    // If the request fails, the missing languages are marked likewise, as the redirect mapping does for entities
+    // that are not redirected; labels and descriptions fetched along with adjacent entities replace the markers.
+    fn fetch_labels_descriptions(&self, entities: &[String]) -> PathfinderResult<()> {
+        let (labels, descriptions) = match self
+            .api_connector
+            .fetch_labels_descriptions(entities, &self.languages)
+        {
+            Ok(values) => values,
+            Err(error) => {
+                warn!(
+                    "Labels and descriptions of {} entities could not be fetched: {}",
+                    entities.len(),
+                    error
+                );
+                return self.mark_missing_languages(entities);
+            }
+        };
+
+        for (mapping, fetched_values) in [
+            (&self.label_mapping, labels),
+            (&self.desc_mapping, descriptions),
+        ] {
+            let mut batch = Batch::default();
+
+            for entity in entities {
+                for language in &self.languages {
+                    let value = fetched_values
+                        .get(entity)
+                        .and_then(|values| values.get(language))
+                        .map_or("", String::as_str);
+                    batch.insert(language_key(entity, language).as_str(), value);
+                }
+            }
+
+            mapping.apply_batch(batch)?;
+        }
+
+        Ok(())
+    }
+
+    // For making fallback request if label or description was not be retrieved before.
+    // Label and description of an entity should already have been retrieved when fetching the adjacent entities of the entity pointing to this entity.
+    fn fallback_get_label_description(&self, entity: &str) -> PathfinderResult<()> {
+        warn!("Fallback request for label of {} triggered.", entity);
+        self.fetch_labels_descriptions(&[entity.to_owned()])
+    }
+}
+
+impl GraphStore for StoreConnector<'_> {
+    // Returns the number of requests for adjacent entities that failed since this instance was created.
+    fn adjacency_failure_count(&self) -> usize {
+        self.adjacency_failure_count.get()
+    }
+
+    // Returns the number of lookups that could not be answered in offline mode since this instance was created.
+    fn cache_misses(&self) -> CacheMisses {
+        self.cache_misses.get()
+    }
+
+    // Resolves an entity reference to the entity that should be used for the search.
+    // References like wd:Q42 are normalised and redirects of merged entities are followed.
+    // Redirects are only looked up for entities without adjacent entities as these are the ones affected.
+    fn normalize_entity(&self, entity: &str) -> String {
+        normalize_entity_id(entity)
+    }
+
+    fn resolve_entity(&self, entity: &str) -> PathfinderResult<String> {
+        let mut resolved_entity = self.normalize_entity(entity);
+
+        // follow chains of redirects, but guard against cycles
+        for _ in 0..MAX_REDIRECT_HOPS {
+            if !self.get_adjacent_entities(&resolved_entity)?.is_empty() {
+                break;
+            }
+
+            match self.get_redirect(&resolved_entity)? {
+                Some(target) if target != resolved_entity => {
+                    info!("{} redirects to {}.", resolved_entity, target);
+                    resolved_entity = target;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(resolved_entity)
+    }
+
+    // Fetches the entities adjacent to the specified entity.
+    // If the entity has not been seen before, its data and the data of the adjacent entities is fetched.
+    fn get_adjacent_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        // literal hubs are virtual nodes without outgoing edges
+        if literal_hub::is_literal_hub(entity) {
+            return Ok(vec![]);
+        }
+
+        // items, properties, lexemes, forms, and senses can be looked up via the Wikidata API
+        if entity_kind(entity).is_none() {
+            warn!("{} is not a valid Wikibase entity ID.", entity);
+            return Ok(vec![]);
+        }
+
+        // in offline mode, cached adjacent entities are used even if labels or descriptions are missing
+        if self.offline && !self.adjacency_list.contains_key(entity)? {
+            self.record_miss(|m| m.adjacent_entities += 1);
+            return Ok(vec![]);
+        }
+
+        if !self.offline
+            && (!contains_language_value(&self.label_mapping, entity, DEFAULT_LANGUAGE)?
+                || !contains_language_value(&self.desc_mapping, entity, DEFAULT_LANGUAGE)?
+                || !self.adjacency_list.contains_key(entity)?)
+        {
+            let (q_label_data, q_desc_data, p_label_data, p_desc_data, adjacent_entities_data) =
+                match self.api_connector.fetch_adjacent_entity_data(entity) {
+                    Ok(data) => data,
+                    Err(error) => {
+                        // nothing is stored such that the request is repeated next time
+                        warn!(
+                            "Adjacent entities of {} could not be fetched: {}",
+                            entity, error
+                        );
+                        self.adjacency_failure_count
+                            .set(self.adjacency_failure_count.get() + 1);
+                        return Ok(vec![]);
+                    }
+                };
+
+            // update the adjacency list for all retrieved entities
+            let mut adjacency_lists = Vec::new();
+            for (some_entity, its_adjacent_entities) in adjacent_entities_data {
+                if !self.adjacency_list.contains_key(&some_entity)? {
+                    adjacency_lists.push((some_entity, its_adjacent_entities));
+                }
+            }
+
+            self.store_adjacency_lists(adjacency_lists, false, SOURCE_WIKIDATA_API)?;
+
+            // update the label and desc mapping for all retrieved entities and properties
+            self.store_language_values(
+                &self.label_mapping,
+                string_pairs(q_label_data.iter().chain(p_label_data.iter())),
+                DEFAULT_LANGUAGE,
+            )?;
+            self.store_language_values(
+                &self.desc_mapping,
+                string_pairs(q_desc_data.iter().chain(p_desc_data.iter())),
+                DEFAULT_LANGUAGE,
+            )?;
+
+            // labels and descriptions in other languages of the fallback chain are fetched in bulk
+            let fetched_entities: Vec<String> = q_label_data
+                .keys()
+                .chain(p_label_data.keys())
+                .cloned()
+                .collect();
+            self.fetch_missing_languages(&fetched_entities)?;
+        }
+
+        // read from store
+        let mut adjacent_entities = self.read_adjacent_entities(entity)?;
+
+        if let Some(literal_hubs) = &self.literal_hubs {
+            adjacent_entities.extend(self.get_adjacent_literal_hubs(entity, literal_hubs)?);
+        }
+
+        debug!(
+            "get_adjacent_entities received entity {} and returned {} entities.",
+            entity,
+            adjacent_entities.len()
+        );
+
+        Ok(adjacent_entities)
+    }
+
+    // Incoming edges are only read from the local stores, so an edge is only known if the adjacency list of
+    // the pointing entity was stored before.
+    fn get_incoming_entities(&self, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+        self.read_incoming_ids(entity)?
+            .into_iter()
+            .map(|(prop, some_entity)| {
+                Ok((self.interner.name(prop)?, self.interner.name(some_entity)?))
+            })
+            .collect()
+    }
+
+    // Returns the label of an entity in the first language of the fallback chain it is available in.
+    // The language is empty if no label is available or if the entity is a literal hub.
+    fn get_label_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        if literal_hub::is_literal_hub(entity) {
+            return Ok((literal_hub::label(entity), String::new()));
+        }
+
+        self.get_language_value(&self.label_mapping, entity, |m| m.labels += 1)
+    }
+
+    // Returns the description of an entity in the first language of the fallback chain it is available in.
+    // The language is empty if no description is available or if the entity is a literal hub.
+    fn get_description_with_language(&self, entity: &str) -> PathfinderResult<(String, String)> {
+        if literal_hub::is_literal_hub(entity) {
+            return Ok((literal_hub::description(entity), String::new()));
+        }
+
+        self.get_language_value(&self.desc_mapping, entity, |m| m.descriptions += 1)
+    }
+
+    // Returns the first language of the fallback chain.
+    fn preferred_language(&self) -> &str {
+        &self.languages[0]
+    }
+
+    // semantic distance is the cosine distance between the embedding vectors of both entities
+    // distances are symmetric, so they are stored once per model for the ordered pair of entity IDs
+    fn get_semantic_distance(&self, entity_a: &str, entity_b: &str) -> PathfinderResult<f64> {
+        let key = distance_key(self.embedding_store.model(), entity_a, entity_b);
+
+        let distance = match self.distances.get(&key)? {
+            Some(value) => {
+                let bytes = value.as_ref().try_into().map_err(|_| {
+                    PathfinderError::DataConsistency(format!(
+                        "The semantic distance between {entity_a} and {entity_b} has {} instead of 8 bytes.",
+                        value.len()
+                    ))
+                })?;
+                f64::from_le_bytes(bytes)
+            }
+            None => {
+                let distance = self.calculate_semantic_distance(entity_a, entity_b)?;
+                if let Some(distance) = distance {
+                    self.distances.insert(key, &distance.to_le_bytes())?;
+                }
+                distance.unwrap_or(UNKNOWN_DISTANCE)
+            }
+        };
+
+        debug!(
+            "Semantic distance between {} and {}: {}",
+            entity_a, entity_b, distance
+        );
+
+        Ok(distance)
+    }
+
+    // in offline mode, only stored embedding vectors and local embedding providers are available
+    // entities whose distance to the target is stored already are not embedded
+    fn prefetch_embeddings(&self, entities: &[String], target: &str) -> PathfinderResult<()> {
+        if self.offline && !self.embedding_provider.is_local() {
+            return Ok(());
+        }
+
+        let mut missing_entities: Vec<&str> = vec![];
+        for entity in entities {
+            let key = distance_key(self.embedding_store.model(), entity, target);
+            if !self.distances.tree().contains_key(&key)?
+                && self.read_legacy_distance(entity, target)?.is_none()
+            {
+                missing_entities.push(entity);
+            }
+        }
+
+        if !missing_entities.is_empty() {
+            missing_entities.push(target);
+            self.embed_entities(&missing_entities)?;
+        }
+
+        Ok(())
+    }
+
+    // the frequencies are calculated from the local frequency table, so they are also known in offline mode
+    fn get_prop_frequency(&self, props: &[String]) -> f64 {
+        self.prop_frequencies.get(props)
+    }
+}
+
+// Converts label or description mappings returned by the Wikidata API to string pairs.
+fn string_pairs<'b>(
+    values: impl Iterator<Item = (&'b String, &'b String)>,
+) -> impl Iterator<Item = (&'b str, &'b str)> {
+    values.map(|(e, l)| (e.as_str(), l.as_str()))
+}
+
+// Compares a refreshed adjacency list with the replaced one, whose edges are of this form: some_property-adjacent_entity
+// Lists of other sources or of unknown source are replaced without reporting their differences as changes.
+fn compare_adjacency_lists(
+    entity: &str,
+    previous_fetch: Option<FetchRecord>,
+    stored: &HashSet<String>,
+    fetched: &HashSet<String>,
+) -> PathfinderResult<AdjacencyChange> {
+    let split_edges = |edges: HashSet<&String>| -> PathfinderResult<Vec<(String, String)>> {
+        let mut split_edges = vec![];
+        for edge in edges {
+            let (prop, adjacent_entity) = edge.split_once('-').ok_or_else(|| {
+                PathfinderError::Decode(format!("The edge {edge} of {entity} has no property."))
+            })?;
+            split_edges.push((prop.to_owned(), adjacent_entity.to_owned()));
+        }
+        split_edges.sort();
+        Ok(split_edges)
+    };
+
+    let compared = previous_fetch
+        .as_ref()
+        .is_some_and(|record| record.source == SOURCE_WIKIDATA_API);
+    let (added, removed) = if compared {
+        (
+            split_edges(fetched.difference(stored).collect())?,
+            split_edges(stored.difference(fetched).collect())?,
+        )
+    } else {
+        (vec![], vec![])
+    };
+
+    Ok(AdjacencyChange {
+        entity: entity.to_owned(),
+        added,
+        removed,
+        previous_fetch,
+        edge_count: fetched.len(),
+        compared,
+    })
+}
+
+// Returns the current time in seconds since the Unix epoch.
+fn unix_time() -> u64 {
+    // a clock set before the epoch is treated as the epoch, which makes all records appear fresh
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// Describes the filters applied to adjacent entities by the Wikidata API and, likewise, to imported edges.
+fn adjacency_filters() -> String {
+    format!(
+        "direct properties between entities, each with a label and a description in {DEFAULT_LANGUAGE}"
+    )
+}
+
+// Creates the key of a semantic distance, which is the same for both orders of the entities.
+fn distance_key(model: &str, entity_a: &str, entity_b: &str) -> String {
+    let (first, second) = if entity_a <= entity_b {
+        (entity_a, entity_b)
+    } else {
+        (entity_b, entity_a)
+    };
+
+    format!("{model}\0{first}\0{second}")
+}
+
+// Creates the key of a label or description in a specific language.
+fn language_key(entity: &str, language: &str) -> String {
+    format!("{entity}@{language}")
+}
+
+// Reads a label or description in a specific language.
+// Stores created before labels and descriptions were language-tagged contain English values keyed by the plain entity ID.
+fn read_language_value(
+    mapping: &Db,
+    entity: &str,
+    language: &str,
+) -> PathfinderResult<Option<String>> {
+    let value = match mapping.get(language_key(entity, language))? {
+        Some(value) => Some(value),
+        None if language == DEFAULT_LANGUAGE => mapping.get(entity)?,
+        None => None,
+    };
+
+    Ok(match value {
+        Some(v) => Some(String::from(std::str::from_utf8(&v)?)),
+        None => None,
+    })
+}
+
+// Removes all entries of a tree whose keys the predicate returns false for and returns their number.
+fn remove_keys(tree: &Tree, keep: impl Fn(&str) -> bool) -> PathfinderResult<usize> {
+    let mut batch = Batch::default();
+    let mut removed_count = 0;
+
+    for key in tree.iter().keys() {
+        let key = key?;
+        if !keep(std::str::from_utf8(&key)?) {
+            batch.remove(key);
+            removed_count += 1;
+        }
+    }
+
+    tree.apply_batch(batch)?;
+    Ok(removed_count)
+}
+
+// Reads a label or description in all stored languages as pairs of language and value.
+// The value keyed by the plain entity ID in stores created before language tags is returned as English value.
+fn read_all_language_values(mapping: &Db, entity: &str) -> PathfinderResult<Vec<(String, String)>> {
+    let mut values: Vec<(String, String)> = vec![];
+    for entry in mapping.scan_prefix(format!("{entity}@")) {
+        let (key, value) = entry?;
+        let key = std::str::from_utf8(&key)?;
+        values.push((
+            key[entity.len() + 1..].to_owned(),
+            String::from(std::str::from_utf8(&value)?),
+        ));
+    }
+
+    if !values
+        .iter()
+        .any(|(language, _)| language == DEFAULT_LANGUAGE)
+    {
+        if let Some(value) = mapping.get(entity)? {
+            values.push((
+                DEFAULT_LANGUAGE.to_owned(),
+                String::from(std::str::from_utf8(&value)?),
+            ));
+        }
+    }
+
+    Ok(values)
+}
+
+fn contains_language_value(mapping: &Db, entity: &str, language: &str) -> PathfinderResult<bool> {
+    Ok(mapping.contains_key(language_key(entity, language))?
+        || (language == DEFAULT_LANGUAGE && mapping.contains_key(entity)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::costs_calculator::Heuristic;
+    use crate::embedding_provider::WembedProvider;
+    use crate::pathfinder::Pathfinder;
+    use crate::prop_frequencies::PropFrequencyAggregation;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    // The stores of a test in their own directory, which is removed at the end of the test.
+    struct TestStores(PathBuf);
+
+    impl TestStores {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("{}_store_connector_{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            Self(path)
+        }
+
+        fn path(&self, store_name: &str) -> String {
+            self.0.join(store_name).to_str().unwrap().to_owned()
+        }
+
+        fn open<'a>(
+            &self,
+            api_connector: &'a ApiConnector,
+            embedding_provider: Box<dyn EmbeddingProvider + 'a>,
+            offline: bool,
+            read_only: bool,
+        ) -> PathfinderResult<StoreConnector<'a>> {
+            StoreConnector::new(
+                api_connector,
+                embedding_provider,
+                StoreConfig {
+                    label_mapping_path: self.path("label_mapping"),
+                    desc_mapping_path: self.path("desc_mapping"),
+                    distance_mapping_path: self.path("distance_mapping"),
+                    embedding_store_path: self.path("embedding_store"),
+                    adjacency_list_path: self.path("adjacency_list"),
+                    literal_list_path: self.path("literal_list"),
+                    redirect_mapping_path: self.path("redirect_mapping"),
+                    statement_list_path: self.path("statement_list"),
+                    sitelink_mapping_path: self.path("sitelink_mapping"),
+                    kv_backend: KvBackend::Sled,
+                    prop_frequencies: PropFrequencies::from_counts(
+                        HashMap::new(),
+                        PropFrequencyAggregation::from_config("average"),
+                    ),
+                    distance_eviction: None,
+                    embedding_eviction: None,
+                    literal_hubs: None,
+                    languages: vec![DEFAULT_LANGUAGE.to_owned()],
+                    offline,
+                    read_only,
+                },
+            )
+        }
+    }
+
+    impl Drop for TestStores {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // The APIs are never reached, as the tests only use stored data.
+    fn unreachable_api() -> ApiConnector {
+        ApiConnector::new(
+            "http://127.0.0.1:9".to_owned(),
+            "http://127.0.0.1:9".to_owned(),
+            Duration::from_secs(1),
+        )
+        .unwrap()
+    }
+
+    // Stores an adjacency list along with the labels and descriptions of the entity and its adjacent entities.
+    fn store_entity(store_connector: &StoreConnector, entity: &str, edges: &[(&str, &str)]) {
+        let adjacency_list = edges
+            .iter()
+            .map(|(prop, adjacent_entity)| format!("{prop}-{adjacent_entity}"))
+            .collect();
+        store_connector
+            .store_adjacency_lists(
+                vec![(entity.to_owned(), adjacency_list)],
+                false,
+                SOURCE_WIKIDATA_API,
+            )
+            .unwrap();
+
+        let entities = std::iter::once(entity).chain(
+            edges
+                .iter()
+                .flat_map(|(prop, adjacent_entity)| [*prop, *adjacent_entity]),
+        );
+        for entity in entities {
+            store_connector
+                .store_labels([(entity, entity)].into_iter(), DEFAULT_LANGUAGE)
+                .unwrap();
+            store_connector
+                .store_descriptions([(entity, "an entity")].into_iter(), DEFAULT_LANGUAGE)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn offline_searches_report_cache_misses() {
+        let stores = TestStores::new("offline");
+        let api_connector = unreachable_api();
+        let provider = Box::new(WembedProvider::new(&api_connector, "model".to_owned()));
+        let store_connector = stores.open(&api_connector, provider, true, false).unwrap();
+        store_entity(&store_connector, "Q1", &[("P31", "Q2")]);
+
+        let pathfinder = Pathfinder::new(&store_connector, 100, false, Heuristic::Text);
+        let result = pathfinder
+            .find_path("Q1", "Q3", &(1.0, 0.0, 1.0), false)
+            .unwrap();
+
+        // the adjacency lists of Q3 while resolving and expanding it and of Q2 are missing, as are the redirect of Q3
+        // and the distances of Q1 and Q2 to the target, whereas all labels and descriptions are stored
+        assert!(result.path_forwards.is_empty());
+        assert_eq!(
+            result.cache_misses,
+            CacheMisses {
+                adjacent_entities: 3,
+                distances: 2,
+                redirects: 1,
+                ..CacheMisses::default()
+            }
+        );
+        assert_eq!(result.cache_misses, store_connector.cache_misses());
+    }
+
+    // Reads all incoming lists with their pairs in a canonical order.
+    fn incoming_lists(store_connector: &StoreConnector) -> Vec<(u32, AdjacencyIds)> {
+        let mut incoming_lists = vec![];
+        store_connector
+            .for_each_incoming_ids(|entity, mut pairs| {
+                pairs.sort();
+                incoming_lists.push((entity, pairs));
+                Ok(())
+            })
+            .unwrap();
+        incoming_lists.sort();
+        incoming_lists
+    }
+
+    #[test]
+    fn incoming_list_updates_match_a_rebuild() {
+        let stores = TestStores::new("incoming");
+        let api_connector = unreachable_api();
+        let provider = Box::new(WembedProvider::new(&api_connector, "model".to_owned()));
+        let store_connector = stores.open(&api_connector, provider, true, false).unwrap();
+
+        let adjacency_list = |edges: &[&str]| edges.iter().map(|e| e.to_string()).collect();
+        for (adjacency_lists, merge) in [
+            (
+                vec![
+                    ("Q1", adjacency_list(&["P31-Q5", "P27-Q2"])),
+                    ("Q2", adjacency_list(&["P31-Q5"])),
+                ],
+                false,
+            ),
+            // replaces the list of Q1 and adds the list of Q3
+            (
+                vec![
+                    ("Q1", adjacency_list(&["P31-Q5", "P17-Q3"])),
+                    ("Q3", adjacency_list(&["P17-Q2"])),
+                ],
+                false,
+            ),
+            // merges an edge that is stored already and a new one
+            (vec![("Q2", adjacency_list(&["P31-Q5", "P17-Q3"]))], true),
+        ] {
+            store_connector
+                .store_adjacency_lists(
+                    adjacency_lists
+                        .into_iter()
+                        .map(|(entity, list)| (entity.to_owned(), list))
+                        .collect(),
+                    merge,
+                    SOURCE_WIKIDATA_API,
+                )
+                .unwrap();
+        }
+
+        let removed_count = store_connector
+            .retain_adjacent_entities(
+                &["Q1".to_owned(), "Q2".to_owned()],
+                |_, _, adjacent_entity| Ok(adjacent_entity != "Q5"),
+            )
+            .unwrap();
+        assert_eq!(removed_count, 2);
+
+        let updated = incoming_lists(&store_connector);
+        assert_eq!(store_connector.rebuild_incoming_list().unwrap(), 2);
+        assert_eq!(updated, incoming_lists(&store_connector));
+
+        let incoming_entities = |entity| {
+            let mut pairs = store_connector.get_incoming_entities(entity).unwrap();
+            pairs.sort();
+            pairs
+        };
+        assert!(incoming_entities("Q5").is_empty());
+        assert_eq!(
+            incoming_entities("Q3"),
+            [
+                ("P17".to_owned(), "Q1".to_owned()),
+                ("P17".to_owned(), "Q2".to_owned())
+            ]
+        );
+    }
+
+    // Writes a store of schema version 1, i.e., adjacency lists as bincode-serialized strings without metadata.
+    fn write_version_1_store(stores: &TestStores) {
+        let adjacency_list =
+            kv_store::open(&stores.path("adjacency_list"), KvBackend::Sled).unwrap();
+        for (entity, edges) in [("Q1", vec!["P31-Q5", "P27-Q2"]), ("Q2", vec!["P31-Q5"])] {
+            adjacency_list
+                .insert(entity, bincode::serialize(&edges).unwrap())
+                .unwrap();
+        }
+        adjacency_list.flush().unwrap();
+    }
+
+    #[test]
+    fn version_1_stores_are_migrated() {
+        let stores = TestStores::new("migration");
+        write_version_1_store(&stores);
+        let api_connector = unreachable_api();
+        let provider = Box::new(WembedProvider::new(&api_connector, "model".to_owned()));
+        let store_connector = stores.open(&api_connector, provider, true, false).unwrap();
+
+        for entity in ["Q1", "Q2"] {
+            let bytes = store_connector.adjacency_list.get(entity).unwrap().unwrap();
+            assert!(adjacency_encoding::is_compact(&bytes));
+        }
+        assert_eq!(
+            store_connector.read_adjacency_list("Q1").unwrap().unwrap(),
+            ["P31-Q5", "P27-Q2"]
+        );
+
+        let mut incoming_entities = store_connector.get_incoming_entities("Q5").unwrap();
+        incoming_entities.sort();
+        assert_eq!(
+            incoming_entities,
+            [
+                ("P31".to_owned(), "Q1".to_owned()),
+                ("P31".to_owned(), "Q2".to_owned())
+            ]
+        );
+        assert_eq!(
+            store_connector.get_incoming_entities("Q2").unwrap(),
+            [("P27".to_owned(), "Q1".to_owned())]
+        );
+
+        for store in store_connector.stores() {
+            assert_eq!(
+                store_schema::read_version(store).unwrap(),
+                Some(SCHEMA_VERSION)
+            );
+        }
+    }
+
+    #[test]
+    fn stores_of_newer_versions_are_refused() {
+        let stores = TestStores::new("newer_version");
+        let api_connector = unreachable_api();
+        let provider = Box::new(WembedProvider::new(&api_connector, "model".to_owned()));
+        let store_connector = stores.open(&api_connector, provider, true, false).unwrap();
+        store_schema::write_version(&store_connector.label_mapping, SCHEMA_VERSION + 1).unwrap();
+        drop(store_connector);
+
+        let provider = Box::new(WembedProvider::new(&api_connector, "model".to_owned()));
+        match stores.open(&api_connector, provider, true, false) {
+            Err(PathfinderError::DataConsistency(message)) => {
+                assert!(message.contains(&format!("schema version {}", SCHEMA_VERSION + 1)))
+            }
+            _ => panic!("The stores of a newer schema version were opened."),
+        };
+    }
+
+    #[test]
+    fn older_stores_are_refused_read_only() {
+        let stores = TestStores::new("read_only");
+        write_version_1_store(&stores);
+        let api_connector = unreachable_api();
+
+        let provider = Box::new(WembedProvider::new(&api_connector, "model".to_owned()));
+        match stores.open(&api_connector, provider, true, true) {
+            Err(PathfinderError::DataConsistency(message)) => assert!(message.contains("migrate")),
+            _ => panic!("The stores of an older schema version were opened read-only."),
+        }
+
+        // the refused stores are left as they are
+        let adjacency_list =
+            kv_store::open(&stores.path("adjacency_list"), KvBackend::Sled).unwrap();
+        assert_eq!(
+            store_schema::read_version(&adjacency_list).unwrap(),
+            Some(1)
+        );
+        assert!(!adjacency_encoding::is_compact(
+            &adjacency_list.get("Q1").unwrap().unwrap()
+        ));
+    }
+
+    // A local embedding provider recording the entities of each batch.
+    // It fails for batches containing the failing entity and cannot embed the unembeddable entity.
+    struct RecordingProvider {
+        batches: Rc<RefCell<Vec<Vec<String>>>>,
+        failing_entity: &'static str,
+        unembeddable_entity: &'static str,
+    }
+
+    impl EmbeddingProvider for RecordingProvider {
+        fn model(&self) -> &str {
+            "model"
+        }
+
+        fn embed(&self, texts: &[(String, String)]) -> PathfinderResult<Vec<Option<Vec<f32>>>> {
+            let entities: Vec<String> = texts.iter().map(|(entity, _)| entity.to_owned()).collect();
+            self.batches.borrow_mut().push(entities.clone());
+
+            if entities.iter().any(|entity| entity == self.failing_entity) {
+                return Err(PathfinderError::Network("unavailable".to_owned()));
+            }
+
+            Ok(entities
+                .iter()
+                .map(|entity| (entity != self.unembeddable_entity).then(|| vec![1.0, 0.0]))
+                .collect())
+        }
+
+        fn is_local(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn embeddings_are_requested_in_batches() {
+        let stores = TestStores::new("batches");
+        let api_connector = unreachable_api();
+        let batches = Rc::new(RefCell::new(vec![]));
+        let provider = Box::new(RecordingProvider {
+            batches: batches.clone(),
+            failing_entity: "",
+            unembeddable_entity: "Q3",
+        });
+        let store_connector = stores.open(&api_connector, provider, true, false).unwrap();
+        store_connector
+            .store_semantic_distances(vec![(
+                "model".to_owned(),
+                "Q1".to_owned(),
+                "Q0".to_owned(),
+                0.5,
+            )])
+            .unwrap();
+
+        let entities: Vec<String> = (1..=EMBEDDING_BATCH_SIZE + 2)
+            .map(|i| format!("Q{i}"))
+            .collect();
+        store_connector
+            .prefetch_embeddings(&entities, "Q0")
+            .unwrap();
+
+        // the entity with a stored distance to the target is skipped, but the target is embedded
+        let batch_sizes: Vec<usize> = batches.borrow().iter().map(Vec::len).collect();
+        assert_eq!(batch_sizes, [EMBEDDING_BATCH_SIZE, 2]);
+        assert!(!batches.borrow().concat().contains(&"Q1".to_owned()));
+        // entities that cannot be embedded are stored with an empty vector and not requested again
+        assert_eq!(store_connector.embedding_count(), EMBEDDING_BATCH_SIZE + 1);
+        assert_eq!(
+            store_connector.embedding_store().get("Q3").unwrap(),
+            Some(vec![])
+        );
+        store_connector
+            .prefetch_embeddings(&entities, "Q0")
+            .unwrap();
+        assert_eq!(batches.borrow().len(), 2);
+    }
+
+    #[test]
+    fn failed_embeddings_are_not_requested_again() {
+        let stores = TestStores::new("failed_embeddings");
+        let api_connector = unreachable_api();
+        let batches = Rc::new(RefCell::new(vec![]));
+        let provider = Box::new(RecordingProvider {
+            batches: batches.clone(),
+            failing_entity: "Q2",
+            unembeddable_entity: "",
+        });
+        let store_connector = stores.open(&api_connector, provider, true, false).unwrap();
+
+        let entities = ["Q1".to_owned(), "Q2".to_owned()];
+        store_connector
+            .prefetch_embeddings(&entities, "Q0")
+            .unwrap();
+        assert_eq!(batches.borrow().len(), 1);
+        assert_eq!(store_connector.embedding_count(), 0);
+
+        // the distance is unknown without vectors and the failed entities are skipped for the rest of the run
+        assert_eq!(
+            store_connector.get_semantic_distance("Q1", "Q0").unwrap(),
+            UNKNOWN_DISTANCE
+        );
+        store_connector
+            .prefetch_embeddings(&entities, "Q0")
+            .unwrap();
+        store_connector
+            .prefetch_embeddings(&["Q4".to_owned()], "Q0")
+            .unwrap();
+        assert_eq!(
+            *batches.borrow(),
+            [
+                vec!["Q1".to_owned(), "Q2".to_owned(), "Q0".to_owned()],
+                vec!["Q4".to_owned()]
+            ]
+        );
+    }
+
+    #[test]
+    fn refreshed_lists_are_only_compared_with_lists_of_the_same_source() {
+        let edges =
+            |edges: &[&str]| -> HashSet<String> { edges.iter().map(|e| e.to_string()).collect() };
+        let (stored, fetched) = (edges(&["P31-Q5", "P27-Q2"]), edges(&["P31-Q5", "P27-Q3"]));
+        let record = |source: &str| {
+            Some(FetchRecord {
+                fetched_at: 0,
+                source: source.to_owned(),
+            })
+        };
+
+        let change =
+            compare_adjacency_lists("Q1", record(SOURCE_WIKIDATA_API), &stored, &fetched).unwrap();
+        assert!(change.compared);
+        assert_eq!(change.added, [("P27".to_owned(), "Q3".to_owned())]);
+        assert_eq!(change.removed, [("P27".to_owned(), "Q2".to_owned())]);
+        assert_eq!(change.edge_count, 2);
+
+        for previous_fetch in [record(SOURCE_JSON_DUMP), record(SOURCE_TRUTHY_DUMP), None] {
+            let change = compare_adjacency_lists("Q1", previous_fetch, &stored, &fetched).unwrap();
+            assert!(!change.compared);
+            assert!(change.added.is_empty() && change.removed.is_empty());
+            assert_eq!(change.edge_count, 2);
+        }
     }
 }
@@ -0,0 +1,548 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use itertools::Itertools;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::importer::{open_dump, BATCH_SIZE};
+use crate::store_connector::{FetchRecord, Literal, Statement, StoreConnector};
+use crate::store_schema::SCHEMA_VERSION;
+
+// The version of the export format, which is independent of the schema version of the sled stores
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+// The files of an export directory
+const MANIFEST_FILE: &str = "manifest.json";
+const ENTITIES_FILE: &str = "entities.jsonl.gz";
+const DISTANCES_FILE: &str = "distances.tsv.gz";
+const LEGACY_DISTANCES_FILE: &str = "legacy_distances.tsv.gz";
+const EMBEDDINGS_FILE: &str = "embeddings.tsv.gz";
+
+// The header lines of the TSV files
+const DISTANCES_HEADER: &str = "model\tentity_a\tentity_b\tdistance";
+const LEGACY_DISTANCES_HEADER: &str = "texts\tdistance";
+const EMBEDDINGS_HEADER: &str = "model\tentity\tvector";
+
+// Describes an export directory; the counts allow to check an import for completeness.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    schema_version: u32,
+    languages: Vec<String>,
+    created_by: String,
+    entities: usize,
+    distances: usize,
+    legacy_distances: usize,
+    embeddings: usize,
+}
+
+// All stored data of an entity or property, written as one line of the entities file.
+// Fields without stored data are omitted.
+#[derive(Serialize, Deserialize)]
+struct EntityRecord {
+    id: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    descriptions: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edges: Option<Vec<(String, String)>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fetched: Option<FetchRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    literals: Option<Vec<Literal>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    statements: Option<Vec<Statement>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sitelinks: Option<usize>,
+}
+
+/// Exports the sled stores to a directory of compressed, line-oriented files that do not depend on sled.
+/// The incoming list and the numeric IDs of the adjacency lists are not exported as they are derived data.
+/// # Arguments
+/// * `store_connector` - The StoreConnector whose stores are exported
+/// * `export_path` - The directory of the export, which is created if necessary
+/// # Returns
+/// * An error if a store cannot be read or a file of the export cannot be written
+pub fn export_stores(store_connector: &StoreConnector, export_path: &str) -> PathfinderResult<()> {
+    fs::create_dir_all(export_path).map_err(|error| {
+        PathfinderError::Store(format!(
+            "Export directory {export_path} could not be created: {error}"
+        ))
+    })?;
+    let export_path = Path::new(export_path);
+
+    info!("Exporting entities to {}.", export_path.display());
+    let mut writer = create_gz(&export_path.join(ENTITIES_FILE))?;
+    let entities = store_connector.stored_entities()?;
+    for entity in &entities {
+        let edges = match store_connector.read_adjacency_list(entity)? {
+            Some(edges) => Some(split_edges(entity, &edges)?),
+            None => None,
+        };
+        let record = EntityRecord {
+            id: entity.to_owned(),
+            labels: store_connector
+                .read_all_labels(entity)?
+                .into_iter()
+                .collect(),
+            descriptions: store_connector
+                .read_all_descriptions(entity)?
+                .into_iter()
+                .collect(),
+            edges,
+            fetched: store_connector.read_fetch_record(entity)?,
+            redirect: store_connector.read_stored_redirect(entity)?,
+            literals: store_connector.read_literals(entity)?,
+            statements: store_connector.read_statements(entity)?,
+            sitelinks: store_connector.read_sitelink_count(entity)?,
+        };
+        writeln!(writer, "{}", serde_json::to_string(&record)?)?;
+    }
+    writer.finish()?;
+
+    info!("Exporting semantic distances and embedding vectors.");
+    let distance_count = write_tsv(
+        &export_path.join(DISTANCES_FILE),
+        DISTANCES_HEADER,
+        store_connector.read_all_semantic_distances().map_ok(
+            |(model, entity_a, entity_b, distance)| {
+                vec![model, entity_a, entity_b, distance.to_string()]
+            },
+        ),
+    )?;
+    let legacy_distance_count = write_tsv(
+        &export_path.join(LEGACY_DISTANCES_FILE),
+        LEGACY_DISTANCES_HEADER,
+        store_connector
+            .read_legacy_semantic_distances()
+            .map_ok(|(texts, distance)| vec![texts, distance.to_string()]),
+    )?;
+    let embedding_count = write_tsv(
+        &export_path.join(EMBEDDINGS_FILE),
+        EMBEDDINGS_HEADER,
+        store_connector
+            .embedding_store()
+            .all_vectors()
+            .map_ok(|(model, entity, vector)| vec![model, entity, vector.iter().join(" ")]),
+    )?;
+
+    let manifest = Manifest {
+        format_version: EXPORT_FORMAT_VERSION,
+        schema_version: SCHEMA_VERSION,
+        languages: store_connector.languages().to_vec(),
+        created_by: concat!("pathfinder ", env!("CARGO_PKG_VERSION")).to_owned(),
+        entities: entities.len(),
+        distances: distance_count,
+        legacy_distances: legacy_distance_count,
+        embeddings: embedding_count,
+    };
+    fs::write(
+        export_path.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    info!(
+        "Exported {} entities, {} semantic distances, {} legacy semantic distances, and {} embedding vectors.",
+        manifest.entities,
+        manifest.distances,
+        manifest.legacy_distances,
+        manifest.embeddings
+    );
+
+    Ok(())
+}
+
+/// Imports an export directory into the sled stores.
+/// Entries of the export replace stored entries with the same key, other stored entries are kept.
+/// # Arguments
+/// * `store_connector` - The StoreConnector whose stores are filled
+/// * `export_path` - The directory written by export_stores
+/// # Returns
+/// * An error with the file and line if a file cannot be read or a line is invalid; earlier batches remain imported
+pub fn import_stores(store_connector: &StoreConnector, export_path: &str) -> PathfinderResult<()> {
+    let export_path = Path::new(export_path);
+
+    let manifest_path = export_path.join(MANIFEST_FILE);
+    let manifest = fs::read_to_string(&manifest_path).map_err(|error| {
+        PathfinderError::Store(format!(
+            "{} could not be read: {error}",
+            manifest_path.display()
+        ))
+    })?;
+    let manifest: Manifest = serde_json::from_str(&manifest).map_err(|error| {
+        PathfinderError::Decode(format!("{} is invalid: {error}", manifest_path.display()))
+    })?;
+
+    if manifest.format_version > EXPORT_FORMAT_VERSION {
+        return Err(PathfinderError::DataConsistency(format!(
+            "The export has format version {}, but only versions up to {} are supported.",
+            manifest.format_version, EXPORT_FORMAT_VERSION
+        )));
+    }
+    if manifest.languages != store_connector.languages() {
+        warn!(
+            "The export was created with the languages {}, but {} are configured now.",
+            manifest.languages.join(","),
+            store_connector.languages().join(",")
+        );
+    }
+
+    info!(
+        "Importing {} entities from {}, exported by {}.",
+        manifest.entities,
+        export_path.display(),
+        manifest.created_by
+    );
+    let entities_path = export_path.join(ENTITIES_FILE);
+    let mut entity_count = 0;
+    for lines in &open_dump(path_str(&entities_path)?)?
+        .lines()
+        .enumerate()
+        .chunks(BATCH_SIZE)
+    {
+        let records = lines
+            .map(|(index, line)| {
+                serde_json::from_str(&line?)
+                    .map_err(|error| invalid_line(&entities_path, index, error))
+            })
+            .collect::<PathfinderResult<Vec<EntityRecord>>>()?;
+        entity_count += records.len();
+        import_entity_records(store_connector, records)?;
+        info!("{} entities imported.", entity_count);
+    }
+
+    let distance_count = read_tsv(
+        &export_path.join(DISTANCES_FILE),
+        parse_distance,
+        |distances| store_connector.store_semantic_distances(distances),
+    )?;
+    let legacy_distance_count = read_tsv(
+        &export_path.join(LEGACY_DISTANCES_FILE),
+        parse_legacy_distance,
+        |distances| store_connector.store_legacy_semantic_distances(distances),
+    )?;
+    let embedding_count = read_tsv(
+        &export_path.join(EMBEDDINGS_FILE),
+        parse_embedding,
+        |vectors| {
+            for (model, entity, vector) in vectors {
+                store_connector
+                    .embedding_store()
+                    .insert_for_model(&model, &entity, &vector)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    info!(
+        "Imported {} of {} entities, {} of {} semantic distances, {} of {} legacy semantic distances, and {} of {} embedding vectors.",
+        entity_count,
+        manifest.entities,
+        distance_count,
+        manifest.distances,
+        legacy_distance_count,
+        manifest.legacy_distances,
+        embedding_count,
+        manifest.embeddings
+    );
+
+    Ok(())
+}
+
+// Writes a batch of entity records to the stores.
+fn import_entity_records(
+    store_connector: &StoreConnector,
+    records: Vec<EntityRecord>,
+) -> PathfinderResult<()> {
+    let mut labels: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let mut descriptions: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let (mut adjacency_lists, mut fetch_records, mut redirects) = (vec![], vec![], vec![]);
+    let (mut literals, mut statements, mut sitelink_counts) = (vec![], vec![], vec![]);
+
+    for record in records {
+        let id = record.id;
+
+        for (language, label) in record.labels {
+            labels
+                .entry(language)
+                .or_default()
+                .push((id.clone(), label));
+        }
+        for (language, description) in record.descriptions {
+            descriptions
+                .entry(language)
+                .or_default()
+                .push((id.clone(), description));
+        }
+        if let Some(edges) = record.edges {
+            let edges = edges
+                .into_iter()
+                .map(|(prop, adjacent_entity)| format!("{prop}-{adjacent_entity}"))
+                .collect();
+            adjacency_lists.push((id.clone(), edges));
+        }
+        if let Some(fetched) = record.fetched {
+            fetch_records.push((id.clone(), fetched));
+        }
+        if let Some(redirect) = record.redirect {
+            redirects.push((id.clone(), redirect));
+        }
+        if let Some(its_literals) = record.literals {
+            literals.push((id.clone(), its_literals));
+        }
+        if let Some(its_statements) = record.statements {
+            statements.push((id.clone(), its_statements));
+        }
+        if let Some(sitelink_count) = record.sitelinks {
+            sitelink_counts.push((id, sitelink_count));
+        }
+    }
+
+    for (language, values) in labels {
+        store_connector.store_labels(
+            values.iter().map(|(e, l)| (e.as_str(), l.as_str())),
+            &language,
+        )?;
+    }
+    for (language, values) in descriptions {
+        store_connector.store_descriptions(
+            values.iter().map(|(e, d)| (e.as_str(), d.as_str())),
+            &language,
+        )?;
+    }
+    store_connector.restore_adjacency_lists(adjacency_lists, fetch_records)?;
+    store_connector.store_redirects(redirects)?;
+    store_connector.store_literals(literals)?;
+    store_connector.store_statements(statements)?;
+    store_connector.store_sitelink_counts(sitelink_counts)
+}
+
+// Splits the edges of an adjacency list into pairs of property and adjacent entity ordered by property.
+fn split_edges(entity: &str, edges: &[String]) -> PathfinderResult<Vec<(String, String)>> {
+    let mut split_edges = vec![];
+    for edge in edges {
+        let (prop, adjacent_entity) = edge.split_once('-').ok_or_else(|| {
+            PathfinderError::Decode(format!("The edge {edge} of {entity} has no property."))
+        })?;
+        split_edges.push((prop.to_owned(), adjacent_entity.to_owned()));
+    }
+    split_edges.sort();
+
+    Ok(split_edges)
+}
+
+fn create_gz(path: &Path) -> PathfinderResult<GzEncoder<BufWriter<File>>> {
+    let file = File::create(path).map_err(|error| {
+        PathfinderError::Store(format!("{} could not be created: {error}", path.display()))
+    })?;
+    Ok(GzEncoder::new(BufWriter::new(file), Compression::default()))
+}
+
+fn path_str(path: &Path) -> PathfinderResult<&str> {
+    path.to_str()
+        .ok_or_else(|| PathfinderError::Store(format!("{} is not valid UTF-8.", path.display())))
+}
+
+// Writes rows of escaped fields to a compressed TSV file with a header line and returns the number of rows.
+fn write_tsv(
+    path: &Path,
+    header: &str,
+    rows: impl Iterator<Item = PathfinderResult<Vec<String>>>,
+) -> PathfinderResult<usize> {
+    let mut writer = create_gz(path)?;
+    writeln!(writer, "{header}")?;
+
+    let mut count = 0;
+    for row in rows {
+        writeln!(
+            writer,
+            "{}",
+            row?.iter().map(|field| escape(field)).join("\t")
+        )?;
+        count += 1;
+    }
+
+    writer.finish()?;
+    Ok(count)
+}
+
+// Reads the rows of a compressed TSV file in batches and returns the number of rows.
+// Lines that cannot be parsed are reported with their line number and the reason returned by parse.
+fn read_tsv<T>(
+    path: &Path,
+    parse: impl Fn(&[String]) -> Result<T, String>,
+    mut store: impl FnMut(Vec<T>) -> PathfinderResult<()>,
+) -> PathfinderResult<usize> {
+    let mut count = 0;
+
+    for lines in &open_dump(path_str(path)?)?
+        .lines()
+        .enumerate()
+        .skip(1)
+        .chunks(BATCH_SIZE)
+    {
+        let rows = lines
+            .map(|(index, line)| {
+                let fields: Vec<String> = line?.split('\t').map(unescape).collect();
+                parse(&fields).map_err(|reason| invalid_line(path, index, reason))
+            })
+            .collect::<PathfinderResult<Vec<T>>>()?;
+        count += rows.len();
+        store(rows)?;
+    }
+
+    Ok(count)
+}
+
+// Parses a row of the distances file as (model, entity, entity, distance) tuple.
+fn parse_distance(fields: &[String]) -> Result<(String, String, String, f64), String> {
+    let [model, entity_a, entity_b, distance] = fields else {
+        return Err(format!("it has {} instead of 4 fields", fields.len()));
+    };
+    Ok((
+        model.to_owned(),
+        entity_a.to_owned(),
+        entity_b.to_owned(),
+        parse_number(distance)?,
+    ))
+}
+
+// Parses a row of the legacy distances file as pair of the texts and the distance.
+fn parse_legacy_distance(fields: &[String]) -> Result<(String, f64), String> {
+    let [texts, distance] = fields else {
+        return Err(format!("it has {} instead of 2 fields", fields.len()));
+    };
+    Ok((texts.to_owned(), parse_number(distance)?))
+}
+
+// Parses a row of the embeddings file as (model, entity, vector) tuple.
+// An empty vector is kept, as it marks an entity that cannot be embedded.
+fn parse_embedding(fields: &[String]) -> Result<(String, String, Vec<f32>), String> {
+    let [model, entity, vector] = fields else {
+        return Err(format!("it has {} instead of 3 fields", fields.len()));
+    };
+    let vector = if vector.is_empty() {
+        vec![]
+    } else {
+        vector
+            .split(' ')
+            .map(parse_number)
+            .collect::<Result<Vec<f32>, _>>()?
+    };
+    Ok((model.to_owned(), entity.to_owned(), vector))
+}
+
+// Creates the error of a line of an export file given by its zero-based index.
+fn invalid_line(path: &Path, index: usize, reason: impl Display) -> PathfinderError {
+    PathfinderError::Decode(format!(
+        "Line {} of {} is invalid: {reason}",
+        index + 1,
+        path.display()
+    ))
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|error| format!("{value} is no number: {error}"))
+}
+
+// Escapes backslashes, tabs, and line breaks, which may occur in the texts of legacy distances.
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes rows to a TSV file in the temporary directory and reads them back with the given parser.
+    fn round_trip<T>(
+        name: &str,
+        rows: Vec<Vec<String>>,
+        parse: impl Fn(&[String]) -> Result<T, String>,
+    ) -> PathfinderResult<Vec<T>> {
+        let path = std::env::temp_dir().join(format!("{}_{name}.tsv.gz", std::process::id()));
+        write_tsv(&path, "header", rows.into_iter().map(Ok)).unwrap();
+
+        let mut parsed = vec![];
+        let result = read_tsv(&path, parse, |rows| {
+            parsed.extend(rows);
+            Ok(())
+        });
+        fs::remove_file(&path).unwrap();
+        result.map(|_| parsed)
+    }
+
+    #[test]
+    fn escaped_fields_are_unescaped() {
+        for field in ["plain", "tab\there", "a\tb\\c", "lines\n\r", "\\", "\\t"] {
+            assert_eq!(unescape(&escape(field)), field);
+        }
+        assert_eq!(escape("a\tb\nc"), "a\\tb\\nc");
+        assert_eq!(unescape("trailing\\"), "trailing\\");
+    }
+
+    #[test]
+    fn empty_embedding_vectors_are_round_tripped() {
+        let rows = vec![
+            vec!["model".to_owned(), "Q1".to_owned(), "0.5 -1".to_owned()],
+            vec!["model".to_owned(), "Q2".to_owned(), String::new()],
+        ];
+        let vectors = round_trip("embeddings", rows, parse_embedding).unwrap();
+
+        assert_eq!(vectors[0].2, vec![0.5, -1.0]);
+        assert!(vectors[1].2.is_empty());
+    }
+
+    #[test]
+    fn invalid_lines_are_reported_with_their_number() {
+        let rows = vec![
+            vec!["texts".to_owned(), "0.5".to_owned()],
+            vec!["texts".to_owned(), "far".to_owned()],
+        ];
+        let error = round_trip("legacy_distances", rows, parse_legacy_distance).unwrap_err();
+        assert!(error.to_string().contains("Line 3"));
+
+        let rows = vec![vec!["model".to_owned(), "Q1".to_owned()]];
+        assert!(round_trip("distances", rows, parse_distance).is_err());
+    }
+}
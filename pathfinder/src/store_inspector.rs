@@ -0,0 +1,339 @@
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::graph_store::GraphStore;
+use crate::refresh::SECONDS_PER_DAY;
+use crate::store_connector::StoreConnector;
+
+// The upper bounds of the degree buckets of the degree distribution, the last bucket is unbounded
+const DEGREE_BUCKETS: [usize; 5] = [0, 1, 9, 99, 999];
+
+// The width of the bars of the property usage histogram in characters
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Prints statistics of the sled stores: the number of entities and edges, the degree distribution,
+/// the hubs with the most adjacent entities, the usage of properties, the coverage of labels and descriptions,
+/// the number of cached semantic distances, and the size of each store.
+/// # Arguments
+/// * `store_connector` - The StoreConnector whose stores are inspected, nothing is fetched
+/// * `top_n` - The number of hubs and properties listed
+/// # Returns
+/// * An error if a store cannot be read
+pub fn print_stats(store_connector: &StoreConnector, top_n: usize) -> PathfinderResult<()> {
+    let (adjacency_lists, names) = store_connector.read_all_adjacency_ids()?;
+
+    let mut out_degrees: Vec<(u32, usize)> = vec![];
+    let mut in_degrees: HashMap<u32, usize> = HashMap::new();
+    let mut prop_counts: HashMap<u32, usize> = HashMap::new();
+    let mut entities: HashSet<u32> = HashSet::new();
+
+    for (some_entity, its_adjacent_entities) in &adjacency_lists {
+        out_degrees.push((*some_entity, its_adjacent_entities.len()));
+        entities.insert(*some_entity);
+
+        for &(prop, adjacent_entity) in its_adjacent_entities {
+            *in_degrees.entry(adjacent_entity).or_default() += 1;
+            *prop_counts.entry(prop).or_default() += 1;
+            entities.insert(adjacent_entity);
+        }
+    }
+
+    let edge_count: usize = out_degrees.iter().map(|(_, degree)| degree).sum();
+
+    println!("Entities");
+    println!("  with adjacency list: {}", adjacency_lists.len());
+    println!(
+        "  in total, including adjacent entities: {}",
+        entities.len()
+    );
+    println!("  properties: {}", prop_counts.len());
+    println!("  edges: {}", edge_count);
+
+    let degrees: Vec<usize> = out_degrees
+        .iter()
+        .map(|(_, degree)| *degree)
+        .sorted()
+        .collect();
+    println!();
+    println!("Out-degree distribution");
+    if let (Some(min), Some(max)) = (degrees.first(), degrees.last()) {
+        println!(
+            "  min {}, median {}, mean {:.2}, max {}",
+            min,
+            degrees[degrees.len() / 2],
+            edge_count as f64 / degrees.len() as f64,
+            max
+        );
+    }
+    for (bucket, count) in degree_distribution(&degrees) {
+        println!("  {:>11}: {}", bucket, count);
+    }
+
+    let label = |id: u32| -> PathfinderResult<String> {
+        let name = &names[id as usize];
+        Ok(match store_connector.read_stored_label(name)? {
+            Some((label, _)) => format!("{name} ({label})"),
+            None => name.to_owned(),
+        })
+    };
+    let print_top = |title: &str, counts: Vec<(u32, usize)>| -> PathfinderResult<()> {
+        println!();
+        println!("{title}");
+        for (id, count) in counts
+            .into_iter()
+            .sorted_by(|(id_a, a), (id_b, b)| b.cmp(a).then(id_a.cmp(id_b)))
+            .take(top_n)
+        {
+            println!("  {:>9}  {}", count, label(id)?);
+        }
+        Ok(())
+    };
+
+    print_top(&format!("Top {top_n} hubs by out-degree"), out_degrees)?;
+    print_top(
+        &format!("Top {top_n} hubs by in-degree"),
+        in_degrees.into_iter().collect(),
+    )?;
+
+    // the bars are scaled relative to the most used property
+    let max_prop_count = prop_counts.values().copied().max().unwrap_or(0);
+    println!();
+    println!("Top {top_n} properties by usage");
+    for (prop, count) in prop_counts
+        .iter()
+        .sorted_by(|(prop_a, a), (prop_b, b)| b.cmp(a).then(prop_a.cmp(prop_b)))
+        .take(top_n)
+    {
+        println!(
+            "  {:>9} {:>6.2}% {:<width$}  {}",
+            count,
+            100.0 * *count as f64 / edge_count as f64,
+            "#".repeat(HISTOGRAM_WIDTH * count / max_prop_count),
+            label(*prop)?,
+            width = HISTOGRAM_WIDTH
+        );
+    }
+
+    println!();
+    println!("Label and description coverage");
+    for (kind, ids) in [
+        ("entities", entities),
+        ("properties", prop_counts.into_keys().collect()),
+    ] {
+        let (mut label_count, mut description_count) = (0, 0);
+        for id in &ids {
+            let name = &names[*id as usize];
+            label_count += store_connector.read_stored_label(name)?.is_some() as usize;
+            description_count += store_connector.read_stored_description(name)?.is_some() as usize;
+        }
+
+        println!(
+            "  {}: {} with label ({}), {} with description ({})",
+            kind,
+            label_count,
+            percentage(label_count, ids.len()),
+            description_count,
+            percentage(description_count, ids.len())
+        );
+    }
+
+    let (distance_count, legacy_distance_count) = store_connector.semantic_distance_counts()?;
+    println!();
+    println!("Semantic distance cache");
+    println!("  distances: {}", distance_count);
+    println!(
+        "  distances keyed by labels and descriptions: {}",
+        legacy_distance_count
+    );
+    println!("  embedding vectors: {}", store_connector.embedding_count());
+
+    println!();
+    println!("Stores");
+    for store_size in store_connector.store_sizes()? {
+        println!(
+            "  {}: {}",
+            store_size.name,
+            human_size(store_size.size_on_disk)
+        );
+        for (tree_name, length) in store_size.tree_lengths {
+            println!("    {}: {} entries", tree_name, length);
+        }
+    }
+
+    println!();
+    println!("Store parameters");
+    for (name, metadata) in store_connector.store_metadata()? {
+        println!("  {}", name);
+        for (key, value) in metadata {
+            println!("    {}: {}", key, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the stored data of an entity: its label and description, the record of its last fetch,
+/// its number of sitelinks and statements, and its outgoing and incoming edges with the labels of the adjacent entities.
+/// # Arguments
+/// * `store_connector` - The StoreConnector whose stores are inspected, nothing is fetched
+/// * `entity` - The entity, e.g., Q42
+/// # Returns
+/// * An error if a store cannot be read
+pub fn show_entity(store_connector: &StoreConnector, entity: &str) -> PathfinderResult<()> {
+    let label = |some_entity: &str| -> PathfinderResult<String> {
+        Ok(store_connector
+            .read_stored_label(some_entity)?
+            .map_or_else(|| "no label".to_owned(), |(label, _)| label))
+    };
+
+    // the label and description are shown with the language of the fallback chain they are stored in
+    let with_language = |value: Option<(String, String)>| -> String {
+        value.map_or_else(
+            || "none".to_owned(),
+            |(v, language)| format!("{v} @{language}"),
+        )
+    };
+
+    println!("{}", entity);
+    println!(
+        "  label: {}",
+        with_language(store_connector.read_stored_label(entity)?)
+    );
+    println!(
+        "  description: {}",
+        with_language(store_connector.read_stored_description(entity)?)
+    );
+
+    match store_connector.read_fetch_record(entity)? {
+        Some(record) => println!(
+            "  fetched: {:.1} days ago from {}",
+            record.age() as f64 / SECONDS_PER_DAY,
+            record.source
+        ),
+        None => println!("  fetched: unknown"),
+    }
+    if let Some(sitelink_count) = store_connector.read_sitelink_count(entity)? {
+        println!("  sitelinks: {}", sitelink_count);
+    }
+    if let Some(statements) = store_connector.read_statements(entity)? {
+        println!("  statements: {}", statements.len());
+    }
+
+    // entities without an adjacency list of their own may still be the object of other entities' edges
+    match store_connector.read_adjacency_list(entity)? {
+        Some(adjacency_list) => {
+            println!();
+            println!("Outgoing edges ({})", adjacency_list.len());
+            for edge in adjacency_list.iter().sorted() {
+                let (prop, adjacent_entity) = edge.split_once('-').ok_or_else(|| {
+                    PathfinderError::Decode(format!("The edge {edge} of {entity} has no property."))
+                })?;
+                println!(
+                    "  {} ({}) -> {} ({})",
+                    prop,
+                    label(prop)?,
+                    adjacent_entity,
+                    label(adjacent_entity)?
+                );
+            }
+        }
+        None => println!("  No adjacency list stored."),
+    }
+
+    let incoming_edges = store_connector.get_incoming_entities(entity)?;
+    println!();
+    println!("Incoming edges ({})", incoming_edges.len());
+    for (prop, some_entity) in incoming_edges.iter().sorted() {
+        println!(
+            "  {} ({}) <- {} ({})",
+            prop,
+            label(prop)?,
+            some_entity,
+            label(some_entity)?
+        );
+    }
+
+    Ok(())
+}
+
+// Counts the degrees per bucket of DEGREE_BUCKETS as pairs of the formatted bucket and the count.
+fn degree_distribution(degrees: &[usize]) -> Vec<(String, usize)> {
+    let mut distribution = vec![];
+    let mut lower_bound = 0;
+    for upper_bound in DEGREE_BUCKETS {
+        let count = degrees
+            .iter()
+            .filter(|degree| (lower_bound..=upper_bound).contains(*degree))
+            .count();
+        distribution.push((degree_range(lower_bound, upper_bound), count));
+        lower_bound = upper_bound + 1;
+    }
+    distribution.push((
+        format!("{lower_bound}+"),
+        degrees
+            .iter()
+            .filter(|degree| **degree >= lower_bound)
+            .count(),
+    ));
+    distribution
+}
+
+// Formats a bucket of the degree distribution.
+fn degree_range(lower_bound: usize, upper_bound: usize) -> String {
+    if lower_bound == upper_bound {
+        upper_bound.to_string()
+    } else {
+        format!("{lower_bound}-{upper_bound}")
+    }
+}
+
+fn percentage(count: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_owned();
+    }
+    format!("{:.1}%", 100.0 * count as f64 / total as f64)
+}
+
+// Formats a number of bytes with binary prefixes, e.g., 1.5 MiB.
+fn human_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in ["B", "KiB", "MiB", "GiB"] {
+        if size < 1024.0 {
+            return format!("{size:.1} {unit}");
+        }
+        size /= 1024.0;
+    }
+    format!("{size:.1} TiB")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrees_are_counted_per_bucket() {
+        let distribution = degree_distribution(&[0, 1, 2, 9, 10, 99, 1000, 5000]);
+        assert_eq!(
+            distribution,
+            [
+                ("0".to_owned(), 1),
+                ("1".to_owned(), 1),
+                ("2-9".to_owned(), 2),
+                ("10-99".to_owned(), 2),
+                ("100-999".to_owned(), 0),
+                ("1000+".to_owned(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_are_formatted() {
+        assert_eq!(percentage(1, 3), "33.3%");
+        assert_eq!(percentage(0, 0), "-");
+        assert_eq!(human_size(512), "512.0 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+        assert_eq!(human_size(2 * 1024 * 1024 * 1024 * 1024), "2.0 TiB");
+    }
+}
@@ -0,0 +1,100 @@
+use log::info;
+use std::collections::HashSet;
+
+use crate::entity_id::normalize_entity_id;
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::graph_store::GraphStore;
+use crate::store_connector::StoreConnector;
+
+/// Removes all entities from the stores that are not reachable within a number of hops from the entities of a query file.
+/// Only stored adjacency lists are followed, so nothing is fetched.
+/// # Arguments
+/// * `store_connector` - The StoreConnector whose stores are pruned
+/// * `query_file_path` - A query file whose first two columns are the start and target entities of the queries
+/// * `hops` - The maximum number of edges between a query entity and a kept entity
+/// * `incoming_edges` - Whether incoming edges are followed as well, as in the search
+/// # Returns
+/// * An error if the query file or a store cannot be read
+pub fn prune_stores(
+    store_connector: &StoreConnector,
+    query_file_path: &str,
+    hops: usize,
+    incoming_edges: bool,
+) -> PathfinderResult<()> {
+    let mut reader = csv::Reader::from_path(query_file_path).map_err(|error| {
+        PathfinderError::Store(format!("{query_file_path} could not be read: {error}"))
+    })?;
+    let mut frontier: HashSet<String> = HashSet::new();
+
+    for record in reader.records() {
+        let record = record.map_err(|error| {
+            PathfinderError::Decode(format!("{query_file_path} is invalid: {error}"))
+        })?;
+        for column in 0..2 {
+            let entity = normalize_entity_id(record.get(column).ok_or_else(|| {
+                PathfinderError::Decode(format!(
+                    "A query of {query_file_path} lacks its start or target entity."
+                ))
+            })?);
+
+            // redirected query entities are resolved by the search, so their targets are kept as well
+            if let Some(target) = store_connector
+                .read_stored_redirect(&entity)?
+                .filter(|target| !target.is_empty())
+            {
+                frontier.insert(target);
+            }
+            frontier.insert(entity);
+        }
+    }
+
+    info!(
+        "Collecting the entities within {} hops of {} query entities.",
+        hops,
+        frontier.len()
+    );
+
+    let mut reachable = frontier.clone();
+    for _ in 0..hops {
+        let mut next_frontier = HashSet::new();
+
+        for entity in &frontier {
+            let mut adjacent_entities = vec![];
+            for edge in store_connector
+                .read_adjacency_list(entity)?
+                .unwrap_or_default()
+            {
+                let (_, adjacent_entity) = edge.split_once('-').ok_or_else(|| {
+                    PathfinderError::Decode(format!("The edge {edge} of {entity} has no property."))
+                })?;
+                adjacent_entities.push(adjacent_entity.to_owned());
+            }
+            if incoming_edges {
+                adjacent_entities.extend(
+                    store_connector
+                        .get_incoming_entities(entity)?
+                        .into_iter()
+                        .map(|(_, some_entity)| some_entity),
+                );
+            }
+
+            for adjacent_entity in adjacent_entities {
+                if reachable.insert(adjacent_entity.clone()) {
+                    next_frontier.insert(adjacent_entity);
+                }
+            }
+        }
+
+        frontier = next_frontier;
+    }
+
+    info!(
+        "Removing all entities except {} reachable entities.",
+        reachable.len()
+    );
+    for (store, removed_count) in store_connector.retain_entities(|e| reachable.contains(e))? {
+        info!("Removed {} entries from {}.", removed_count, store);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use crate::error::{PathfinderError, PathfinderResult};
+use crate::kv_store::{Batch, Db, Tree};
+
+/// The schema version of the stores written by this version of the pathfinder.
+/// Version 1 are the stores without metadata, i.e., adjacency lists as bincode-serialized strings.
+/// Version 2 stores adjacency lists as interned ID pairs along with an incoming list.
+/// Version 3 stores semantic distances keyed by embedding model and entity IDs along with embedding vectors.
+/// Version 4 keeps the incoming list in the adjacency list store instead of a store of its own.
+pub const SCHEMA_VERSION: u32 = 4;
+
+// The keys of the metadata tree
+const VERSION_KEY: &str = "schema_version";
+const CREATED_BY_KEY: &str = "created_by";
+
+// The prefix of the keys of the parameters of the latest import into a store
+const IMPORT_PREFIX: &str = "import_";
+
+/// The parameters a store is filled with as pairs of key and value, e.g., the language fallback chain.
+/// They are recorded when a store is created and reported if the current configuration differs.
+pub type StoreParameters = Vec<(&'static str, String)>;
+
+/// Opens the metadata tree of a store.
+pub fn metadata(db: &Db) -> PathfinderResult<Tree> {
+    db.open_tree("metadata")
+        .map_err(|error| PathfinderError::Store(format!("Metadata could not be opened: {error}")))
+}
+
+/// Reads the schema version of a store.
+/// Stores without version are of version 1 unless they are empty, i.e., newly created.
+pub fn read_version(db: &Db) -> PathfinderResult<Option<u32>> {
+    let metadata = metadata(db)?;
+
+    match metadata.get(VERSION_KEY)? {
+        Some(value) => std::str::from_utf8(&value)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Some)
+            .ok_or_else(|| PathfinderError::Decode("Schema version is not a number.".to_owned())),
+        None if db.is_empty()? => Ok(None),
+        None => Ok(Some(1)),
+    }
+}
+
+/// Records the schema version of a store.
+pub fn write_version(db: &Db, version: u32) -> PathfinderResult<()> {
+    let metadata = metadata(db)?;
+    metadata.insert(VERSION_KEY, version.to_string().as_str())?;
+    metadata.flush()?;
+    Ok(())
+}
+
+/// Records the parameters a store was created with unless they are recorded already.
+pub fn write_creation_parameters(db: &Db, parameters: &StoreParameters) -> PathfinderResult<()> {
+    let metadata = metadata(db)?;
+
+    if metadata.contains_key(CREATED_BY_KEY)? {
+        return Ok(());
+    }
+
+    for (key, value) in parameters {
+        metadata.insert(key, value.as_str())?;
+    }
+    metadata.insert(
+        CREATED_BY_KEY,
+        concat!("pathfinder ", env!("CARGO_PKG_VERSION")),
+    )?;
+
+    Ok(())
+}
+
+/// Removes the recorded creation parameters of a store, such that the current ones are recorded again.
+pub fn clear_creation_parameters(db: &Db) -> PathfinderResult<()> {
+    let metadata = metadata(db)?;
+
+    let mut batch = Batch::default();
+    for entry in metadata.iter() {
+        let (key, _) = entry?;
+        if key != VERSION_KEY.as_bytes() && !key.starts_with(IMPORT_PREFIX.as_bytes()) {
+            batch.remove(key);
+        }
+    }
+    metadata.apply_batch(batch)?;
+    Ok(())
+}
+
+/// Returns the recorded creation parameters that differ from the given ones as (key, recorded, given) triples.
+pub fn changed_creation_parameters(
+    db: &Db,
+    parameters: &StoreParameters,
+) -> PathfinderResult<Vec<(&'static str, String, String)>> {
+    let metadata = metadata(db)?;
+    let mut changed = vec![];
+
+    for (key, value) in parameters {
+        if let Some(recorded) = metadata.get(key)? {
+            let recorded = String::from_utf8_lossy(&recorded).into_owned();
+            if recorded != *value {
+                changed.push((*key, recorded, value.to_owned()));
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Records the parameters of an import into a store, replacing the parameters of earlier imports.
+/// The keys are prefixed with import_ to tell them apart from the creation parameters.
+pub fn write_import_parameters(db: &Db, parameters: &StoreParameters) -> PathfinderResult<()> {
+    let metadata = metadata(db)?;
+
+    let mut batch = Batch::default();
+    for entry in metadata.scan_prefix(IMPORT_PREFIX) {
+        let (key, _) = entry?;
+        batch.remove(key);
+    }
+    for (key, value) in parameters {
+        batch.insert(format!("{IMPORT_PREFIX}{key}").as_str(), value.as_str());
+    }
+    metadata.apply_batch(batch)?;
+    metadata.flush()?;
+
+    Ok(())
+}
+
+/// The metadata of a store, i.e., its schema version and its creation and import parameters, as pairs of key and value.
+pub type Metadata = Vec<(String, String)>;
+
+/// Reads all metadata of a store.
+pub fn read_metadata(db: &Db) -> PathfinderResult<Metadata> {
+    let mut entries = vec![];
+    for entry in metadata(db)?.iter() {
+        let (key, value) = entry?;
+        entries.push((
+            String::from_utf8_lossy(&key).into_owned(),
+            String::from_utf8_lossy(&value).into_owned(),
+        ));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv_store;
+
+    #[test]
+    fn import_parameters_replace_those_of_earlier_imports() {
+        let db = kv_store::open_temporary();
+        write_creation_parameters(&db, &vec![("languages", "en".to_owned())]).unwrap();
+        write_import_parameters(
+            &db,
+            &vec![
+                ("source", "json_dump".to_owned()),
+                ("offset", "100".to_owned()),
+            ],
+        )
+        .unwrap();
+        write_import_parameters(&db, &vec![("source", "truthy_dump".to_owned())]).unwrap();
+
+        let metadata = read_metadata(&db).unwrap();
+        assert!(metadata.contains(&("import_source".to_owned(), "truthy_dump".to_owned())));
+        assert!(metadata.contains(&("languages".to_owned(), "en".to_owned())));
+        assert!(!metadata.iter().any(|(key, _)| key == "import_offset"));
+
+        let changed =
+            changed_creation_parameters(&db, &vec![("languages", "de,en".to_owned())]).unwrap();
+        assert_eq!(
+            changed,
+            vec![("languages", "en".to_owned(), "de,en".to_owned())]
+        );
+        clear_creation_parameters(&db).unwrap();
+        let metadata = read_metadata(&db).unwrap();
+        assert_eq!(
+            metadata,
+            vec![("import_source".to_owned(), "truthy_dump".to_owned())]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use itertools::Itertools;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::embedding_provider::EmbeddingProvider;
+use crate::error::{PathfinderError, PathfinderResult};
+
+// The magic number at the start of binary fastText models, which are no word2vec files despite their extension
+const FASTTEXT_MAGIC: [u8; 4] = 793712314_i32.to_le_bytes();
+
+/// Embeds the texts describing entities by averaging static word vectors, e.g., of GloVe or fastText.
+/// Vectors are read from a text file with one word and its values per line, optionally preceded by a header line
+/// with the number of words and dimensions, or from a binary file in the word2vec format (.bin).
+pub struct WordVectorProvider {
+    model: String,
+    vectors: HashMap<String, Vec<f32>>,
+    dimensions: usize,
+}
+
+impl WordVectorProvider {
+    /// Loads a WordVectorProvider from a vector file.
+    /// # Arguments
+    /// * `path` - The path of the vector file
+    /// # Returns
+    /// * The instance or an error if the file cannot be read, is a binary fastText model, or has no valid vectors
+    pub fn load(path: &str) -> PathfinderResult<Self> {
+        info!("Loading word vectors from {}.", path);
+
+        let file = File::open(path).map_err(|error| {
+            PathfinderError::Store(format!(
+                "Word vector file {path} could not be opened: {error}"
+            ))
+        })?;
+        let mut reader = BufReader::new(HashingReader::new(file));
+
+        let (vectors, dimensions) = if path.ends_with(".bin") {
+            read_binary(&mut reader)?
+        } else {
+            read_text(&mut reader)?
+        };
+
+        info!(
+            "Loaded {} word vectors with {} dimensions.",
+            vectors.len(),
+            dimensions
+        );
+
+        // the model is identified by the file name and the hash of its content,
+        // so vectors of different files with the same name are never mixed
+        let file_name = Path::new(path)
+            .file_name()
+            .map_or_else(|| path.into(), |name| name.to_string_lossy());
+        let content_hash = reader.into_inner().hash;
+
+        Ok(Self {
+            model: format!("word_vectors:{file_name}:{content_hash:016x}"),
+            vectors,
+            dimensions,
+        })
+    }
+
+    // Averages the vectors of all tokens of a text; None if no token has a vector.
+    // Tokens are looked up as they are and in lower case, as some vector files contain lower case words only.
+    fn embed_text(&self, text: &str) -> Option<Vec<f32>> {
+        let mut sum = vec![0.0; self.dimensions];
+        let mut count = 0;
+
+        for token in tokenize(text) {
+            let vector = self
+                .vectors
+                .get(token)
+                .or_else(|| self.vectors.get(&token.to_lowercase()));
+
+            if let Some(vector) = vector {
+                for (s, v) in sum.iter_mut().zip(vector) {
+                    *s += v;
+                }
+                count += 1;
+            }
+        }
+
+        (count > 0).then(|| sum.into_iter().map(|s| s / count as f32).collect())
+    }
+}
+
+impl EmbeddingProvider for WordVectorProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, texts: &[(String, String)]) -> PathfinderResult<Vec<Option<Vec<f32>>>> {
+        Ok(texts
+            .iter()
+            .map(|(_, text)| self.embed_text(text))
+            .collect())
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+// A reader that calculates the FNV-1a hash of all bytes read, which is stable across platforms and versions.
+struct HashingReader<R: Read> {
+    inner: R,
+    hash: u64,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hash: 0xcbf29ce484222325,
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let length = self.inner.read(buffer)?;
+        for &byte in &buffer[..length] {
+            self.hash = (self.hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        Ok(length)
+    }
+}
+
+// Splits a text into words at all characters that are neither letters nor digits.
+fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+}
+
+// Reads vectors in the text format and returns them along with their dimensions.
+// A first line with two numbers only is the header of fastText files, otherwise the first line determines the dimensions.
+// As words may contain spaces, e.g., in GloVe files, the last values of a line are the vector and the rest is the word.
+// Lines with other dimensions or values that are no numbers are skipped.
+fn read_text(reader: &mut impl BufRead) -> PathfinderResult<(HashMap<String, Vec<f32>>, usize)> {
+    let mut vectors = HashMap::new();
+    let mut dimensions = None;
+    let mut malformed_count = 0;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let fields: Vec<&str> = line.trim_end().split(' ').collect();
+
+        if index == 0 {
+            if let Some((_, header_dimensions)) = parse_header(&line) {
+                dimensions = Some(header_dimensions);
+                continue;
+            }
+        }
+
+        let dimensions = *dimensions.get_or_insert(fields.len().saturating_sub(1));
+        if dimensions == 0 || fields.len() <= dimensions {
+            malformed_count += 1;
+            continue;
+        }
+
+        let (word, values) = fields.split_at(fields.len() - dimensions);
+        match values
+            .iter()
+            .map(|v| v.parse())
+            .collect::<Result<Vec<f32>, _>>()
+        {
+            Ok(vector) => {
+                vectors.insert(word.join(" "), vector);
+            }
+            Err(_) => malformed_count += 1,
+        }
+    }
+
+    if malformed_count > 0 {
+        warn!(
+            "{} malformed lines of the word vector file were skipped.",
+            malformed_count
+        );
+    }
+
+    match dimensions {
+        Some(dimensions) if !vectors.is_empty() => Ok((vectors, dimensions)),
+        _ => Err(PathfinderError::Decode(
+            "The word vector file contains no valid vectors.".to_owned(),
+        )),
+    }
+}
+
+// Reads vectors in the binary word2vec format and returns them along with their dimensions:
+// a header line with the number of words and dimensions, then each word followed by a space and its values as f32
+fn read_binary(reader: &mut impl BufRead) -> PathfinderResult<(HashMap<String, Vec<f32>>, usize)> {
+    if reader.fill_buf()?.starts_with(&FASTTEXT_MAGIC) {
+        return Err(PathfinderError::Decode(
+            "Binary fastText models are not supported, use the .vec text file of the model instead.".to_owned(),
+        ));
+    }
+
+    let mut header = vec![];
+    reader.read_until(b'\n', &mut header)?;
+    let (count, dimensions) = std::str::from_utf8(&header)
+        .ok()
+        .and_then(parse_header)
+        .filter(|&(_, dimensions)| dimensions > 0)
+        .ok_or_else(|| {
+            PathfinderError::Decode(
+                "The word vector file has no header with the number of words and dimensions."
+                    .to_owned(),
+            )
+        })?;
+
+    // the capacity is limited, as the header is not validated yet
+    let mut vectors = HashMap::with_capacity(count.min(1 << 20));
+    let mut buffer = vec![0; dimensions * 4];
+
+    for index in 0..count {
+        let mut word = vec![];
+        reader.read_until(b' ', &mut word)?;
+        let word = String::from_utf8_lossy(&word);
+
+        reader.read_exact(&mut buffer).map_err(|error| {
+            PathfinderError::Decode(format!(
+                "The word vector file ends at word {} of {}: {error}",
+                index + 1,
+                count
+            ))
+        })?;
+        let vector = buffer
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+
+        // some files terminate each vector with a line break, which then precedes the next word
+        vectors.insert(word.trim().to_owned(), vector);
+    }
+
+    Ok((vectors, dimensions))
+}
+
+// Parses a header line with the number of words and the number of dimensions.
+fn parse_header(line: &str) -> Option<(usize, usize)> {
+    line.split_whitespace()
+        .map(|v| v.parse::<usize>().ok())
+        .collect::<Option<Vec<usize>>>()?
+        .into_iter()
+        .collect_tuple()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn binary_file(header: &str, words: &[(&str, [f32; 2])]) -> Vec<u8> {
+        let mut bytes = header.as_bytes().to_vec();
+        for (word, vector) in words {
+            bytes.extend(format!("{word} ").as_bytes());
+            bytes.extend(vector.iter().flat_map(|v| v.to_le_bytes()));
+            bytes.push(b'\n');
+        }
+        bytes
+    }
+
+    #[test]
+    fn text_vectors_are_read_with_and_without_header() {
+        let (vectors, dimensions) =
+            read_text(&mut Cursor::new("2 3\ncat 1 0 0\ndog 0 1 0.5\n")).unwrap();
+        assert_eq!(dimensions, 3);
+        assert_eq!(vectors["dog"], vec![0.0, 1.0, 0.5]);
+
+        let (vectors, dimensions) = read_text(&mut Cursor::new("cat 1 0\ndog 0 1\n")).unwrap();
+        assert_eq!(dimensions, 2);
+        assert_eq!(vectors.len(), 2);
+    }
+
+    #[test]
+    fn words_with_spaces_are_kept_and_malformed_lines_are_skipped() {
+        let (vectors, _) = read_text(&mut Cursor::new(
+            "cat 1 0\nNew York 0 1\nbroken 1 x\nshort 1\n",
+        ))
+        .unwrap();
+
+        assert_eq!(vectors["New York"], vec![0.0, 1.0]);
+        assert_eq!(vectors.len(), 2);
+        assert!(read_text(&mut Cursor::new("1 2\n")).is_err());
+    }
+
+    #[test]
+    fn binary_vectors_are_read() {
+        let bytes = binary_file("2 2\n", &[("cat", [1.0, 0.0]), ("dog", [0.0, 1.0])]);
+        let (vectors, dimensions) = read_binary(&mut Cursor::new(bytes)).unwrap();
+
+        assert_eq!(dimensions, 2);
+        assert_eq!(vectors["cat"], vec![1.0, 0.0]);
+        assert_eq!(vectors["dog"], vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn truncated_binary_files_and_fasttext_models_are_rejected() {
+        let bytes = binary_file("3 2\n", &[("cat", [1.0, 0.0])]);
+        assert!(read_binary(&mut Cursor::new(bytes)).is_err());
+        assert!(read_binary(&mut Cursor::new("no header\n")).is_err());
+
+        let mut fasttext = FASTTEXT_MAGIC.to_vec();
+        fasttext.extend([12, 0, 0, 0]);
+        assert!(read_binary(&mut Cursor::new(fasttext)).is_err());
+    }
+
+    #[test]
+    fn the_model_identifies_the_content() {
+        let mut reader = HashingReader::new(Cursor::new("cat 1 0\n"));
+        reader.read_to_end(&mut vec![]).unwrap();
+        let mut other_reader = HashingReader::new(Cursor::new("cat 0 1\n"));
+        other_reader.read_to_end(&mut vec![]).unwrap();
+
+        assert_ne!(reader.hash, other_reader.hash);
+    }
+}
@@ -6,7 +6,8 @@ from sentence_transformers import SentenceTransformer
 
 
 app = Flask(__name__)
-model = SentenceTransformer('sentence-transformers/all-mpnet-base-v2')
+model_name = 'sentence-transformers/all-mpnet-base-v2'
+model = SentenceTransformer(model_name)
 
 
 @app.route("/")
@@ -25,7 +26,24 @@ def get_distance():
 
     return jsonify(
         {
-            "distance": the_distance
+            "distance": the_distance,
+            "embedding_a": embedding_a.tolist(),
+            "embedding_b": embedding_b.tolist()
+        }
+    ), 200
+
+
+@app.route("/embed", methods=["POST"])
+def get_embeddings():
+
+    texts = request.get_json()["texts"]
+
+    embeddings = model.encode(texts)
+
+    return jsonify(
+        {
+            "model": model_name,
+            "embeddings": embeddings.tolist()
         }
     ), 200
 
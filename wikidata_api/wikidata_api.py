@@ -26,6 +26,21 @@ with open("wd_properties.csv", newline="", ) as csvfile:
         prop_frequency_dict[prop] = int(frequency)
 
 
+# items, properties, lexemes, and forms and senses of lexemes can be part of a path
+entity_id_pattern = "^http://www.wikidata.org/entity/([QP][0-9]+|L[0-9]+(-[FS][0-9]+)?)$"
+
+# lexemes, forms, and senses have no rdfs:label and schema:description
+# thus, their lemma, representation, or gloss and their lexical category or grammatical features are used instead
+label_path = "rdfs:label|wikibase:lemma|ontolex:representation|skos:definition"
+description_path = "schema:description|(wikibase:lexicalCategory/rdfs:label)|(wikibase:grammaticalFeature/rdfs:label)|(^ontolex:sense/wikibase:lemma)"
+
+entity_template_values = {
+    "entity_id_pattern": entity_id_pattern,
+    "label_path": label_path,
+    "description_path": description_path,
+}
+
+
 def query_wikidata(query):
     sparql_wrapper.setQuery(query)
     sparql_wrapper.setReturnFormat(JSON)
@@ -56,20 +71,20 @@ def adjacent_entities():
         where_block_template = Template("""
             $subject_id $predicate_id $object_id .
 
-            FILTER ( CONTAINS( str($subject_id), "http://www.wikidata.org/entity/Q" ) ) .
+            FILTER ( REGEX( str($subject_id), "$entity_id_pattern" ) ) .
             FILTER ( CONTAINS( str($predicate_id), "http://www.wikidata.org/prop/direct/P" ) ) .
-            FILTER ( CONTAINS( str($object_id), "http://www.wikidata.org/entity/Q" ) ) .
+            FILTER ( REGEX( str($object_id), "$entity_id_pattern" ) ) .
 
-            $subject_id rdfs:label $the_subject_label .
+            $subject_id $label_path $the_subject_label .
             FILTER ( lang($the_subject_label) = "en" ) .
 
-            $subject_id schema:description $the_subject_description .
+            $subject_id $description_path $the_subject_description .
             FILTER ( lang($the_subject_description) = "en" ) .
 
-            $object_id rdfs:label $the_object_label .
+            $object_id $label_path $the_object_label .
             FILTER ( lang($the_object_label) = "en" ) .
 
-            $object_id schema:description $the_object_description .
+            $object_id $description_path $the_object_description .
             FILTER ( lang($the_object_description) = "en" ) .
 
             $foo wikibase:directClaim $predicate_id .
@@ -89,7 +104,8 @@ def adjacent_entities():
             object_id="$object_id0",
             the_object_label="$the_object_label0",
             the_object_description="$the_object_description0",
-            foo="$foo0")
+            foo="$foo0",
+            **entity_template_values)
 
         other_where_block_content = " ".join([where_block_template.substitute(
             subject_id=f"$object_id{ctr-1}",
@@ -101,7 +117,8 @@ def adjacent_entities():
             object_id=f"$object_id{ctr}",
             the_object_label=f"$the_object_label{ctr}",
             the_object_description=f"$the_object_description{ctr}",
-            foo=f"$foo{ctr}") for ctr in range(1, depth)])
+            foo=f"$foo{ctr}",
+            **entity_template_values) for ctr in range(1, depth)])
 
         where_block = f"""WHERE {{
             VALUES ?subject_id0 {{ <http://www.wikidata.org/entity/{entity}> }}
@@ -122,11 +139,12 @@ def adjacent_entities():
     if not results:
         return jsonify(
             {
-                "adjacent_entities": {entity: {}},
+                "adjacent_entities": {entity: []},
                 "q_labels": {},
                 "q_descriptions": {},
                 "p_labels": {},
                 "p_descriptions": {},
+                "error": "The adjacent entities could not be retrieved from Wikidata.",
             }
         ), 200
 
@@ -187,6 +205,97 @@ def adjacent_entities():
     ), 200
 
 
+# Replaces the month and day of a date beyond its Wikidata precision (9 = year, 10 = month) with zeros
+# like in the Wikidata JSON, e.g., 1952-01-01T00:00:00Z with year precision becomes 1952-00-00T00:00:00Z.
+def mask_time_precision(value, precision):
+    sign = value[0] if value[0] in "+-" else ""
+    date, _, time = value[len(sign):].partition("T")
+    parts = date.split("-")
+    if len(parts) != 3:
+        return value
+    if precision <= 9:
+        parts[1] = "00"
+    if precision <= 10:
+        parts[2] = "00"
+    return f"{sign}{'-'.join(parts)}T{time}"
+
+
+@app.route("/adjacent_literals", methods=["GET"])
+def adjacent_literals():
+    entity = request.args.get("entity")
+
+    query = f"""
+    SELECT ?predicate_id ?property_type ?value ?precision ?the_predicate_label ?the_predicate_description
+    WHERE {{
+    VALUES ?subject_id {{ <http://www.wikidata.org/entity/{entity}> }}
+
+    ?subject_id ?predicate_id ?value .
+
+    ?property wikibase:directClaim ?predicate_id ;
+              wikibase:propertyType ?property_type .
+    FILTER ( ?property_type IN ( wikibase:Time, wikibase:GlobeCoordinate, wikibase:ExternalId ) ) .
+
+    # the truthy value of a date carries no precision, e.g., a year is returned as its first day
+    OPTIONAL {{
+        ?property wikibase:claim ?claim ;
+                  wikibase:statementValue ?statement_value .
+        ?subject_id ?claim ?statement .
+        ?statement ?statement_value ?value_node .
+        ?value_node wikibase:timeValue ?value ;
+                    wikibase:timePrecision ?precision .
+    }}
+
+    ?property rdfs:label ?the_predicate_label .
+    FILTER ( lang(?the_predicate_label) = "en" ) .
+
+    ?property schema:description ?the_predicate_description .
+    FILTER ( lang(?the_predicate_description) = "en" ) .
+    }}
+    """
+
+    results = query_wikidata(query)
+
+    if not results:
+        return jsonify(
+            {
+                "literals": [],
+                "p_labels": {},
+                "p_descriptions": {},
+                "error": "The adjacent literals could not be retrieved from Wikidata.",
+            }
+        ), 200
+
+    literals = []
+    clean_p_labels = dict()
+    clean_p_descriptions = dict()
+
+    for result in results["results"]["bindings"]:
+        predicate = result["predicate_id"]["value"].split("/")[-1]
+
+        value = result["value"]["value"]
+        if "precision" in result:
+            value = mask_time_precision(value, int(result["precision"]["value"]))
+
+        literals.append(
+            {
+                "predicate": predicate,
+                "type": result["property_type"]["value"].split("#")[-1],
+                "value": value,
+            }
+        )
+
+        clean_p_labels[predicate] = result["the_predicate_label"]["value"]
+        clean_p_descriptions[predicate] = result["the_predicate_description"]["value"]
+
+    return jsonify(
+        {
+            "literals": literals,
+            "p_labels": clean_p_labels,
+            "p_descriptions": clean_p_descriptions,
+        }
+    ), 200
+
+
 @app.route("/label_description", methods=["GET"])
 def label_description():
     entity = request.args.get("entity")
@@ -196,10 +305,10 @@ def label_description():
     WHERE {{     
     VALUES ?subject_id {{ <http://www.wikidata.org/entity/{entity}> }}       
 
-    $subject_id rdfs:label $the_subject_label .       
+    $subject_id {label_path} $the_subject_label .       
     FILTER ( lang($the_subject_label) = "en" ) .
     
-    $subject_id schema:description $the_subject_description .
+    $subject_id {description_path} $the_subject_description .
     FILTER ( lang($the_subject_description) = "en" ) .
     }}
     """
@@ -230,6 +339,98 @@ def label_description():
     ), 200
 
 
+@app.route("/labels_descriptions", methods=["GET"])
+def labels_descriptions():
+    entities = request.args.get("entities").split("|")
+    languages = request.args.get("languages").split("|")
+
+    entity_values = " ".join(
+        [f"<http://www.wikidata.org/entity/{entity}>" for entity in entities])
+    language_values = ", ".join([f'"{language}"' for language in languages])
+
+    query = f"""
+    SELECT ?subject_id ?the_subject_label ?the_subject_description
+    WHERE {{
+    VALUES ?subject_id {{ {entity_values} }}
+
+    {{
+        $subject_id {label_path} $the_subject_label .
+        FILTER ( lang($the_subject_label) IN ( {language_values} ) ) .
+    }}
+    UNION
+    {{
+        $subject_id {description_path} $the_subject_description .
+        FILTER ( lang($the_subject_description) IN ( {language_values} ) ) .
+    }}
+    }}
+    """
+
+    results = query_wikidata(query)
+
+    if not results:
+        return jsonify(
+            {
+                "labels": {},
+                "descriptions": {},
+                "error": "The labels and descriptions could not be retrieved from Wikidata.",
+            }
+        ), 200
+
+    clean_labels = dict()
+    clean_descriptions = dict()
+
+    for result in results["results"]["bindings"]:
+        subject = result["subject_id"]["value"].split("/")[-1]
+
+        if "the_subject_label" in result:
+            label = result["the_subject_label"]
+            clean_labels.setdefault(subject, {})[label["xml:lang"]] = label["value"]
+
+        if "the_subject_description" in result:
+            description = result["the_subject_description"]
+            clean_descriptions.setdefault(subject, {})[description["xml:lang"]] = description["value"]
+
+    return jsonify(
+        {
+            "labels": clean_labels,
+            "descriptions": clean_descriptions,
+        }
+    ), 200
+
+
+@app.route("/redirect", methods=["GET"])
+def redirect():
+    entity = request.args.get("entity")
+
+    query = f"""
+    SELECT ?target_id
+    WHERE {{
+    <http://www.wikidata.org/entity/{entity}> owl:sameAs ?target_id .
+    }}
+    """
+
+    results = query_wikidata(query)
+
+    if not results:
+        return jsonify(
+            {
+                "target": "",
+                "error": "The redirect could not be retrieved from Wikidata.",
+            }
+        ), 200
+
+    result_bindings = results["results"]["bindings"]
+
+    target = result_bindings[0]["target_id"]["value"].split(
+        "/")[-1] if result_bindings else ""
+
+    return jsonify(
+        {
+            "target": target,
+        }
+    ), 200
+
+
 @app.route("/id", methods=["GET"])
 def id():
     label = request.args.get("label")